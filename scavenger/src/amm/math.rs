@@ -1,9 +1,14 @@
-use uint::construct_uint;
-
 // 定义 U256 以支持高精度计算 (避免 u128 溢出)
-construct_uint! {
-    pub struct U256(4);
+// construct_uint! 宏展开的代码会触发 clippy 的风格检查
+#[allow(clippy::manual_div_ceil, clippy::assign_op_pattern)]
+mod u256 {
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U256(4);
+    }
 }
+pub use u256::U256;
 
 // Constant Product AMM Calculator
 // Formula: (x + dx) * (y - dy) = x * y = k
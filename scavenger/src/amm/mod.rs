@@ -1,4 +1,40 @@
+use serde::{Deserialize, Serialize};
+
 pub mod raydium_v4;
 pub mod math;
 pub mod orca_whirlpool;
 pub mod serum;
+
+/// 池子生命周期状态 (由链上账户字段推导)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoolLifecycle {
+    /// 已创建但尚未到开放交易时间
+    PendingOpen { open_time: u64 },
+    Active,
+    /// 被程序禁用或处于不允许 Swap 的状态
+    Disabled,
+    /// 流动性已被抽空
+    Drained,
+}
+
+impl PoolLifecycle {
+    /// 到达开放时间后 PendingOpen 自动视为 Active
+    pub fn at(self, now: u64) -> Self {
+        match self {
+            PoolLifecycle::PendingOpen { open_time } if now >= open_time => PoolLifecycle::Active,
+            s => s,
+        }
+    }
+
+    pub fn is_tradable(self, now: u64) -> bool {
+        self.at(now) == PoolLifecycle::Active
+    }
+}
+
+/// 当前 Unix 时间戳 (秒)
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,5 +1,8 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use crate::amm::raydium_v4::read_pubkey;
+use crate::amm::PoolLifecycle;
 
 /// Orca Whirlpool Account Layout
 /// Source: https://github.com/orca-so/whirlpools/blob/main/programs/whirlpool/src/state/whirlpool.rs
@@ -41,7 +44,59 @@ pub struct WhirlpoolPrice {
     pub liquidity: u128,
 }
 
+/// Whirlpool 的静态账户 (池子生命周期内不变)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhirlpoolKeys {
+    pub id: Pubkey,
+    pub whirlpools_config: Pubkey,
+    pub token_mint_a: Pubkey,
+    pub token_mint_b: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_rate: u16,
+}
+
 impl Whirlpool {
+    // 含 8 字节 discriminator 的字段偏移
+    pub const LEN: usize = 653;
+    pub const CONFIG_OFFSET: usize = 8;
+    pub const TICK_SPACING_OFFSET: usize = 41;
+    pub const FEE_RATE_OFFSET: usize = 45;
+    pub const LIQUIDITY_OFFSET: usize = 49;
+    pub const TOKEN_MINT_A_OFFSET: usize = 101;
+    pub const TOKEN_VAULT_A_OFFSET: usize = 133;
+    pub const TOKEN_MINT_B_OFFSET: usize = 181;
+    pub const TOKEN_VAULT_B_OFFSET: usize = 213;
+
+    /// Whirlpool 没有禁用开关，活跃流动性为 0 即视为已抽空
+    pub fn lifecycle(data: &[u8]) -> Option<PoolLifecycle> {
+        let liquidity = u128::from_le_bytes(data.get(Self::LIQUIDITY_OFFSET..Self::LIQUIDITY_OFFSET + 16)?.try_into().ok()?);
+        if liquidity == 0 {
+            Some(PoolLifecycle::Drained)
+        } else {
+            Some(PoolLifecycle::Active)
+        }
+    }
+
+    /// 解析池子的静态 Key
+    pub fn decode_keys(pool_id: &Pubkey, data: &[u8]) -> Option<WhirlpoolKeys> {
+        let read_u16 = |offset: usize| -> Option<u16> {
+            Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+        };
+
+        Some(WhirlpoolKeys {
+            id: *pool_id,
+            whirlpools_config: read_pubkey(data, Self::CONFIG_OFFSET)?,
+            token_mint_a: read_pubkey(data, Self::TOKEN_MINT_A_OFFSET)?,
+            token_mint_b: read_pubkey(data, Self::TOKEN_MINT_B_OFFSET)?,
+            token_vault_a: read_pubkey(data, Self::TOKEN_VAULT_A_OFFSET)?,
+            token_vault_b: read_pubkey(data, Self::TOKEN_VAULT_B_OFFSET)?,
+            tick_spacing: read_u16(Self::TICK_SPACING_OFFSET)?,
+            fee_rate: read_u16(Self::FEE_RATE_OFFSET)?,
+        })
+    }
+
     /// Manual parsing from data slice to avoid defining the full complex struct
     /// Whirlpool layout:
     /// discriminator: 8 bytes
@@ -92,8 +147,27 @@ pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128) -> f64 {
     p * p
 }
 
+/// 按两边代币精度调整价格，得到 UI 单位下的 Token B / Token A
+pub fn adjust_price_for_decimals(price: f64, decimals_a: u8, decimals_b: u8) -> f64 {
+    price * 10f64.powi(decimals_a as i32 - decimals_b as i32)
+}
+
 /// Convert tick index to price
 /// Price = 1.0001 ^ tick
 pub fn tick_to_price(tick: i32) -> f64 {
     1.0001f64.powi(tick)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_branches() {
+        let mut data = vec![0u8; Whirlpool::LEN];
+        assert_eq!(Whirlpool::lifecycle(&data), Some(PoolLifecycle::Drained));
+        data[Whirlpool::LIQUIDITY_OFFSET..Whirlpool::LIQUIDITY_OFFSET + 16].copy_from_slice(&1u128.to_le_bytes());
+        assert_eq!(Whirlpool::lifecycle(&data), Some(PoolLifecycle::Active));
+        assert_eq!(Whirlpool::lifecycle(&data[..Whirlpool::LIQUIDITY_OFFSET + 8]), None);
+    }
+}
@@ -1,5 +1,6 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
+use crate::amm::PoolLifecycle;
 
 // Raydium AMM V4 State Layout (752 bytes)
 // 参考: https://github.com/raydium-io/raydium-amm/blob/master/program/src/state.rs
@@ -71,4 +72,91 @@ pub struct AmmState {
 
 impl AmmState {
     pub const LEN: usize = 752;
+
+    // 链上真实布局中的字段偏移 (与上面的 Borsh 结构体无关，按官方 AmmInfo 计算)
+    // 静态 Key 的解析和 memcmp 过滤都应使用这些偏移
+    pub const STATUS_OFFSET: usize = 0;
+    pub const NONCE_OFFSET: usize = 8;
+    pub const COIN_DECIMALS_OFFSET: usize = 32;
+    pub const PC_DECIMALS_OFFSET: usize = 40;
+    pub const POOL_OPEN_TIME_OFFSET: usize = 224;
+    pub const COIN_VAULT_OFFSET: usize = 336;
+    pub const PC_VAULT_OFFSET: usize = 368;
+    pub const COIN_MINT_OFFSET: usize = 400;
+    pub const PC_MINT_OFFSET: usize = 432;
+    pub const LP_MINT_OFFSET: usize = 464;
+    pub const OPEN_ORDERS_OFFSET: usize = 496;
+    pub const MARKET_OFFSET: usize = 528;
+    pub const MARKET_PROGRAM_OFFSET: usize = 560;
+    pub const TARGET_ORDERS_OFFSET: usize = 592;
+    pub const LP_AMOUNT_OFFSET: usize = 720;
+
+    // AmmStatus (raydium-amm/program/src/state.rs)
+    pub const STATUS_INITIALIZED: u64 = 1;
+    pub const STATUS_SWAP_ONLY: u64 = 6;
+    pub const STATUS_WAITING_TRADE: u64 = 7;
+
+    /// 根据 status / pool_open_time / lp_amount 推导池子生命周期
+    /// Disabled / Drained 可能恢复 (重新启用 / 重新注入流动性)，由 Inventory 定期重查
+    pub fn lifecycle(data: &[u8], now: u64) -> Option<PoolLifecycle> {
+        let status = read_u64(data, Self::STATUS_OFFSET)?;
+        let open_time = read_u64(data, Self::POOL_OPEN_TIME_OFFSET)?;
+        let lp_amount = read_u64(data, Self::LP_AMOUNT_OFFSET)?;
+
+        let state = match status {
+            Self::STATUS_INITIALIZED | Self::STATUS_SWAP_ONLY | Self::STATUS_WAITING_TRADE => {
+                if lp_amount == 0 {
+                    PoolLifecycle::Drained
+                } else if open_time > now {
+                    PoolLifecycle::PendingOpen { open_time }
+                } else {
+                    PoolLifecycle::Active
+                }
+            }
+            // Uninitialized / Disabled / WithdrawOnly / LiquidityOnly / OrderBookOnly 都不能 Swap
+            _ => PoolLifecycle::Disabled,
+        };
+        Some(state)
+    }
+}
+
+/// 按偏移读取 u64 (小端)
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes: [u8; 8] = data.get(offset..offset + 8)?.try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
+
+/// 按偏移读取 Pubkey
+pub fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    let bytes: [u8; 32] = data.get(offset..offset + 32)?.try_into().ok()?;
+    Some(Pubkey::new_from_array(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amm_data(status: u64, open_time: u64, lp_amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; AmmState::LEN];
+        data[AmmState::STATUS_OFFSET..AmmState::STATUS_OFFSET + 8].copy_from_slice(&status.to_le_bytes());
+        data[AmmState::POOL_OPEN_TIME_OFFSET..AmmState::POOL_OPEN_TIME_OFFSET + 8].copy_from_slice(&open_time.to_le_bytes());
+        data[AmmState::LP_AMOUNT_OFFSET..AmmState::LP_AMOUNT_OFFSET + 8].copy_from_slice(&lp_amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_lifecycle_branches() {
+        let now = 1_000;
+        for status in [AmmState::STATUS_INITIALIZED, AmmState::STATUS_SWAP_ONLY, AmmState::STATUS_WAITING_TRADE] {
+            assert_eq!(AmmState::lifecycle(&amm_data(status, 900, 5), now), Some(PoolLifecycle::Active));
+            assert_eq!(AmmState::lifecycle(&amm_data(status, 2_000, 5), now), Some(PoolLifecycle::PendingOpen { open_time: 2_000 }));
+            // 没有流动性时不论开放时间都视为已抽空
+            assert_eq!(AmmState::lifecycle(&amm_data(status, 2_000, 0), now), Some(PoolLifecycle::Drained));
+        }
+        // Uninitialized(0) / Disabled(2) / WithdrawOnly(3) / LiquidityOnly(4) / OrderBookOnly(5)
+        for status in [0, 2, 3, 4, 5] {
+            assert_eq!(AmmState::lifecycle(&amm_data(status, 900, 5), now), Some(PoolLifecycle::Disabled));
+        }
+        assert_eq!(AmmState::lifecycle(&[0u8; 16], now), None);
+    }
 }
@@ -0,0 +1,380 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use log::{info, warn};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::sync::broadcast;
+use crate::config::BusConfig;
+use crate::scout::events::{AccountUpdate, SlotUpdate};
+
+/// 总线事件类型，策略按类型订阅
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    AccountUpdate,
+    NewPool,
+    Swap,
+    Slot,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 4] = [EventKind::AccountUpdate, EventKind::NewPool, EventKind::Swap, EventKind::Slot];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dex {
+    Raydium,
+    Orca,
+}
+
+/// 侦察层发现的新池
+#[derive(Debug, Clone)]
+pub struct NewPool {
+    pub dex: Dex,
+    pub signature: String,
+    pub pool: Pubkey,
+    pub token_a: Pubkey,
+    pub token_b: Pubkey,
+    /// 开放交易时间 (Unix 秒)，未知时为 None
+    pub open_time: Option<u64>,
+}
+
+/// 链上成交 (多跳交易每一跳一条)
+#[derive(Debug, Clone)]
+pub struct SwapEvent {
+    pub dex: Dex,
+    pub signature: Signature,
+    pub slot: u64,
+    pub pool: Pubkey,
+    /// 发起交换的钱包 (指令中的 owner / token_authority)
+    pub signer: Pubkey,
+    /// true 表示 token_a -> token_b (Raydium 的 token_a 为 Coin)
+    pub a_to_b: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// true 表示金额来自链上日志 (ray_log / Traded)；false 表示只有指令参数 (指定金额 + 滑点阈值)
+    pub exact: bool,
+}
+
+/// 侦察层发布给策略层的事件
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    AccountUpdate(AccountUpdate),
+    NewPool(NewPool),
+    Swap(SwapEvent),
+    Slot(SlotUpdate),
+}
+
+impl BusEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            BusEvent::AccountUpdate(_) => EventKind::AccountUpdate,
+            BusEvent::NewPool(_) => EventKind::NewPool,
+            BusEvent::Swap(_) => EventKind::Swap,
+            BusEvent::Slot(_) => EventKind::Slot,
+        }
+    }
+}
+
+struct SubscriberMetrics {
+    name: String,
+    received: AtomicU64,
+    lagged: AtomicU64,
+}
+
+/// 单个事件类型的广播通道
+struct Channel {
+    tx: broadcast::Sender<Arc<BusEvent>>,
+    capacity: usize,
+    published: AtomicU64,
+    peak_depth: AtomicUsize,
+}
+
+impl Channel {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            tx: broadcast::channel(capacity).0,
+            capacity,
+            published: AtomicU64::new(0),
+            peak_depth: AtomicUsize::new(0),
+        }
+    }
+}
+
+struct BusInner {
+    /// 按 EventKind 下标索引，每种事件独立排队，高频事件不会挤掉低频事件
+    channels: [Channel; EventKind::ALL.len()],
+    /// 所有订阅方关心的事件类型并集 (位掩码)，没人关心的事件直接丢弃
+    interests: AtomicU8,
+    subscribers: Mutex<Vec<Arc<SubscriberMetrics>>>,
+}
+
+/// 侦察层 -> 策略层的有界广播总线
+/// 每种事件一个独立的广播通道，队列满时最慢的订阅方会丢失该类型最旧的事件 (记为 lagged)，发布方永不阻塞
+#[derive(Clone)]
+pub struct EventBus {
+    inner: Arc<BusInner>,
+}
+
+/// 单个订阅方的积压统计
+#[derive(Debug, Clone)]
+pub struct SubscriberStats {
+    pub name: String,
+    pub received: u64,
+    pub lagged: u64,
+}
+
+/// 单个事件类型的通道统计
+#[derive(Debug, Clone)]
+pub struct ChannelStats {
+    pub kind: EventKind,
+    pub capacity: usize,
+    /// 当前最慢订阅方尚未消费的事件数
+    pub depth: usize,
+    pub peak_depth: usize,
+    pub published: u64,
+}
+
+/// 总线统计快照
+#[derive(Debug, Clone)]
+pub struct BusStats {
+    pub channels: Vec<ChannelStats>,
+    pub subscribers: Vec<SubscriberStats>,
+}
+
+impl EventBus {
+    pub fn new(config: &BusConfig) -> Self {
+        let capacity = |kind: EventKind| match kind {
+            EventKind::NewPool => config.new_pool_capacity,
+            _ => config.capacity,
+        };
+        Self {
+            inner: Arc::new(BusInner {
+                channels: EventKind::ALL.map(|k| Channel::new(capacity(k))),
+                interests: AtomicU8::new(0),
+                subscribers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// 订阅指定类型的事件；`name` 只用于统计和日志
+    pub fn subscribe(&self, name: &str, kinds: &[EventKind]) -> BusReceiver {
+        let mask = kinds.iter().fold(0, |m, k| m | k.bit());
+        self.inner.interests.fetch_or(mask, Ordering::Relaxed);
+        let metrics = Arc::new(SubscriberMetrics {
+            name: name.to_string(),
+            received: AtomicU64::new(0),
+            lagged: AtomicU64::new(0),
+        });
+        self.inner.subscribers.lock().unwrap().push(metrics.clone());
+        let rxs = EventKind::ALL
+            .iter()
+            .filter(|k| mask & k.bit() != 0)
+            .map(|k| self.inner.channels[*k as usize].tx.subscribe())
+            .collect();
+        BusReceiver { rxs, next: 0, metrics }
+    }
+
+    /// 是否有订阅方关心该类型，侦察层据此决定要不要采集对应数据
+    pub fn wants(&self, kind: EventKind) -> bool {
+        self.inner.interests.load(Ordering::Relaxed) & kind.bit() != 0
+    }
+
+    /// 发布事件，没有订阅方关心时返回 false
+    pub fn publish(&self, event: BusEvent) -> bool {
+        let kind = event.kind();
+        if !self.wants(kind) {
+            return false;
+        }
+        let channel = &self.inner.channels[kind as usize];
+        if channel.tx.send(Arc::new(event)).is_err() {
+            return false;
+        }
+        channel.published.fetch_add(1, Ordering::Relaxed);
+        channel.peak_depth.fetch_max(channel.tx.len(), Ordering::Relaxed);
+        true
+    }
+
+    pub fn stats(&self) -> BusStats {
+        BusStats {
+            channels: EventKind::ALL
+                .iter()
+                .map(|k| {
+                    let channel = &self.inner.channels[*k as usize];
+                    ChannelStats {
+                        kind: *k,
+                        capacity: channel.capacity,
+                        depth: channel.tx.len(),
+                        peak_depth: channel.peak_depth.load(Ordering::Relaxed),
+                        published: channel.published.load(Ordering::Relaxed),
+                    }
+                })
+                .collect(),
+            subscribers: self
+                .inner
+                .subscribers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|m| SubscriberStats {
+                    name: m.name.clone(),
+                    received: m.received.load(Ordering::Relaxed),
+                    lagged: m.lagged.load(Ordering::Relaxed),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn log_stats(&self) {
+        let stats = self.stats();
+        for channel in stats.channels.iter().filter(|c| self.wants(c.kind)) {
+            info!("🚌 [Bus] {:?} | 积压 {} / {} (峰值 {}) | 已发布 {}",
+                channel.kind, channel.depth, channel.capacity, channel.peak_depth, channel.published);
+        }
+        for sub in &stats.subscribers {
+            info!("🚌 [Bus] 订阅方 {} | 已接收 {} | 丢失 {}", sub.name, sub.received, sub.lagged);
+        }
+    }
+}
+
+/// 按类型过滤的总线订阅端 (每个关心的类型一个接收端)
+pub struct BusReceiver {
+    rxs: Vec<broadcast::Receiver<Arc<BusEvent>>>,
+    // 轮询起点，避免高频类型饿死低频类型
+    next: usize,
+    metrics: Arc<SubscriberMetrics>,
+}
+
+impl BusReceiver {
+    /// 等待下一条关心的事件，总线关闭时返回 None
+    /// 消费太慢时跳过被覆盖的事件并计入 lagged，而不是让发布方等待
+    pub async fn recv(&mut self) -> Option<Arc<BusEvent>> {
+        loop {
+            let n = self.rxs.len();
+            let mut closed = 0;
+            for i in 0..n {
+                let idx = (self.next + i) % n;
+                match self.rxs[idx].try_recv() {
+                    Ok(event) => {
+                        self.next = (idx + 1) % n;
+                        self.metrics.received.fetch_add(1, Ordering::Relaxed);
+                        return Some(event);
+                    }
+                    Err(broadcast::error::TryRecvError::Lagged(k)) => self.lagged(k),
+                    Err(broadcast::error::TryRecvError::Empty) => {}
+                    Err(broadcast::error::TryRecvError::Closed) => closed += 1,
+                }
+            }
+            if closed == n {
+                return None;
+            }
+
+            // 所有通道都空时等待任意一个
+            let waits = self.rxs.iter_mut().map(|rx| Box::pin(rx.recv()));
+            let (result, idx, rest) = futures::future::select_all(waits).await;
+            drop(rest);
+            match result {
+                Ok(event) => {
+                    self.next = (idx + 1) % n;
+                    self.metrics.received.fetch_add(1, Ordering::Relaxed);
+                    return Some(event);
+                }
+                Err(broadcast::error::RecvError::Lagged(k)) => self.lagged(k),
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    fn lagged(&self, n: u64) {
+        self.metrics.lagged.fetch_add(n, Ordering::Relaxed);
+        warn!("⚠️ [Bus] 订阅方 {} 消费过慢，丢失 {} 条事件", self.metrics.name, n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scout::events::{EventSource, SlotStatus};
+
+    fn slot(slot: u64) -> BusEvent {
+        BusEvent::Slot(SlotUpdate { slot, parent: None, status: SlotStatus::Processed, source: EventSource::WebSocket, received_at: std::time::Instant::now() })
+    }
+
+    fn bus(capacity: usize, new_pool_capacity: usize) -> EventBus {
+        EventBus::new(&BusConfig { capacity, new_pool_capacity })
+    }
+
+    fn new_pool() -> BusEvent {
+        BusEvent::NewPool(NewPool {
+            dex: Dex::Raydium,
+            signature: String::new(),
+            pool: Pubkey::new_unique(),
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            open_time: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_filter_and_lag_metrics() {
+        let bus = bus(2, 2);
+        assert!(!bus.publish(slot(1)), "没有订阅方关心的事件不应进入总线");
+
+        let mut slots = bus.subscribe("slots", &[EventKind::Slot]);
+        let _pools = bus.subscribe("pools", &[EventKind::NewPool]);
+        assert!(bus.wants(EventKind::Slot) && bus.wants(EventKind::NewPool));
+        assert!(!bus.wants(EventKind::Swap));
+
+        for s in 2..=5 {
+            assert!(bus.publish(slot(s)));
+        }
+        // 容量为 2，最旧的两条被覆盖
+        match slots.recv().await.as_deref() {
+            Some(BusEvent::Slot(u)) => assert_eq!(u.slot, 4),
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        let stats = bus.stats();
+        let channel = stats.channels.iter().find(|c| c.kind == EventKind::Slot).unwrap();
+        assert_eq!((channel.peak_depth, channel.published), (2, 4));
+        let sub = stats.subscribers.iter().find(|s| s.name == "slots").unwrap();
+        assert_eq!((sub.received, sub.lagged), (1, 2));
+    }
+
+    #[tokio::test]
+    async fn test_high_rate_kind_does_not_evict_new_pools() {
+        let bus = bus(2, 4);
+        let mut pools = bus.subscribe("pools", &[EventKind::NewPool]);
+        let mut both = bus.subscribe("both", &[EventKind::NewPool, EventKind::Slot]);
+
+        assert!(bus.publish(new_pool()));
+        for s in 0..100 {
+            assert!(bus.publish(slot(s)));
+        }
+        assert!(bus.publish(new_pool()));
+
+        // Slot 的积压不影响 NewPool 通道
+        for _ in 0..2 {
+            assert!(matches!(pools.recv().await.as_deref(), Some(BusEvent::NewPool(_))));
+        }
+        let mut new_pools = 0;
+        let mut slots = Vec::new();
+        for _ in 0..4 {
+            match both.recv().await.as_deref() {
+                Some(BusEvent::NewPool(_)) => new_pools += 1,
+                Some(BusEvent::Slot(u)) => slots.push(u.slot),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(new_pools, 2);
+        assert_eq!(slots, vec![98, 99]);
+
+        let stats = bus.stats();
+        let lagged = |name: &str| stats.subscribers.iter().find(|s| s.name == name).unwrap().lagged;
+        assert_eq!((lagged("pools"), lagged("both")), (0, 98));
+    }
+}
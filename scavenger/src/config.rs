@@ -1,5 +1,11 @@
 use config::{Config, ConfigError, File};
 use serde::Deserialize;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use crate::core::rpc_pool::RpcRole;
+use crate::scout::discovery::DiscoverySource;
+use crate::scout::events::StreamCommitment;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct NetworkConfig {
@@ -28,12 +34,511 @@ pub struct LogConfig {
     pub level: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct InventoryConfig {
+    /// Inventory 快照路径
+    pub snapshot_path: String,
+    /// 快照写入间隔 (秒)
+    pub snapshot_interval_secs: u64,
+    /// 本地 Token List 文件 (可选，提供 Symbol / Name)
+    pub token_list_path: Option<String>,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            snapshot_path: "inventory.snapshot".to_string(),
+            snapshot_interval_secs: 300,
+            token_list_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScoutConfig {
+    /// 账户订阅预算 (Arb 模式最多同时监听的池子数)
+    pub max_subscriptions: usize,
+    /// 监控名单重新排序的间隔 (秒)
+    pub rerank_interval_secs: u64,
+    /// 排序权重: TVL / 更新频率 / 历史价差
+    pub tvl_weight: f64,
+    pub activity_weight: f64,
+    pub spread_weight: f64,
+    /// 重查 Disabled / Drained 池子是否恢复的间隔 (秒)
+    pub lifecycle_recheck_secs: u64,
+    /// 预留给名单外套利对的订阅数 (按池子计)，每轮排序轮换一批，让它们有机会积累活跃度和价差数据
+    pub exploration_slots: usize,
+    /// 扫描到的新池最多占用的订阅数 (从 max_subscriptions 中划出，满了替换最早的新池)
+    pub new_pool_slots: usize,
+    /// 新池订阅的保留时间 (秒)，到期后在下一轮排序时退订，除非已进入监控名单
+    pub new_pool_ttl_secs: u64,
+}
+
+impl Default for ScoutConfig {
+    fn default() -> Self {
+        Self {
+            max_subscriptions: 50,
+            rerank_interval_secs: 60,
+            tvl_weight: 1.0,
+            activity_weight: 1.0,
+            spread_weight: 1.0,
+            lifecycle_recheck_secs: 300,
+            exploration_slots: 4,
+            new_pool_slots: 8,
+            new_pool_ttl_secs: 600,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    /// 池子发现数据源，按顺序合并: "onchain" (getProgramAccounts) / "api" (REST)
+    pub sources: Vec<String>,
+    /// 只发现与这些 Quote Token 配对的池子
+    pub quote_mints: Vec<String>,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            sources: vec!["onchain".to_string(), "api".to_string()],
+            quote_mints: vec![
+                "So11111111111111111111111111111111111111112".to_string(), // WSOL
+                "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string(), // USDC
+            ],
+        }
+    }
+}
+
+impl DiscoveryConfig {
+    pub fn sources(&self) -> Result<Vec<DiscoverySource>, String> {
+        self.sources.iter().map(|s| s.parse()).collect()
+    }
+
+    pub fn quote_mints(&self) -> Result<Vec<Pubkey>, String> {
+        self.quote_mints
+            .iter()
+            .map(|m| Pubkey::from_str(m).map_err(|e| format!("invalid quote mint {}: {}", m, e)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct PoolSourceConfig {
+    /// REST API 池子列表的缓存目录
+    pub cache_dir: String,
+    /// 缓存有效期，过期后发起条件请求 (ETag / If-Modified-Since)
+    pub ttl_secs: u64,
+}
+
+impl Default for PoolSourceConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: "cache".to_string(),
+            ttl_secs: 1800,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct WebSocketConfig {
+    /// 额外的 WebSocket 节点，为空时只使用 network.ws_url
+    pub urls: Vec<String>,
+    /// 每个节点建立的连接数
+    pub connections_per_url: usize,
+    /// 每条连接的订阅上限 (含 Slot 心跳)
+    pub max_subscriptions_per_connection: usize,
+    /// 超过该时间没有任何推送 (包括 Slot 心跳) 即判定连接失效
+    pub heartbeat_timeout_secs: u64,
+    /// 重连退避的初始 / 最大延迟
+    pub reconnect_base_delay_ms: u64,
+    pub reconnect_max_delay_ms: u64,
+    /// 单个订阅中断时按退避单独重试，连续失败超过该次数才重建整条连接
+    pub max_subscription_retries: u32,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            connections_per_url: 1,
+            max_subscriptions_per_connection: 100,
+            heartbeat_timeout_secs: 10,
+            reconnect_base_delay_ms: 500,
+            reconnect_max_delay_ms: 30_000,
+            max_subscription_retries: 3,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct GeyserConfig {
+    /// 启用后池子账户更新改走 Yellowstone Geyser gRPC
+    pub enabled: bool,
+    /// Geyser gRPC 地址 (例如 "https://xxx.rpcpool.com:443")
+    pub endpoint: String,
+    /// 鉴权 Token (x-token)
+    pub x_token: Option<String>,
+    /// 超过该时间没有任何推送 (包括服务端 Ping) 即判定流失效
+    pub heartbeat_timeout_secs: u64,
+    /// 重连退避的初始 / 最大延迟
+    pub reconnect_base_delay_ms: u64,
+    pub reconnect_max_delay_ms: u64,
+}
+
+impl Default for GeyserConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            x_token: None,
+            heartbeat_timeout_secs: 30,
+            reconnect_base_delay_ms: 500,
+            reconnect_max_delay_ms: 30_000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BusConfig {
+    /// 每种高频事件 (账户更新 / 成交 / Slot) 的通道容量，最慢的策略积压超过该值时丢弃该类型最旧的事件
+    pub capacity: usize,
+    /// 新池事件的独立通道容量 (低频，不受高频事件积压影响)
+    pub new_pool_capacity: usize,
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self { capacity: 4096, new_pool_capacity: 1024 }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackfillConfig {
+    /// 启动时在后台回填停机期间创建的池子
+    pub on_startup: bool,
+    /// 回填进度 (每个 DEX 最新处理到的签名 / Slot)
+    pub checkpoint_path: String,
+    /// 扫描签名的地址: 默认是只在建池时出现的账户 (Raydium 建池手续费账户 / Orca WhirlpoolsConfig)
+    /// 也可以填程序地址，但程序本身的交易量太大，很难回溯多久
+    pub raydium_address: String,
+    pub orca_address: String,
+    /// 每个地址最多回溯的签名数 (没有 Checkpoint 时即回溯深度)
+    pub max_signatures: usize,
+    /// 同时拉取的交易数
+    pub fetch_concurrency: usize,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            on_startup: false,
+            checkpoint_path: "backfill.checkpoint.json".to_string(),
+            raydium_address: "7YttLkHDoNj9wyDur5pM1ejNaAvT9X4eqaYcHQqtj2G5".to_string(),
+            orca_address: "2LecshUwdy9xi7meFgHtFJQNSKk4KdTrcpvaB56dP2NQ".to_string(),
+            max_signatures: 20_000,
+            fetch_concurrency: 8,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct DedupConfig {
+    /// 去重记录保留时间 (秒)
+    pub ttl_secs: u64,
+    /// 早于最新 Slot 这么多的事件视为过期，直接丢弃
+    pub max_slot_age: u64,
+    /// 去重记录上限，超出时淘汰最旧的记录
+    pub max_entries: usize,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 30,
+            max_slot_age: 150,
+            max_entries: 200_000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LeaderConfig {
+    /// 运行 Jito-Solana 的验证者 Identity，只有这些 Leader 会处理 Bundle
+    pub jito_validators: Vec<String>,
+    /// 向前查找 Jito Leader 的最大 Slot 数
+    pub lookahead_slots: u64,
+    /// Jito Leader 在这么多 Slot 内出块时立即发送
+    pub send_ahead_slots: u64,
+    /// Jito Leader 在这么多 Slot 内出块时等待后再发送，更远则放弃
+    pub max_hold_slots: u64,
+}
+
+impl Default for LeaderConfig {
+    fn default() -> Self {
+        Self {
+            jito_validators: Vec::new(),
+            lookahead_slots: 64,
+            send_ahead_slots: 2,
+            max_hold_slots: 8,
+        }
+    }
+}
+
+impl LeaderConfig {
+    pub fn jito_validators(&self) -> Result<Vec<Pubkey>, String> {
+        self.jito_validators
+            .iter()
+            .map(|v| Pubkey::from_str(v).map_err(|e| format!("invalid jito validator {}: {}", v, e)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct BlockhashConfig {
+    /// 后台刷新 Blockhash 的间隔 (毫秒)
+    pub refresh_interval_ms: u64,
+    /// 剩余有效区块数低于该值时视为即将过期，需要换新的 Blockhash 重建交易
+    pub expiry_margin_blocks: u64,
+    /// 超过该时间没有刷新成功时缓存失效，改为直接请求 RPC (毫秒)
+    pub max_staleness_ms: u64,
+}
+
+impl Default for BlockhashConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval_ms: 400,
+            expiry_margin_blocks: 20,
+            max_staleness_ms: 5000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RpcEndpointConfig {
+    pub url: String,
+    /// 承担的请求类型: "read" / "send" / "historical"
+    pub roles: Vec<String>,
+    /// 路由权重，与健康评分相乘
+    pub weight: f64,
+    /// 该节点单独的限速 (不填时使用 rpc.rate_limit)
+    pub rate_limit: Option<RateLimitConfig>,
+}
+
+impl Default for RpcEndpointConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            roles: vec!["read".to_string(), "send".to_string(), "historical".to_string()],
+            weight: 1.0,
+            rate_limit: None,
+        }
+    }
+}
+
+impl RpcEndpointConfig {
+    pub fn roles(&self) -> Result<Vec<RpcRole>, String> {
+        self.roles.iter().map(|r| r.parse()).collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct StreamCommitmentConfig {
+    /// 池子账户推送的确认级别: processed / confirmed / finalized
+    pub accounts: String,
+    /// 程序日志推送 (新池 / 成交扫描) 的确认级别
+    pub logs: String,
+    /// 拉取完整交易的确认级别 (confirmed / finalized)
+    pub transactions: String,
+    /// 账户按 processed 推送时跟踪分叉: 未确认的状态按 Slot 保留，分叉被放弃时回滚到已确认状态
+    pub track_forks: bool,
+    /// WebSocket 模式下查询 confirmed Slot 的间隔 (毫秒，Geyser 直接推送 Slot 确认)
+    pub confirmed_poll_ms: u64,
+    /// 未确认数据最多保留的 Slot 数
+    pub max_pending_slots: u64,
+}
+
+impl Default for StreamCommitmentConfig {
+    fn default() -> Self {
+        Self {
+            accounts: "processed".to_string(),
+            logs: "processed".to_string(),
+            transactions: "confirmed".to_string(),
+            track_forks: true,
+            confirmed_poll_ms: 400,
+            max_pending_slots: 150,
+        }
+    }
+}
+
+fn parse_commitment(stream: &str, level: &str) -> Result<CommitmentConfig, String> {
+    match level {
+        "processed" => Ok(CommitmentConfig::processed()),
+        "confirmed" => Ok(CommitmentConfig::confirmed()),
+        "finalized" => Ok(CommitmentConfig::finalized()),
+        other => Err(format!("invalid commitment for {}: {}", stream, other)),
+    }
+}
+
+impl StreamCommitmentConfig {
+    pub fn resolve(&self) -> Result<StreamCommitment, String> {
+        let transactions = parse_commitment("transactions", &self.transactions)?;
+        if !transactions.is_at_least_confirmed() {
+            return Err("getTransaction requires confirmed or finalized commitment".to_string());
+        }
+        Ok(StreamCommitment {
+            accounts: parse_commitment("accounts", &self.accounts)?,
+            logs: parse_commitment("logs", &self.logs)?,
+            transactions,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct LatencyConfig {
+    /// 打印各阶段延迟分布的间隔 (秒)
+    pub report_interval_secs: u64,
+    /// 端到端超过该耗时的机会打印逐阶段明细 (毫秒)
+    pub slow_opportunity_ms: u64,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self {
+            report_interval_secs: 60,
+            slow_opportunity_ms: 250,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct AccountLoaderConfig {
+    /// 合并零散账户请求的窗口 (毫秒)
+    pub batch_window_ms: u64,
+    /// 单次 getMultipleAccounts 的账户数 (RPC 上限 100)
+    pub max_batch_size: usize,
+}
+
+impl Default for AccountLoaderConfig {
+    fn default() -> Self {
+        Self {
+            batch_window_ms: 2,
+            max_batch_size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// 每个节点各类方法的请求速率 (每秒，<= 0 表示不限速)
+    pub send_rps: f64,
+    pub heavy_rps: f64,
+    pub light_rps: f64,
+    /// 令牌桶容量 = 速率 × burst_secs
+    pub burst_secs: f64,
+    /// 热路径读取 / 后台任务不能动用的令牌比例，留给更高优先级
+    pub hot_path_reserve: f64,
+    pub background_reserve: f64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            send_rps: 5.0,
+            heavy_rps: 2.0,
+            light_rps: 10.0,
+            burst_secs: 1.0,
+            hot_path_reserve: 0.2,
+            background_reserve: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct RpcPoolConfig {
+    /// RPC 节点列表 (为空时只使用 network.rpc_url)
+    pub endpoints: Vec<RpcEndpointConfig>,
+    /// 健康检查间隔 (毫秒)
+    pub health_check_interval_ms: u64,
+    /// 单次请求超时 (秒)
+    pub request_timeout_secs: u64,
+    /// 落后最高 Slot 超过该值的节点视为不健康
+    pub max_slot_lag: u64,
+    /// 错误率 (指数滑动平均) 超过该值的节点视为不健康
+    pub max_error_rate: f64,
+    /// 连续失败达到该次数的节点视为不健康，健康检查成功后恢复
+    pub max_consecutive_failures: u32,
+    /// 客户端限速 (每个节点独立计数)
+    pub rate_limit: RateLimitConfig,
+}
+
+impl Default for RpcPoolConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            health_check_interval_ms: 2000,
+            request_timeout_secs: 30,
+            max_slot_lag: 50,
+            max_error_rate: 0.5,
+            max_consecutive_failures: 3,
+            rate_limit: RateLimitConfig::default(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub network: NetworkConfig,
     pub jito: JitoConfig,
     pub strategy: StrategyConfig,
     pub log: LogConfig,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub scout: ScoutConfig,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    #[serde(default)]
+    pub pool_source: PoolSourceConfig,
+    #[serde(default)]
+    pub geyser: GeyserConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub bus: BusConfig,
+    #[serde(default)]
+    pub dedup: DedupConfig,
+    #[serde(default)]
+    pub backfill: BackfillConfig,
+    #[serde(default)]
+    pub leader: LeaderConfig,
+    #[serde(default)]
+    pub blockhash: BlockhashConfig,
+    #[serde(default)]
+    pub rpc: RpcPoolConfig,
+    #[serde(default)]
+    pub account_loader: AccountLoaderConfig,
+    #[serde(default)]
+    pub latency: LatencyConfig,
+    #[serde(default)]
+    pub commitment: StreamCommitmentConfig,
 }
 
 impl AppConfig {
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use log::{debug, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::StateWithExtensions;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use crate::config::AccountLoaderConfig;
+
+/// 同一时刻 (同一个 Context Slot) 读到的一组账户
+#[derive(Debug, Clone, Default)]
+pub struct AccountSnapshot {
+    /// 所有批次中最高的 Context Slot
+    pub slot: u64,
+    /// 所有批次是否落在同一个 Slot
+    pub consistent: bool,
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl AccountSnapshot {
+    /// 账户不存在时返回 None
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    /// Token 账户余额 (兼容 SPL Token 与 Token-2022)
+    pub fn token_amount(&self, pubkey: &Pubkey) -> Option<u64> {
+        token_amount(&self.get(pubkey)?.data)
+    }
+}
+
+/// 解析 Token 账户中的余额，替代 getTokenAccountBalance
+pub fn token_amount(data: &[u8]) -> Option<u64> {
+    if data.len() < spl_token_2022::state::Account::LEN {
+        return None;
+    }
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(data)
+        .ok()
+        .map(|state| state.base.amount)
+}
+
+/// 保持顺序去重
+fn unique(keys: &[Pubkey]) -> Vec<Pubkey> {
+    let mut seen = HashSet::new();
+    keys.iter().filter(|k| seen.insert(**k)).copied().collect()
+}
+
+type ChunkResult = Result<(u64, Vec<Option<Account>>), String>;
+
+/// 按 `batch_size` 分批拉取并组装快照，`fetch` 负责单批 getMultipleAccounts
+async fn collect_snapshot<F, Fut>(keys: &[Pubkey], batch_size: usize, fetch: F) -> Result<AccountSnapshot, String>
+where
+    F: Fn(Vec<Pubkey>, Option<u64>) -> Fut,
+    Fut: Future<Output = ChunkResult>,
+{
+    let keys = unique(keys);
+    if keys.is_empty() {
+        return Ok(AccountSnapshot { consistent: true, ..Default::default() });
+    }
+    let chunks: Vec<&[Pubkey]> = keys.chunks(batch_size.max(1)).collect();
+    let mut results = futures::future::try_join_all(chunks.iter().map(|chunk| fetch(chunk.to_vec(), None))).await?;
+
+    let max_slot = results.iter().map(|(slot, _)| *slot).max().unwrap_or(0);
+    for (chunk, result) in chunks.iter().zip(results.iter_mut()) {
+        if result.0 < max_slot {
+            match fetch(chunk.to_vec(), Some(max_slot)).await {
+                Ok(fresh) => *result = fresh,
+                Err(e) => debug!("[Accounts] 按 Slot {} 重拉 {} 个账户失败，保留 Slot {} 的结果: {}", max_slot, chunk.len(), result.0, e),
+            }
+        }
+    }
+
+    let slot = results.iter().map(|(slot, _)| *slot).max().unwrap_or(0);
+    let consistent = results.iter().all(|(s, _)| *s == slot);
+    if !consistent {
+        debug!("[Accounts] {} 个账户跨越多个 Slot，最高 {}", keys.len(), slot);
+    }
+    // 返回条数与请求不符的批次无法按位置对应账户，整批丢弃
+    let accounts = chunks
+        .iter()
+        .zip(results)
+        .filter(|(chunk, (_, accounts))| {
+            let matched = accounts.len() == chunk.len();
+            if !matched {
+                warn!("⚠️ [Accounts] 请求 {} 个账户却返回 {} 个，丢弃该批次", chunk.len(), accounts.len());
+            }
+            matched
+        })
+        .flat_map(|(chunk, (_, accounts))| chunk.iter().copied().zip(accounts))
+        .filter_map(|(pubkey, account)| account.map(|a| (pubkey, a)))
+        .collect();
+    Ok(AccountSnapshot { slot, consistent, accounts })
+}
+
+struct Pending {
+    pubkey: Pubkey,
+    reply: oneshot::Sender<Result<Option<Account>, String>>,
+}
+
+struct LoaderInner {
+    rpc_client: Arc<RpcClient>,
+    config: AccountLoaderConfig,
+    /// 与账户推送一致的确认级别，避免对手盘读到比推送更旧的状态
+    commitment: CommitmentConfig,
+    /// 批量窗口的请求队列，`spawn` 之后才有
+    queue: OnceLock<mpsc::UnboundedSender<Pending>>,
+}
+
+/// 账户批量加载: 用 getMultipleAccounts (每次最多 100 个) 取代逐个 getAccountInfo
+/// - `snapshot`: 一次机会需要的账户一起拉取，保证报价用的是同一个 Slot 的状态
+/// - `load`: 零散的单账户请求在很短的窗口内合并成一次调用
+#[derive(Clone)]
+pub struct AccountLoader {
+    inner: Arc<LoaderInner>,
+}
+
+impl AccountLoader {
+    pub fn new(rpc_client: Arc<RpcClient>, config: &AccountLoaderConfig, commitment: CommitmentConfig) -> Self {
+        Self {
+            inner: Arc::new(LoaderInner {
+                rpc_client,
+                config: config.clone(),
+                commitment,
+                queue: OnceLock::new(),
+            }),
+        }
+    }
+
+    fn batch_size(&self) -> usize {
+        self.inner.config.max_batch_size.clamp(1, MAX_MULTIPLE_ACCOUNTS)
+    }
+
+    async fn fetch_chunk(&self, keys: &[Pubkey], min_context_slot: Option<u64>) -> Result<(u64, Vec<Option<Account>>), String> {
+        let rpc_client = &self.inner.rpc_client;
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64Zstd),
+            commitment: Some(self.inner.commitment),
+            min_context_slot,
+            data_slice: None,
+        };
+        rpc_client
+            .get_multiple_accounts_with_config(keys, config)
+            .await
+            .map(|response| (response.context.slot, response.value))
+            .map_err(|e| e.to_string())
+    }
+
+    /// 一次性拉取一组账户
+    /// 超过 100 个时分批并发请求；批次之间 Slot 不一致时，把落后的批次按最高 Slot (minContextSlot) 重拉一次
+    /// 重拉失败 (节点仍落后) 时保留原结果，快照标记为不一致，由调用方决定是否使用
+    pub async fn snapshot(&self, keys: &[Pubkey]) -> Result<AccountSnapshot, String> {
+        collect_snapshot(keys, self.batch_size(), |chunk, min_context_slot| async move {
+            self.fetch_chunk(&chunk, min_context_slot).await
+        })
+        .await
+    }
+
+    /// 单个账户；批量窗口已启动时与同一窗口内的其他请求合并
+    pub async fn load(&self, pubkey: &Pubkey) -> Result<Option<Account>, String> {
+        let queue = match self.inner.queue.get() {
+            Some(queue) => queue,
+            None => return Ok(self.snapshot(&[*pubkey]).await?.get(pubkey).cloned()),
+        };
+        let (reply, rx) = oneshot::channel();
+        if queue.send(Pending { pubkey: *pubkey, reply }).is_err() {
+            return Ok(self.snapshot(&[*pubkey]).await?.get(pubkey).cloned());
+        }
+        rx.await.map_err(|_| "account loader dropped the request".to_string())?
+    }
+
+    /// 启动批量窗口: 第一个请求到达后等待 `batch_window_ms` 或攒满一批再发出
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Pending>();
+        if self.inner.queue.set(tx).is_err() {
+            warn!("⚠️ [Accounts] 批量加载已经启动");
+        }
+        let loader = self.clone();
+        let window = Duration::from_millis(self.inner.config.batch_window_ms);
+        let batch_size = self.batch_size();
+        tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                let mut batch = vec![first];
+                let deadline = tokio::time::sleep(window);
+                tokio::pin!(deadline);
+                while batch.len() < batch_size {
+                    tokio::select! {
+                        _ = &mut deadline => break,
+                        next = rx.recv() => match next {
+                            Some(pending) => batch.push(pending),
+                            None => break,
+                        },
+                    }
+                }
+                let loader = loader.clone();
+                tokio::spawn(async move { loader.flush(batch).await });
+            }
+        })
+    }
+
+    async fn flush(&self, batch: Vec<Pending>) {
+        let keys: Vec<Pubkey> = batch.iter().map(|p| p.pubkey).collect();
+        let snapshot = self.snapshot(&keys).await;
+        for pending in batch {
+            let result = match &snapshot {
+                Ok(snapshot) => Ok(snapshot.get(&pending.pubkey).cloned()),
+                Err(e) => Err(e.clone()),
+            };
+            let _ = pending.reply.send(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use spl_token_2022::state::{Account as TokenAccount, AccountState};
+
+    #[test]
+    fn test_unique_keys_and_token_amount() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(unique(&[a, b, a, b, a]), vec![a, b]);
+
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount { mint: a, owner: b, amount: 42_000, state: AccountState::Initialized, ..Default::default() },
+            &mut data,
+        )
+        .unwrap();
+        assert_eq!(token_amount(&data), Some(42_000));
+        assert_eq!(token_amount(&data[..64]), None);
+
+        let snapshot = AccountSnapshot {
+            slot: 7,
+            consistent: true,
+            accounts: HashMap::from([(a, Account { data, owner: spl_token::id(), ..Default::default() })]),
+        };
+        assert_eq!(snapshot.token_amount(&a), Some(42_000));
+        assert_eq!(snapshot.token_amount(&b), None);
+    }
+
+    /// 模拟节点: `slots` 依次作为每次调用返回的 Context Slot，`fail_refetch` 时带 minContextSlot 的请求失败
+    struct FakeNode {
+        accounts: HashMap<Pubkey, Account>,
+        slots: Mutex<Vec<u64>>,
+        calls: Mutex<Vec<(usize, Option<u64>)>>,
+        fail_refetch: bool,
+    }
+
+    impl FakeNode {
+        fn new(accounts: HashMap<Pubkey, Account>, slots: Vec<u64>, fail_refetch: bool) -> Self {
+            Self { accounts, slots: Mutex::new(slots), calls: Mutex::new(Vec::new()), fail_refetch }
+        }
+
+        async fn fetch(&self, keys: Vec<Pubkey>, min_context_slot: Option<u64>) -> ChunkResult {
+            self.calls.lock().unwrap().push((keys.len(), min_context_slot));
+            if min_context_slot.is_some() && self.fail_refetch {
+                return Err("Minimum context slot has not been reached".to_string());
+            }
+            let slot = self.slots.lock().unwrap().remove(0);
+            Ok((slot, keys.iter().map(|k| self.accounts.get(k).cloned()).collect()))
+        }
+    }
+
+    fn accounts(n: u64) -> (Vec<Pubkey>, HashMap<Pubkey, Account>) {
+        let keys: Vec<Pubkey> = (0..n).map(|_| Pubkey::new_unique()).collect();
+        // 奇数下标的账户不存在；lamports 记录下标，用来检查对应关系
+        let accounts = keys
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| i % 2 == 0)
+            .map(|(i, k)| (*k, Account { lamports: i as u64, ..Default::default() }))
+            .collect();
+        (keys, accounts)
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_chunking_order_and_missing() {
+        let loader = AccountLoader::new(
+            Arc::new(RpcClient::new_mock("succeeds".to_string())),
+            &AccountLoaderConfig { max_batch_size: 500, ..Default::default() },
+            CommitmentConfig::processed(),
+        );
+        assert_eq!(loader.batch_size(), MAX_MULTIPLE_ACCOUNTS);
+
+        let (keys, accounts) = accounts(250);
+        let node = FakeNode::new(accounts, vec![9, 9, 9], false);
+        // 重复的 Key 只拉一次，且不打乱批次内的顺序
+        let mut requested = keys.clone();
+        requested.extend_from_slice(&keys[..20]);
+        requested.insert(50, keys[3]);
+        let snapshot = collect_snapshot(&requested, MAX_MULTIPLE_ACCOUNTS, |chunk, min| node.fetch(chunk, min)).await.unwrap();
+
+        assert_eq!(*node.calls.lock().unwrap(), vec![(100, None), (100, None), (50, None)]);
+        assert_eq!((snapshot.slot, snapshot.consistent), (9, true));
+        for (i, key) in keys.iter().enumerate() {
+            match snapshot.get(key) {
+                Some(account) => assert_eq!(account.lamports, i as u64),
+                None => assert!(i % 2 == 1, "account {} missing", i),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_refetches_lagging_chunk() {
+        let (keys, accounts) = accounts(150);
+
+        // 第一批落后，按最高 Slot 重拉后一致
+        let node = FakeNode::new(accounts.clone(), vec![10, 12, 12], false);
+        let snapshot = collect_snapshot(&keys, 100, |chunk, min| node.fetch(chunk, min)).await.unwrap();
+        assert_eq!(*node.calls.lock().unwrap(), vec![(100, None), (50, None), (100, Some(12))]);
+        assert_eq!((snapshot.slot, snapshot.consistent), (12, true));
+
+        // 重拉失败时不让整个快照失败，保留原结果并标记为不一致
+        let node = FakeNode::new(accounts, vec![10, 12], true);
+        let snapshot = collect_snapshot(&keys, 100, |chunk, min| node.fetch(chunk, min)).await.unwrap();
+        assert_eq!((snapshot.slot, snapshot.consistent), (12, false));
+        assert_eq!(snapshot.get(&keys[0]).map(|a| a.lamports), Some(0));
+        assert_eq!(snapshot.get(&keys[148]).map(|a| a.lamports), Some(148));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_drops_short_chunk() {
+        let (keys, accounts) = accounts(150);
+        let node = FakeNode::new(accounts, vec![5, 5], false);
+        // 第二批少返回一个账户，无法按位置对应，整批丢弃
+        let snapshot = collect_snapshot(&keys, 100, |chunk, min| {
+            let node = &node;
+            async move {
+                let (slot, mut accounts) = node.fetch(chunk, min).await?;
+                if accounts.len() < 100 {
+                    accounts.pop();
+                }
+                Ok((slot, accounts))
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(snapshot.get(&keys[98]).map(|a| a.lamports), Some(98));
+        assert!(snapshot.get(&keys[100]).is_none());
+    }
+}
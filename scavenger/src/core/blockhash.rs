@@ -0,0 +1,149 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use arc_swap::ArcSwapOption;
+use log::{info, warn};
+use solana_client::client_error::ClientError;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::clock::MAX_PROCESSING_AGE;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use tokio::task::JoinHandle;
+use crate::config::BlockhashConfig;
+
+/// 一次 getLatestBlockhash 的结果
+#[derive(Debug, Clone, Copy)]
+pub struct RecentBlockhash {
+    pub blockhash: Hash,
+    /// 超过该区块高度后使用此 Blockhash 的交易不会再被处理
+    pub last_valid_block_height: u64,
+    pub fetched_at: Instant,
+}
+
+struct CacheInner {
+    latest: ArcSwapOption<RecentBlockhash>,
+    config: BlockhashConfig,
+}
+
+/// 后台定时刷新的 Blockhash 缓存，读取无锁
+/// 发现机会时直接取缓存，省掉一次 RPC 往返
+#[derive(Clone)]
+pub struct BlockhashCache {
+    inner: Arc<CacheInner>,
+}
+
+impl BlockhashCache {
+    pub fn new(config: &BlockhashConfig) -> Self {
+        Self {
+            inner: Arc::new(CacheInner {
+                latest: ArcSwapOption::empty(),
+                config: config.clone(),
+            }),
+        }
+    }
+
+    fn store(&self, blockhash: Hash, last_valid_block_height: u64) {
+        self.inner.latest.store(Some(Arc::new(RecentBlockhash {
+            blockhash,
+            last_valid_block_height,
+            fetched_at: Instant::now(),
+        })));
+    }
+
+    /// 最新的 Blockhash；刷新长时间失败导致缓存过旧时返回 None
+    pub fn get(&self) -> Option<RecentBlockhash> {
+        let latest = **self.inner.latest.load().as_ref()?;
+        let max_staleness = Duration::from_millis(self.inner.config.max_staleness_ms);
+        (latest.fetched_at.elapsed() <= max_staleness).then_some(latest)
+    }
+
+    /// 优先取缓存，缓存不可用时直接请求 RPC (结果同时写回缓存)
+    pub async fn get_or_fetch(&self, rpc_client: &RpcClient) -> Result<RecentBlockhash, ClientError> {
+        if let Some(latest) = self.get() {
+            return Ok(latest);
+        }
+        let (blockhash, last_valid_block_height) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        self.store(blockhash, last_valid_block_height);
+        Ok(self.get().unwrap())
+    }
+
+    /// 估算 `recent` 还剩多少个区块过期 (已过期为 0)
+    /// 当前区块高度由最新一次刷新推算: last_valid_block_height = block_height + MAX_PROCESSING_AGE
+    pub fn blocks_remaining(&self, recent: &RecentBlockhash) -> Option<u64> {
+        let latest = self.inner.latest.load_full()?;
+        let block_height = latest.last_valid_block_height.saturating_sub(MAX_PROCESSING_AGE as u64);
+        Some(recent.last_valid_block_height.saturating_sub(block_height))
+    }
+
+    /// 即将过期的 Blockhash，在途交易应换新的 Blockhash 重建
+    pub fn is_expiring(&self, recent: &RecentBlockhash) -> bool {
+        self.blocks_remaining(recent)
+            .is_some_and(|remaining| remaining <= self.inner.config.expiry_margin_blocks)
+    }
+
+    /// 启动后台刷新任务
+    pub fn spawn(&self, rpc_client: Arc<RpcClient>) -> JoinHandle<()> {
+        let cache = self.clone();
+        let interval = Duration::from_millis(self.inner.config.refresh_interval_ms.max(50));
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(interval);
+            tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut failures = 0u64;
+            loop {
+                tick.tick().await;
+                match rpc_client.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed()).await {
+                    Ok((blockhash, last_valid_block_height)) => {
+                        if failures > 0 {
+                            info!("✅ [Blockhash] 刷新已恢复 (此前连续失败 {} 次)", failures);
+                            failures = 0;
+                        }
+                        cache.store(blockhash, last_valid_block_height);
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        // 只在首次失败和之后每 50 次打印，避免刷屏
+                        if failures == 1 || failures % 50 == 0 {
+                            warn!("⚠️ [Blockhash] 刷新失败 (连续 {} 次): {}", failures, e);
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staleness_and_expiry() {
+        let cache = BlockhashCache::new(&BlockhashConfig {
+            refresh_interval_ms: 400,
+            expiry_margin_blocks: 20,
+            max_staleness_ms: 5000,
+        });
+        assert!(cache.get().is_none());
+
+        cache.store(Hash::new_unique(), 1_150);
+        let first = cache.get().unwrap();
+        assert_eq!(cache.blocks_remaining(&first), Some(150));
+        assert!(!cache.is_expiring(&first));
+
+        // 135 个区块之后只剩 15 个区块
+        cache.store(Hash::new_unique(), 1_285);
+        assert_eq!(cache.blocks_remaining(&first), Some(15));
+        assert!(cache.is_expiring(&first));
+        assert!(!cache.is_expiring(&cache.get().unwrap()));
+
+        cache.store(Hash::new_unique(), 1_400);
+        assert_eq!(cache.blocks_remaining(&first), Some(0));
+
+        // 刷新长时间失败后不再提供旧值
+        let stale = BlockhashCache::new(&BlockhashConfig { max_staleness_ms: 0, ..Default::default() });
+        stale.store(Hash::new_unique(), 1_150);
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(stale.get().is_none());
+    }
+}
@@ -17,6 +17,12 @@ struct BundleRequest {
     params: Vec<serde_json::Value>,
 }
 
+impl Default for JitoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JitoClient {
     pub fn new() -> Self {
         Self {
@@ -1,13 +1,19 @@
 use reqwest::Client;
 use serde_json::{json, Value};
-use log::{info, error};
 use std::time::Duration;
+use crate::core::latency::{OpportunityTrace, Stage};
 
 pub struct JitoHttpClient {
     client: Client,
     url: String,
 }
 
+impl Default for JitoHttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JitoHttpClient {
     pub fn new() -> Self {
         Self {
@@ -21,6 +27,11 @@ impl JitoHttpClient {
     }
 
     pub async fn send_bundle(&self, txs_base58: Vec<String>) -> Result<String, String> {
+        self.send_bundle_traced(txs_base58, None).await
+    }
+
+    /// 同 `send_bundle`，并在收到响应头 (Submit) 和解析完回执 (Response) 时打点
+    pub async fn send_bundle_traced(&self, txs_base58: Vec<String>, mut trace: Option<&mut OpportunityTrace>) -> Result<String, String> {
         let payload = json!({
             "jsonrpc": "2.0",
             "id": 1,
@@ -30,25 +41,34 @@ impl JitoHttpClient {
             ]
         });
 
-        match self.client.post(&self.url).json(&payload).send().await {
+        let response = self.client.post(&self.url).json(&payload).send().await;
+        if let Some(trace) = trace.as_deref_mut() {
+            trace.mark(Stage::Submit);
+        }
+        let result = match response {
             Ok(resp) => {
                 match resp.json::<Value>().await {
                     Ok(json) => {
                         if let Some(result) = json.get("result") {
-                            if let Some(uuid) = result.as_str() {
-                                return Ok(uuid.to_string());
+                            match result.as_str() {
+                                Some(uuid) => Ok(uuid.to_string()),
+                                // Sometimes result is just the bundle ID string directly or inside an object
+                                None => Ok(result.to_string()),
                             }
-                            // Sometimes result is just the bundle ID string directly or inside an object
-                            return Ok(result.to_string());
                         } else if let Some(err) = json.get("error") {
-                            return Err(format!("Jito Error: {:?}", err));
+                            Err(format!("Jito Error: {:?}", err))
+                        } else {
+                            Ok("Bundle Sent (No ID returned)".to_string())
                         }
-                        Ok("Bundle Sent (No ID returned)".to_string())
                     },
                     Err(e) => Err(format!("Failed to parse Jito response: {}", e)),
                 }
             },
             Err(e) => Err(format!("HTTP Request Failed: {}", e)),
+        };
+        if let Some(trace) = trace {
+            trace.mark(Stage::Response);
         }
+        result
     }
 }
@@ -0,0 +1,312 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use log::{info, warn};
+use tokio::task::JoinHandle;
+use crate::config::LatencyConfig;
+use crate::scout::events::EventSource;
+
+/// 每个 2 的幂区间再细分的桶数 (相对误差 <= 25%)
+const SUB_BUCKETS: usize = 4;
+/// 覆盖到 2^40 微秒，远超任何实际延迟
+const BUCKETS: usize = 40 * SUB_BUCKETS;
+const SOURCES: [EventSource; 2] = [EventSource::WebSocket, EventSource::Geyser];
+
+/// 一次机会从事件到达到 Bundle 回执经过的阶段 (按顺序)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// 事件到达 -> 池子状态解析出价格
+    Decode,
+    /// 拉取对手盘并报价
+    Quote,
+    /// 价差判断、方向与数量
+    Sizing,
+    /// 构建 Swap / Tip 指令
+    Build,
+    /// 等待 Jito Leader
+    LeaderWait,
+    /// 取 Blockhash 并签名
+    Sign,
+    /// 发出 Bundle 到收到 HTTP 响应头
+    Submit,
+    /// 读取并解析回执
+    Response,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 8] = [
+        Stage::Decode,
+        Stage::Quote,
+        Stage::Sizing,
+        Stage::Build,
+        Stage::LeaderWait,
+        Stage::Sign,
+        Stage::Submit,
+        Stage::Response,
+    ];
+}
+
+fn bucket_of(us: u64) -> usize {
+    if us < SUB_BUCKETS as u64 {
+        return us as usize;
+    }
+    let octave = 63 - us.leading_zeros() as usize; // >= 2
+    let sub = (us >> (octave - 2)) as usize & (SUB_BUCKETS - 1);
+    ((octave - 1) * SUB_BUCKETS + sub).min(BUCKETS - 1)
+}
+
+/// 桶的上界 (微秒)
+fn bucket_upper(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64 + 1;
+    }
+    let octave = index / SUB_BUCKETS + 1;
+    let sub = (index % SUB_BUCKETS) as u64;
+    ((SUB_BUCKETS as u64 + sub) << (octave - 2)) + (1 << (octave - 2))
+}
+
+/// 无锁的对数分桶直方图
+pub struct Histogram {
+    buckets: Vec<AtomicU64>,
+    count: AtomicU64,
+    sum_us: AtomicU64,
+    max_us: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_us: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_of(us)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// 分位数 (取所在桶的上界，不超过最大值)
+    pub fn percentile(&self, q: f64) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((count as f64 * q).ceil() as u64).clamp(1, count);
+        let max = self.max_us.load(Ordering::Relaxed);
+        let mut seen = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= rank {
+                return Duration::from_micros(bucket_upper(i).min(max));
+            }
+        }
+        Duration::from_micros(max)
+    }
+
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::ZERO,
+            n => Duration::from_micros(self.sum_us.load(Ordering::Relaxed) / n),
+        }
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_us.load(Ordering::Relaxed))
+    }
+}
+
+/// 单个 (阶段, 数据源) 的延迟统计；`stage` 为 None 表示端到端总耗时
+#[derive(Debug, Clone)]
+pub struct LatencyStats {
+    pub stage: Option<Stage>,
+    pub source: EventSource,
+    pub count: u64,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+struct TrackerInner {
+    /// 按 (数据源, 阶段) 展开
+    stages: Vec<Histogram>,
+    totals: Vec<Histogram>,
+    next_id: AtomicU64,
+    config: LatencyConfig,
+}
+
+/// 机会延迟追踪: 按阶段和数据源 (WebSocket / Geyser) 统计耗时分布
+#[derive(Clone)]
+pub struct LatencyTracker {
+    inner: Arc<TrackerInner>,
+}
+
+impl LatencyTracker {
+    pub fn new(config: &LatencyConfig) -> Self {
+        Self {
+            inner: Arc::new(TrackerInner {
+                stages: (0..SOURCES.len() * Stage::ALL.len()).map(|_| Histogram::default()).collect(),
+                totals: (0..SOURCES.len()).map(|_| Histogram::default()).collect(),
+                next_id: AtomicU64::new(1),
+                config: config.clone(),
+            }),
+        }
+    }
+
+    /// 从事件到达时刻开始追踪一次机会
+    pub fn begin(&self, source: EventSource, arrived: Instant) -> OpportunityTrace {
+        OpportunityTrace {
+            id: self.inner.next_id.fetch_add(1, Ordering::Relaxed),
+            source,
+            arrived,
+            last: arrived,
+            stages: Vec::with_capacity(Stage::ALL.len()),
+            tracker: self.clone(),
+        }
+    }
+
+    pub fn histogram(&self, stage: Stage, source: EventSource) -> &Histogram {
+        &self.inner.stages[source as usize * Stage::ALL.len() + stage as usize]
+    }
+
+    pub fn total(&self, source: EventSource) -> &Histogram {
+        &self.inner.totals[source as usize]
+    }
+
+    /// 有数据的 (阶段, 数据源) 统计，阶段之后附带端到端总耗时
+    pub fn stats(&self) -> Vec<LatencyStats> {
+        let summarize = |stage, source, h: &Histogram| LatencyStats {
+            stage,
+            source,
+            count: h.count(),
+            mean: h.mean(),
+            p50: h.percentile(0.5),
+            p90: h.percentile(0.9),
+            p99: h.percentile(0.99),
+            max: h.max(),
+        };
+        SOURCES
+            .iter()
+            .flat_map(|&source| {
+                Stage::ALL
+                    .iter()
+                    .map(move |&stage| summarize(Some(stage), source, self.histogram(stage, source)))
+                    .chain(std::iter::once(summarize(None, source, self.total(source))))
+            })
+            .filter(|s| s.count > 0)
+            .collect()
+    }
+
+    pub fn log_stats(&self) {
+        for s in self.stats() {
+            let stage = s.stage.map_or("total".to_string(), |stage| format!("{:?}", stage));
+            info!("⏱️ [Latency] {:?} | {} | n={} | avg {:?} | p50 {:?} | p90 {:?} | p99 {:?} | max {:?}",
+                s.source, stage, s.count, s.mean, s.p50, s.p90, s.p99, s.max);
+        }
+    }
+
+    /// 定期打印延迟分布
+    pub fn spawn_reporter(&self) -> JoinHandle<()> {
+        let tracker = self.clone();
+        let interval = Duration::from_secs(self.inner.config.report_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(interval);
+            tick.tick().await;
+            loop {
+                tick.tick().await;
+                tracker.log_stats();
+            }
+        })
+    }
+}
+
+/// 单次机会的阶段时间戳；每个阶段的耗时从上一个阶段结束 (或事件到达) 算起
+pub struct OpportunityTrace {
+    pub id: u64,
+    pub source: EventSource,
+    arrived: Instant,
+    last: Instant,
+    stages: Vec<(Stage, Duration)>,
+    tracker: LatencyTracker,
+}
+
+impl OpportunityTrace {
+    pub fn mark(&mut self, stage: Stage) {
+        self.mark_at(stage, Instant::now());
+    }
+
+    /// 补记一个在追踪开始前就已结束的阶段 (例如确认是机会之前的解析 / 报价)
+    pub fn mark_at(&mut self, stage: Stage, at: Instant) {
+        let elapsed = at.saturating_duration_since(self.last);
+        self.last = at;
+        self.stages.push((stage, elapsed));
+        self.tracker.histogram(stage, self.source).record(elapsed);
+    }
+
+    /// 事件到达至今的耗时
+    pub fn elapsed(&self) -> Duration {
+        self.arrived.elapsed()
+    }
+
+    /// 机会走完全流程 (收到 Bundle 回执)，记录端到端耗时；超过阈值时打印各阶段明细
+    pub fn finish(self) -> Duration {
+        let total = self.elapsed();
+        self.tracker.total(self.source).record(total);
+        if total > Duration::from_millis(self.tracker.inner.config.slow_opportunity_ms) {
+            let breakdown: Vec<String> = self.stages.iter().map(|(stage, d)| format!("{:?} {:?}", stage, d)).collect();
+            warn!("🐌 [Latency] 机会 #{} ({:?}) 总耗时 {:?}: {}", self.id, self.source, total, breakdown.join(" -> "));
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_and_trace() {
+        // 桶边界连续，上界不小于样本
+        for us in [0, 1, 3, 4, 5, 7, 8, 100, 1_000, 123_456, 10_000_000] {
+            let i = bucket_of(us);
+            assert!(bucket_upper(i) > us, "{}us -> bucket {}", us, i);
+            assert!(i == 0 || bucket_upper(i - 1) <= us, "{}us -> bucket {}", us, i);
+        }
+
+        let h = Histogram::default();
+        for ms in 1..=100 {
+            h.record(Duration::from_millis(ms));
+        }
+        assert_eq!(h.count(), 100);
+        let p50 = h.percentile(0.5);
+        assert!(p50 >= Duration::from_millis(50) && p50 <= Duration::from_micros(62_500), "{:?}", p50);
+        assert_eq!(h.percentile(1.0), Duration::from_millis(100));
+        assert_eq!(h.max(), Duration::from_millis(100));
+
+        let tracker = LatencyTracker::new(&LatencyConfig::default());
+        let arrived = Instant::now() - Duration::from_millis(5);
+        let mut trace = tracker.begin(EventSource::Geyser, arrived);
+        trace.mark_at(Stage::Decode, arrived + Duration::from_millis(2));
+        trace.mark(Stage::Quote);
+        assert!(trace.finish() >= Duration::from_millis(5));
+        assert_eq!(tracker.histogram(Stage::Decode, EventSource::Geyser).max(), Duration::from_millis(2));
+        assert!(tracker.histogram(Stage::Quote, EventSource::Geyser).max() >= Duration::from_millis(3));
+        assert_eq!(tracker.histogram(Stage::Quote, EventSource::WebSocket).count(), 0);
+        assert_eq!(tracker.begin(EventSource::WebSocket, arrived).id, 2);
+        // Decode / Quote / total 三条 (只有 Geyser)
+        assert_eq!(tracker.stats().len(), 3);
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_response::RpcLeaderSchedule;
+use solana_sdk::epoch_schedule::EpochSchedule;
+use solana_sdk::pubkey::Pubkey;
+use tokio::task::JoinHandle;
+use crate::bus::{BusEvent, EventBus, EventKind};
+use crate::config::LeaderConfig;
+
+/// 检查 Leader Schedule 是否需要刷新的间隔；期间没有收到 Slot 推送时改为主动拉取
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Bundle 提交时机
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitDecision {
+    /// 当前或即将出块的 Leader 运行 Jito，立即发送
+    Send,
+    /// 下一个 Jito Leader 还有 `slots` 个 Slot，等待后再发送
+    Hold { slots: u64 },
+    /// 前瞻窗口内没有 Jito Leader，发送也不会被打包
+    Skip,
+}
+
+/// 一个 Epoch 的 Leader 排期 (按 Slot 偏移存 Identity 下标，避免每个 Slot 存一份公钥)
+struct EpochLeaders {
+    first_slot: u64,
+    identities: Vec<Pubkey>,
+    slots: Vec<u32>,
+}
+
+impl EpochLeaders {
+    fn from_rpc(first_slot: u64, slots_in_epoch: u64, schedule: &RpcLeaderSchedule) -> Self {
+        let mut identities = Vec::with_capacity(schedule.len());
+        let mut slots = vec![u32::MAX; slots_in_epoch as usize];
+        for (identity, indexes) in schedule {
+            let pubkey = match Pubkey::from_str(identity) {
+                Ok(pubkey) => pubkey,
+                Err(_) => continue,
+            };
+            let id = identities.len() as u32;
+            identities.push(pubkey);
+            for &i in indexes {
+                if let Some(slot) = slots.get_mut(i) {
+                    *slot = id;
+                }
+            }
+        }
+        Self { first_slot, identities, slots }
+    }
+
+    fn leader(&self, slot: u64) -> Option<Pubkey> {
+        let offset = slot.checked_sub(self.first_slot)? as usize;
+        let id = *self.slots.get(offset)?;
+        self.identities.get(id as usize).copied()
+    }
+}
+
+struct TrackerInner {
+    /// 观察到的最高 Slot (0 表示未知)
+    slot: AtomicU64,
+    epoch_schedule: OnceLock<EpochSchedule>,
+    /// 当前 Epoch 与下一个 Epoch 的排期 (按 Epoch 编号)
+    schedules: RwLock<HashMap<u64, Arc<EpochLeaders>>>,
+    jito_validators: HashSet<Pubkey>,
+    config: LeaderConfig,
+}
+
+/// Slot / Leader 追踪: Slot 来自总线 (slotSubscribe 或 Geyser)，Leader Schedule 每个 Epoch 拉取一次
+/// 提交代码据此判断距下一个 Jito Leader 还有多少个 Slot，决定发送、等待还是放弃
+#[derive(Clone)]
+pub struct LeaderTracker {
+    inner: Arc<TrackerInner>,
+}
+
+impl LeaderTracker {
+    pub fn new(config: &LeaderConfig) -> Result<Self, String> {
+        let jito_validators = config.jito_validators()?.into_iter().collect();
+        Ok(Self {
+            inner: Arc::new(TrackerInner {
+                slot: AtomicU64::new(0),
+                epoch_schedule: OnceLock::new(),
+                schedules: RwLock::new(HashMap::new()),
+                jito_validators,
+                config: config.clone(),
+            }),
+        })
+    }
+
+    /// 记录新的 Slot；多条连接 / 多种确认级别的推送乱序到达，只保留最大值
+    pub fn observe_slot(&self, slot: u64) {
+        self.inner.slot.fetch_max(slot, Ordering::Relaxed);
+    }
+
+    pub fn current_slot(&self) -> Option<u64> {
+        Some(self.inner.slot.load(Ordering::Relaxed)).filter(|s| *s > 0)
+    }
+
+    /// 指定 Slot 的 Leader，排期未加载时返回 None
+    pub fn leader_at(&self, slot: u64) -> Option<Pubkey> {
+        let epoch = self.inner.epoch_schedule.get()?.get_epoch(slot);
+        self.inner.schedules.read().unwrap().get(&epoch)?.leader(slot)
+    }
+
+    pub fn is_jito_leader(&self, slot: u64) -> bool {
+        self.leader_at(slot).is_some_and(|l| self.inner.jito_validators.contains(&l))
+    }
+
+    /// 距下一个 Jito Leader 的 Slot 数 (当前 Leader 即为 Jito 时为 0)，前瞻窗口内没有时返回 None
+    pub fn slots_until_jito_leader(&self) -> Option<u64> {
+        let current = self.current_slot()?;
+        (0..=self.inner.config.lookahead_slots).find(|d| self.is_jito_leader(current + d))
+    }
+
+    /// 根据下一个 Jito Leader 的距离决定提交时机
+    /// 未配置 Jito 验证者或 Slot / 排期未知时无法判断，按原有行为直接发送
+    pub fn decide(&self) -> SubmitDecision {
+        let config = &self.inner.config;
+        if self.inner.jito_validators.is_empty() {
+            return SubmitDecision::Send;
+        }
+        let current = match self.current_slot() {
+            Some(slot) => slot,
+            None => return SubmitDecision::Send,
+        };
+        if self.leader_at(current).is_none() {
+            return SubmitDecision::Send;
+        }
+        match self.slots_until_jito_leader() {
+            Some(d) if d <= config.send_ahead_slots => SubmitDecision::Send,
+            Some(d) if d <= config.max_hold_slots => SubmitDecision::Hold { slots: d - config.send_ahead_slots },
+            _ => SubmitDecision::Skip,
+        }
+    }
+
+    /// 订阅总线上的 Slot 更新，并在跨 Epoch 时刷新 Leader Schedule
+    /// 必须在侦察层启动前调用 (侦察层根据总线订阅决定是否采集 Slot)
+    pub fn spawn(&self, rpc_client: Arc<RpcClient>, bus: &EventBus) -> JoinHandle<()> {
+        if self.inner.jito_validators.is_empty() {
+            warn!("⚠️ [Leader] 未配置 Jito 验证者，Bundle 将不做 Leader 判断直接发送");
+        }
+        let mut events = bus.subscribe("leader", &[EventKind::Slot]);
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+            let mut pushed = false;
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event.as_deref() {
+                        Some(BusEvent::Slot(update)) => {
+                            tracker.observe_slot(update.slot);
+                            pushed = true;
+                        }
+                        Some(_) => {}
+                        None => break,
+                    },
+                    _ = refresh.tick() => {
+                        // 没有 Slot 推送 (例如 Geyser 未开启 Slot 订阅) 时退回 RPC 轮询
+                        if !pushed {
+                            match rpc_client.get_slot().await {
+                                Ok(slot) => tracker.observe_slot(slot),
+                                Err(e) => warn!("⚠️ [Leader] 获取 Slot 失败: {}", e),
+                            }
+                        }
+                        pushed = false;
+                        tracker.refresh(&rpc_client).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// 保证当前与下一个 Epoch 的排期已加载，并清理过期的 Epoch
+    async fn refresh(&self, rpc_client: &RpcClient) {
+        let current = match self.current_slot() {
+            Some(slot) => slot,
+            None => return,
+        };
+        if self.inner.epoch_schedule.get().is_none() {
+            match rpc_client.get_epoch_schedule().await {
+                Ok(schedule) => {
+                    let _ = self.inner.epoch_schedule.set(schedule);
+                }
+                Err(e) => {
+                    warn!("⚠️ [Leader] 获取 Epoch Schedule 失败: {}", e);
+                    return;
+                }
+            }
+        }
+        let epoch_schedule = *self.inner.epoch_schedule.get().unwrap();
+        let epoch = epoch_schedule.get_epoch(current);
+
+        self.inner.schedules.write().unwrap().retain(|e, _| *e >= epoch);
+        for e in [epoch, epoch + 1] {
+            if self.inner.schedules.read().unwrap().contains_key(&e) {
+                continue;
+            }
+            let first_slot = epoch_schedule.get_first_slot_in_epoch(e);
+            match rpc_client.get_leader_schedule(Some(first_slot)).await {
+                Ok(Some(schedule)) => {
+                    let leaders = EpochLeaders::from_rpc(first_slot, epoch_schedule.get_slots_in_epoch(e), &schedule);
+                    let jito = leaders.identities.iter().filter(|l| self.inner.jito_validators.contains(l)).count();
+                    info!("🗓️ [Leader] 已加载 Epoch {} 的 Leader Schedule: {} 个验证者，其中 Jito {} 个",
+                        e, leaders.identities.len(), jito);
+                    self.inner.schedules.write().unwrap().insert(e, Arc::new(leaders));
+                }
+                // 下一个 Epoch 的排期在当前 Epoch 早期可能还未生成，下次刷新再试
+                Ok(None) => {}
+                Err(e) => warn!("⚠️ [Leader] 获取 Leader Schedule 失败: {}", e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_until_jito_leader_and_decision() {
+        let (jito, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let tracker = LeaderTracker::new(&LeaderConfig {
+            jito_validators: vec![jito.to_string()],
+            lookahead_slots: 16,
+            send_ahead_slots: 1,
+            max_hold_slots: 8,
+        })
+        .unwrap();
+        // 每个 Epoch 32 个 Slot，每个 Leader 连续出 4 个块
+        let epoch_schedule = EpochSchedule::custom(32, 32, false);
+        let schedule: RpcLeaderSchedule = HashMap::from([
+            (jito.to_string(), (8..12).chain(28..32).collect()),
+            (other.to_string(), (0..8).chain(12..28).collect()),
+        ]);
+        let leaders = EpochLeaders::from_rpc(32, 32, &schedule);
+        tracker.inner.epoch_schedule.set(epoch_schedule).unwrap();
+        tracker.inner.schedules.write().unwrap().insert(1, Arc::new(leaders));
+
+        // Slot 未知时无法判断，保持直接发送
+        assert_eq!(tracker.decide(), SubmitDecision::Send);
+
+        tracker.observe_slot(34);
+        tracker.observe_slot(33); // 乱序到达的旧 Slot 不会回退
+        assert_eq!(tracker.current_slot(), Some(34));
+        assert_eq!(tracker.leader_at(40), Some(jito));
+        assert_eq!(tracker.slots_until_jito_leader(), Some(6));
+        assert_eq!(tracker.decide(), SubmitDecision::Hold { slots: 5 });
+
+        tracker.observe_slot(39);
+        assert_eq!(tracker.decide(), SubmitDecision::Send);
+
+        // 44 之后下一个 Jito Leader 在 60，超出等待上限
+        tracker.observe_slot(44);
+        assert_eq!(tracker.slots_until_jito_leader(), Some(16));
+        assert_eq!(tracker.decide(), SubmitDecision::Skip);
+
+        // 下一个 Epoch 的排期尚未加载
+        tracker.observe_slot(64);
+        assert_eq!(tracker.leader_at(64), None);
+        assert_eq!(tracker.decide(), SubmitDecision::Send);
+    }
+}
@@ -1,11 +1,17 @@
+pub mod accounts;
 pub mod arbitrage;
+pub mod blockhash;
 pub mod jito;
 pub mod jito_http;
+pub mod latency;
+pub mod leader;
 pub mod orca;
 pub mod pricing;
 pub mod quote;
+pub mod rate_limit;
 pub mod raydium_keys;
 pub mod risk;
+pub mod rpc_pool;
 pub mod swap;
 
 pub fn init() {
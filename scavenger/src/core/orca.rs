@@ -16,6 +16,7 @@ pub struct WhirlpoolSwapData {
 }
 
 // 构建 Orca Whirlpool Swap 指令
+#[allow(clippy::too_many_arguments)]
 pub fn swap(
     whirlpool_program_id: &Pubkey,
     token_program: &Pubkey,
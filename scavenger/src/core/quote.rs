@@ -1,10 +1,11 @@
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
-use std::sync::Arc;
 use log::{info, error};
 use borsh::BorshDeserialize;
 use crate::amm::raydium_v4::AmmState;
 use crate::amm::math;
+use crate::core::accounts::{token_amount, AccountLoader};
+use crate::core::raydium_keys::decode_raydium_keys;
+use crate::state::{Inventory, PoolKeys};
 
 // 模拟获取 Raydium 报价 (Quote)
 // 实际实现需要：
@@ -12,21 +13,51 @@ use crate::amm::math;
 // 2. 解析 Reserve A 和 Reserve B
 // 3. 计算 Constant Product (x * y = k)
 pub async fn get_raydium_quote(
-    rpc_client: Arc<RpcClient>,
+    accounts: &AccountLoader,
+    inventory: &Inventory,
     pool_id: &Pubkey,
     amount_in: u64,
     input_mint: &Pubkey,
 ) -> Option<u64> {
-    // 1. 获取 Pool Account State
-    let account = match rpc_client.get_account(pool_id).await {
-        Ok(acc) => acc,
+    // 1. 静态 Key (Vault / Mint) 优先取缓存，首次报价时才单独拉一次 Pool 账户解析
+    let keys = match inventory.get_pool_keys(pool_id) {
+        Some(PoolKeys::Raydium(keys)) => *keys,
+        _ => {
+            let account = match accounts.load(pool_id).await {
+                Ok(Some(acc)) => acc,
+                Ok(None) => {
+                    error!("❌ Pool 账户不存在: {}", pool_id);
+                    return None;
+                }
+                Err(e) => {
+                    error!("❌ 无法获取 Pool 账户: {} - {}", pool_id, e);
+                    return None;
+                }
+            };
+            let keys = decode_raydium_keys(pool_id, &account)?;
+            inventory.set_pool_keys(PoolKeys::Raydium(Box::new(keys.clone())));
+            keys
+        }
+    };
+
+    // 2. Pool 和两个 Vault 一次拉取，保证费率和储备量来自同一个 Slot
+    // Raydium State 中没有直接存储实时 reserve，必须查 Vault Token Account
+    let snapshot = match accounts.snapshot(&[*pool_id, keys.base_vault, keys.quote_vault]).await {
+        Ok(snapshot) => snapshot,
         Err(e) => {
-            error!("❌ 无法获取 Pool 账户: {} - {}", pool_id, e);
+            error!("❌ 无法获取 Pool / Vault 账户: {} - {}", pool_id, e);
+            return None;
+        }
+    };
+    let account = match snapshot.get(pool_id) {
+        Some(acc) => acc,
+        None => {
+            error!("❌ Pool 账户不存在: {}", pool_id);
             return None;
         }
     };
 
-    // 2. 反序列化 AMM State
+    // 3. 反序列化 AMM State
     let amm_state = match AmmState::try_from_slice(&account.data) {
         Ok(state) => state,
         Err(e) => {
@@ -34,25 +65,16 @@ pub async fn get_raydium_quote(
             return None;
         }
     };
-
-    // 3. 获取 Vault 余额 (Reserves)
-    // 实际的 Reserve 应该查 Vault Account 的 Balance，而不是 AmmState 中的缓存值 (如果不可信)
-    // Raydium State 中没有直接存储实时 reserve，而是存储了 need_take_pnl 等
-    // 我们必须查 Vault Token Account
-    
-    let coin_vault = amm_state.pool_coin_token_account;
-    let pc_vault = amm_state.pool_pc_token_account;
-    
-    let reserve_coin = get_token_balance(&rpc_client, &coin_vault).await?;
-    let reserve_pc = get_token_balance(&rpc_client, &pc_vault).await?;
+    let reserve_coin = snapshot.token_amount(&keys.base_vault)?;
+    let reserve_pc = snapshot.token_amount(&keys.quote_vault)?;
     
     // 4. 确定方向
     // 如果 input_mint == coin_mint, 则是 Coin -> PC
     // 如果 input_mint == pc_mint, 则是 PC -> Coin
     
-    let (reserve_in, reserve_out) = if *input_mint == amm_state.coin_mint_address {
+    let (reserve_in, reserve_out) = if *input_mint == keys.base_mint {
         (reserve_coin, reserve_pc)
-    } else if *input_mint == amm_state.pc_mint_address {
+    } else if *input_mint == keys.quote_mint {
         (reserve_pc, reserve_coin)
     } else {
         error!("❌ 输入代币 {} 不属于该 Pool {}", input_mint, pool_id);
@@ -75,11 +97,9 @@ pub async fn get_raydium_quote(
 }
 
 // 辅助：获取 Token 余额
-pub async fn get_token_balance(rpc_client: &RpcClient, vault: &Pubkey) -> Option<u64> {
-    match rpc_client.get_token_account_balance(vault).await {
-        Ok(ui_amount) => {
-            ui_amount.amount.parse::<u64>().ok()
-        },
-        Err(_) => None
+pub async fn get_token_balance(accounts: &AccountLoader, vault: &Pubkey) -> Option<u64> {
+    match accounts.load(vault).await {
+        Ok(Some(account)) => token_amount(&account.data),
+        _ => None
     }
 }
@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use solana_client::rpc_request::RpcRequest;
+use crate::config::RateLimitConfig;
+
+/// 请求优先级: 令牌紧张时低优先级先让路
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// 发送 / 模拟交易
+    Execution,
+    /// 热路径读取 (报价、新池解析)
+    HotPath,
+    /// 后台任务 (冷启动加载、回填、元数据解析)
+    Background,
+}
+
+/// 按 RPC 方法的开销分类，每类单独限速
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MethodClass {
+    Send,
+    /// 扫描 / 历史类的重请求
+    Heavy,
+    Light,
+}
+
+impl MethodClass {
+    pub const ALL: [MethodClass; 3] = [MethodClass::Send, MethodClass::Heavy, MethodClass::Light];
+
+    pub fn of(request: RpcRequest) -> Self {
+        match request {
+            RpcRequest::SendTransaction | RpcRequest::SimulateTransaction | RpcRequest::RequestAirdrop => MethodClass::Send,
+            RpcRequest::GetProgramAccounts
+            | RpcRequest::GetTransaction
+            | RpcRequest::GetSignaturesForAddress
+            | RpcRequest::GetBlock
+            | RpcRequest::GetBlocks
+            | RpcRequest::GetBlocksWithLimit
+            | RpcRequest::GetLargestAccounts
+            | RpcRequest::GetTokenLargestAccounts
+            | RpcRequest::GetTokenAccountsByOwner
+            | RpcRequest::GetLeaderSchedule => MethodClass::Heavy,
+            _ => MethodClass::Light,
+        }
+    }
+}
+
+struct BucketState {
+    tokens: f64,
+    updated: Instant,
+    /// 服务端返回 429 时按 Retry-After 暂停
+    blocked_until: Option<Instant>,
+}
+
+/// 令牌桶: 低优先级只能使用高于保留水位的令牌，保证发送交易时还有余量
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+impl Bucket {
+    fn new(rate: f64, burst_secs: f64, now: Instant) -> Self {
+        let capacity = (rate * burst_secs).max(1.0);
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(BucketState { tokens: capacity, updated: now, blocked_until: None }),
+        }
+    }
+
+    fn try_acquire(&self, reserve: f64, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(until) = state.blocked_until {
+            if now < until {
+                return Err(until - now);
+            }
+            state.blocked_until = None;
+        }
+        // rate <= 0 表示不限速
+        if self.rate <= 0.0 {
+            return Ok(());
+        }
+        let elapsed = now.saturating_duration_since(state.updated).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+        state.updated = now;
+
+        let floor = self.capacity * reserve;
+        if state.tokens - 1.0 >= floor {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((floor + 1.0 - state.tokens) / self.rate))
+        }
+    }
+
+    fn block(&self, until: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.blocked_until = Some(state.blocked_until.map_or(until, |b| b.max(until)));
+    }
+}
+
+/// 单个 RPC 节点的限速器 (每种方法类别一个令牌桶)
+pub struct RateLimiter {
+    buckets: [Bucket; 3],
+    hot_path_reserve: f64,
+    background_reserve: f64,
+    /// 服务端 429 次数
+    throttled: AtomicU64,
+    /// 客户端主动等待 / 让路的次数
+    deferred: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let now = Instant::now();
+        let bucket = |rps: f64| Bucket::new(rps, config.burst_secs, now);
+        Self {
+            buckets: [bucket(config.send_rps), bucket(config.heavy_rps), bucket(config.light_rps)],
+            hot_path_reserve: config.hot_path_reserve.clamp(0.0, 1.0),
+            background_reserve: config.background_reserve.clamp(0.0, 1.0),
+            throttled: AtomicU64::new(0),
+            deferred: AtomicU64::new(0),
+        }
+    }
+
+    fn bucket(&self, class: MethodClass) -> &Bucket {
+        &self.buckets[class as usize]
+    }
+
+    /// 取一个令牌；拿不到时返回需要等待的时间
+    pub fn try_acquire(&self, class: MethodClass, priority: Priority, now: Instant) -> Result<(), Duration> {
+        let reserve = match priority {
+            Priority::Execution => 0.0,
+            Priority::HotPath => self.hot_path_reserve,
+            Priority::Background => self.background_reserve,
+        };
+        let result = self.bucket(class).try_acquire(reserve, now);
+        if result.is_err() {
+            self.deferred.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// 服务端限流: 该类别在 Retry-After 之前不再发请求
+    pub fn throttle(&self, class: MethodClass, retry_after: Duration, now: Instant) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+        self.bucket(class).block(now + retry_after);
+    }
+
+    pub fn throttled(&self) -> u64 {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    pub fn deferred(&self) -> u64 {
+        self.deferred.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_reserve_and_retry_after() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            send_rps: 10.0,
+            heavy_rps: 10.0,
+            light_rps: 10.0,
+            burst_secs: 1.0,
+            hot_path_reserve: 0.2,
+            background_reserve: 0.5,
+        });
+        let now = Instant::now();
+        let light = MethodClass::Light;
+
+        // 容量 10: 后台任务只能用到剩 5 个，热路径到剩 2 个，执行可以用完
+        for _ in 0..5 {
+            assert!(limiter.try_acquire(light, Priority::Background, now).is_ok());
+        }
+        assert!(limiter.try_acquire(light, Priority::Background, now).is_err());
+        for _ in 0..3 {
+            assert!(limiter.try_acquire(light, Priority::HotPath, now).is_ok());
+        }
+        assert!(limiter.try_acquire(light, Priority::HotPath, now).is_err());
+        for _ in 0..2 {
+            assert!(limiter.try_acquire(light, Priority::Execution, now).is_ok());
+        }
+        let wait = limiter.try_acquire(light, Priority::Execution, now).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(100));
+        // 其他类别的令牌桶互不影响
+        assert!(limiter.try_acquire(MethodClass::Heavy, Priority::Background, now).is_ok());
+        assert_eq!(limiter.deferred(), 3);
+
+        // 100ms 后补回 1 个令牌
+        assert!(limiter.try_acquire(light, Priority::Execution, now + Duration::from_millis(100)).is_ok());
+
+        // 429: Retry-After 期间所有优先级都要等待
+        limiter.throttle(MethodClass::Send, Duration::from_secs(2), now);
+        let wait = limiter.try_acquire(MethodClass::Send, Priority::Execution, now + Duration::from_secs(1)).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(1));
+        assert!(limiter.try_acquire(MethodClass::Send, Priority::Execution, now + Duration::from_secs(2)).is_ok());
+        assert_eq!(limiter.throttled(), 1);
+        assert_eq!(MethodClass::of(RpcRequest::GetProgramAccounts), MethodClass::Heavy);
+        assert_eq!(MethodClass::of(RpcRequest::SendTransaction), MethodClass::Send);
+    }
+}
@@ -1,15 +1,20 @@
-use std::sync::Arc;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use borsh::BorshDeserialize;
-use crate::amm::raydium_v4::AmmState;
+use serde::{Deserialize, Serialize};
+use crate::amm::raydium_v4::{AmmState, read_pubkey, read_u64};
+use crate::amm::serum::{SerumMarketV3, get_vault_signer};
 use solana_sdk::account::Account;
+use crate::core::accounts::AccountLoader;
 
-#[derive(Debug, Clone)]
+/// Raydium V4 swap 需要的全部静态账户
+/// 这些地址在池子生命周期内不会变化，可以缓存并写入 Inventory 快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RaydiumPoolKeys {
     pub id: Pubkey,
     pub base_mint: Pubkey,
     pub quote_mint: Pubkey,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
     pub lp_mint: Pubkey,
     pub base_vault: Pubkey,
     pub quote_vault: Pubkey,
@@ -18,54 +23,94 @@ pub struct RaydiumPoolKeys {
     pub market_program_id: Pubkey,
     pub market_id: Pubkey,
     pub amm_authority: Pubkey, // derived
-    // ... we can add market keys (bids/asks/event_queue) later if needed for full serum swap
-    // Raydium V4 swap needs these.
+    // Serum / OpenBook Market Keys (需要额外拉取 Market 账户，未拉取时为 None)
+    pub market: Option<MarketKeys>,
 }
 
-/// Fetch and decode Raydium AMM State
-pub async fn fetch_raydium_keys(rpc_client: Arc<RpcClient>, pool_id: &Pubkey) -> Option<RaydiumPoolKeys> {
-    match rpc_client.get_account(pool_id).await {
-        Ok(account) => {
-            decode_raydium_keys(pool_id, &account)
-        },
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketKeys {
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub vault_signer: Pubkey,
+}
+
+/// Fetch and decode Raydium AMM State (包含 Market Keys)
+pub async fn fetch_raydium_keys(accounts: &AccountLoader, pool_id: &Pubkey) -> Option<RaydiumPoolKeys> {
+    let mut keys = match accounts.load(pool_id).await {
+        Ok(Some(account)) => decode_raydium_keys(pool_id, &account)?,
+        Ok(None) => {
+            log::error!("❌ Raydium AMM Account Not Found: {}", pool_id);
+            return None;
+        }
         Err(e) => {
             log::error!("❌ Fetch Raydium AMM Account Error: {}", e);
-            None
+            return None;
         }
+    };
+
+    match accounts.load(&keys.market_id).await {
+        Ok(Some(market)) => keys.market = decode_market_keys(&keys.market_id, &keys.market_program_id, &market.data),
+        Ok(None) => log::warn!("⚠️ Serum Market {} Not Found", keys.market_id),
+        Err(e) => log::warn!("⚠️ Fetch Serum Market {} Error: {}", keys.market_id, e),
     }
+    Some(keys)
 }
 
 pub fn decode_raydium_keys(pool_id: &Pubkey, account: &Account) -> Option<RaydiumPoolKeys> {
-    if account.data.len() != AmmState::LEN {
-        log::warn!("⚠️ Raydium AMM State Length Mismatch: Expected {}, Got {}", AmmState::LEN, account.data.len());
-        // In reality, it might be safer to check >= LEN
-        if account.data.len() < AmmState::LEN { return None; }
+    decode_raydium_keys_from_data(pool_id, &account.owner, &account.data)
+}
+
+/// 按链上布局偏移解析静态 Key，`program_id` 为 AMM 账户的 owner
+pub fn decode_raydium_keys_from_data(pool_id: &Pubkey, program_id: &Pubkey, data: &[u8]) -> Option<RaydiumPoolKeys> {
+    if data.len() != AmmState::LEN {
+        log::warn!("⚠️ Raydium AMM State Length Mismatch: Expected {}, Got {}", AmmState::LEN, data.len());
+        if data.len() < AmmState::LEN { return None; }
     }
 
-    let amm_state = match AmmState::try_from_slice(&account.data[..AmmState::LEN]) {
-        Ok(s) => s,
+    // Raydium V4 Authority = PDA(["amm authority", nonce])
+    let nonce = read_u64(data, AmmState::NONCE_OFFSET)? as u8;
+    let amm_authority = Pubkey::create_program_address(&[b"amm authority", &[nonce]], program_id)
+        .unwrap_or_default();
+
+    Some(RaydiumPoolKeys {
+        id: *pool_id,
+        base_mint: read_pubkey(data, AmmState::COIN_MINT_OFFSET)?,
+        quote_mint: read_pubkey(data, AmmState::PC_MINT_OFFSET)?,
+        base_decimals: read_u64(data, AmmState::COIN_DECIMALS_OFFSET)? as u8,
+        quote_decimals: read_u64(data, AmmState::PC_DECIMALS_OFFSET)? as u8,
+        lp_mint: read_pubkey(data, AmmState::LP_MINT_OFFSET)?,
+        base_vault: read_pubkey(data, AmmState::COIN_VAULT_OFFSET)?,
+        quote_vault: read_pubkey(data, AmmState::PC_VAULT_OFFSET)?,
+        open_orders: read_pubkey(data, AmmState::OPEN_ORDERS_OFFSET)?,
+        target_orders: read_pubkey(data, AmmState::TARGET_ORDERS_OFFSET)?,
+        market_program_id: read_pubkey(data, AmmState::MARKET_PROGRAM_OFFSET)?,
+        market_id: read_pubkey(data, AmmState::MARKET_OFFSET)?,
+        amm_authority,
+        market: None,
+    })
+}
+
+/// 解析 Serum Market V3 中 swap 需要的账户
+pub fn decode_market_keys(market_id: &Pubkey, market_program_id: &Pubkey, data: &[u8]) -> Option<MarketKeys> {
+    // 不能用 try_from_slice: Market 账户尾部还有 7 字节 padding
+    let market = match SerumMarketV3::deserialize(&mut &data[..]) {
+        Ok(m) => m,
         Err(e) => {
-            log::error!("❌ Decode Raydium AMM State Error: {}", e);
+            log::error!("❌ Decode Serum Market {} Error: {}", market_id, e);
             return None;
         }
     };
+    let vault_signer = get_vault_signer(market_id, market_program_id, market.vault_signer_nonce).ok()?;
 
-    // Calculate Authority
-    // let (authority, _) = Pubkey::find_program_address(&[b"amm authority"], &program_id);
-    // Hardcode or derive? Raydium V4 authority is usually derived.
-    // For now we assume we just need the state keys.
-
-    Some(RaydiumPoolKeys {
-        id: *pool_id,
-        base_mint: amm_state.coin_mint_address,
-        quote_mint: amm_state.pc_mint_address,
-        lp_mint: amm_state.lp_mint_address,
-        base_vault: amm_state.pool_coin_token_account,
-        quote_vault: amm_state.pool_pc_token_account,
-        open_orders: amm_state.amm_open_orders,
-        target_orders: amm_state.amm_target_orders,
-        market_program_id: amm_state.serum_program_id,
-        market_id: amm_state.serum_market,
-        amm_authority: Pubkey::default(), // TODO: Derive properly if needed
+    Some(MarketKeys {
+        bids: market.bids,
+        asks: market.asks,
+        event_queue: market.event_queue,
+        base_vault: market.base_vault,
+        quote_vault: market.quote_vault,
+        vault_signer,
     })
 }
@@ -1,9 +1,16 @@
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
-use spl_token::state::Mint;
-use solana_sdk::program_pack::Pack;
+use spl_token_2022::extension::ExtensionType;
 use log::{info, warn, error};
-use std::sync::Arc;
+use crate::core::accounts::AccountLoader;
+use crate::token_registry::{decode_mint_state, TokenProgram, TokenRegistry};
+
+// 会让持有者失去对代币控制权的 Token-2022 扩展
+const DANGEROUS_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::NonTransferable,
+    ExtensionType::DefaultAccountState,
+];
 
 // 风险检查结果
 #[derive(Debug)]
@@ -13,43 +20,69 @@ pub struct RiskReport {
     pub freeze_authority: Option<Pubkey>,
     pub supply: u64,
     pub decimals: u8,
+    pub token_program: TokenProgram,
+    pub extensions: Vec<String>,
 }
 
-pub async fn check_token_risk(rpc_client: &Arc<RpcClient>, mint: &Pubkey) -> Option<RiskReport> {
+pub async fn check_token_risk(accounts: &AccountLoader, registry: &TokenRegistry, mint: &Pubkey) -> Option<RiskReport> {
     // 1. 获取 Mint 账户信息
-    match rpc_client.get_account(mint).await {
-        Ok(account) => {
-            // 2. 解析 Mint 数据
-            if let Ok(mint_data) = Mint::unpack(&account.data) {
-                let mut is_safe = true;
-                
-                // 检查 Freeze Authority (必须为 None)
-                if mint_data.freeze_authority.is_some() {
-                    warn!("⚠️ 风险警告: 代币 {} 存在 Freeze Authority!", mint);
-                    is_safe = false;
+    match accounts.load(mint).await {
+        Ok(Some(account)) => {
+            // 2. 解析 Mint 数据 (兼容 SPL Token 与 Token-2022)，顺带写入注册表
+            let (info, mint_data) = match decode_mint_state(mint, &account.owner, &account.data) {
+                Some(decoded) => decoded,
+                None => {
+                    error!("❌ 无法解析 Mint 数据: {}", mint);
+                    return None;
                 }
+            };
+            let label = registry.label(mint);
+            let mut is_safe = true;
+
+            // 检查 Freeze Authority (必须为 None)
+            if mint_data.freeze_authority.is_some() {
+                warn!("⚠️ 风险警告: 代币 {} 存在 Freeze Authority!", label);
+                is_safe = false;
+            }
+
+            // 检查 Mint Authority (最好为 None，但部分新代币可能还没丢弃)
+            if mint_data.mint_authority.is_some() {
+                warn!("⚠️ 风险提示: 代币 {} Mint Authority 尚未丢弃!", label);
+                // 在严格模式下，这可能被视为不安全
+                // is_safe = false;
+            }
 
-                // 检查 Mint Authority (最好为 None，但部分新代币可能还没丢弃)
-                if mint_data.mint_authority.is_some() {
-                    warn!("⚠️ 风险提示: 代币 {} Mint Authority 尚未丢弃!", mint);
-                    // 在严格模式下，这可能被视为不安全
-                    // is_safe = false; 
+            // 检查 Token-2022 扩展
+            for extension in DANGEROUS_EXTENSIONS {
+                if info.has_extension(extension) {
+                    warn!("⚠️ 风险警告: 代币 {} 启用了 Token-2022 扩展 {:?}!", label, extension);
+                    is_safe = false;
                 }
+            }
+            if info.has_extension(ExtensionType::TransferFeeConfig) {
+                warn!("⚠️ 风险提示: 代币 {} 存在转账手续费，报价需扣除", label);
+            }
 
-                let report = RiskReport {
-                    is_safe,
-                    mint_authority: mint_data.mint_authority.into(),
-                    freeze_authority: mint_data.freeze_authority.into(),
-                    supply: mint_data.supply,
-                    decimals: mint_data.decimals,
-                };
-                
-                info!("🛡️ 风险检查报告 [{}]: Safe={}, Auth={:?}", mint, is_safe, report.mint_authority);
-                return Some(report);
+            let report = RiskReport {
+                is_safe,
+                mint_authority: mint_data.mint_authority.into(),
+                freeze_authority: mint_data.freeze_authority.into(),
+                supply: mint_data.supply,
+                decimals: mint_data.decimals,
+                token_program: info.token_program.unwrap_or(TokenProgram::Spl),
+                extensions: info.extensions.clone(),
+            };
+
+            if let Some(prev) = registry.get(mint) {
+                registry.insert(crate::token_registry::TokenInfo { symbol: prev.symbol, name: prev.name, ..info });
             } else {
-                error!("❌ 无法解析 Mint 数据: {}", mint);
+                registry.insert(info);
             }
+
+            info!("🛡️ 风险检查报告 [{}]: Safe={}, Program={:?}, Auth={:?}", label, is_safe, report.token_program, report.mint_authority);
+            return Some(report);
         },
+        Ok(None) => error!("❌ Mint 账户不存在: {}", mint),
         Err(e) => {
             error!("❌ 获取 Mint 账户失败: {} - {}", mint, e);
         }
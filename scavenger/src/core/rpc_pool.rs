@@ -0,0 +1,662 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use log::{info, warn};
+use rand::Rng;
+use solana_client::client_error::{ClientError, ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::{RpcClient as BlockingRpcClient, RpcClientConfig};
+use solana_client::rpc_custom_error::{
+    NodeUnhealthyErrorData, JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+    JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE, JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
+};
+use solana_client::rpc_request::{RpcError, RpcRequest, RpcResponseErrorData};
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+use reqwest::header::{HeaderMap, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tokio::task::JoinHandle;
+use crate::config::{NetworkConfig, RpcEndpointConfig, RpcPoolConfig};
+use crate::core::rate_limit::{MethodClass, Priority, RateLimiter};
+
+/// 错误率滑动平均的权重 (每次请求)
+const ERROR_RATE_ALPHA: f64 = 0.05;
+/// 延迟滑动平均的权重 (每次健康检查)
+const LATENCY_ALPHA: f64 = 0.3;
+/// 每隔多少轮健康检查打印一次节点统计
+const STATS_LOG_EVERY: u64 = 30;
+/// JSON-RPC: 节点不支持该方法
+const JSON_RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+/// RPC 节点承担的请求类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpcRole {
+    /// 账户 / 状态查询
+    Read,
+    /// 发送交易
+    Send,
+    /// 历史交易与区块 (需要完整账本的节点)
+    Historical,
+}
+
+impl FromStr for RpcRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(RpcRole::Read),
+            "send" => Ok(RpcRole::Send),
+            "historical" => Ok(RpcRole::Historical),
+            other => Err(format!("unknown rpc role: {}", other)),
+        }
+    }
+}
+
+impl RpcRole {
+    pub fn for_request(request: RpcRequest) -> Self {
+        match request {
+            RpcRequest::SendTransaction | RpcRequest::RequestAirdrop => RpcRole::Send,
+            RpcRequest::GetTransaction
+            | RpcRequest::GetSignaturesForAddress
+            | RpcRequest::GetBlock
+            | RpcRequest::GetBlocks
+            | RpcRequest::GetBlocksWithLimit
+            | RpcRequest::GetBlockTime => RpcRole::Historical,
+            _ => RpcRole::Read,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Health {
+    /// 健康检查延迟的滑动平均 (毫秒)，None 表示还没检查过
+    latency_ms: Option<f64>,
+    /// 请求失败率的滑动平均
+    error_rate: f64,
+    slot: u64,
+    consecutive_failures: u32,
+}
+
+struct Endpoint {
+    url: String,
+    roles: Vec<RpcRole>,
+    weight: f64,
+    health: Mutex<Health>,
+    limiter: RateLimiter,
+    requests: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl Endpoint {
+    fn record(&self, ok: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let mut health = self.health.lock().unwrap();
+        let sample = if ok { 0.0 } else { 1.0 };
+        health.error_rate += ERROR_RATE_ALPHA * (sample - health.error_rate);
+        if ok {
+            health.consecutive_failures = 0;
+        } else {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+            health.consecutive_failures += 1;
+        }
+    }
+}
+
+/// 单个节点的统计快照
+#[derive(Debug, Clone)]
+pub struct EndpointStats {
+    pub url: String,
+    pub healthy: bool,
+    pub latency_ms: Option<f64>,
+    pub slot_lag: u64,
+    pub error_rate: f64,
+    pub requests: u64,
+    pub failures: u64,
+    /// 节点返回 429 的次数
+    pub throttled: u64,
+    /// 客户端因令牌不足等待 / 让路的次数
+    pub deferred: u64,
+}
+
+struct PoolInner {
+    endpoints: Vec<Endpoint>,
+    config: RpcPoolConfig,
+}
+
+impl PoolInner {
+    fn max_slot(&self) -> u64 {
+        self.endpoints.iter().map(|e| e.health.lock().unwrap().slot).max().unwrap_or(0)
+    }
+
+    fn is_healthy(&self, health: &Health, max_slot: u64) -> bool {
+        let config = &self.config;
+        // 还没检查过 Slot 的节点不按落后判断
+        let lagging = health.slot > 0 && max_slot.saturating_sub(health.slot) > config.max_slot_lag;
+        health.consecutive_failures < config.max_consecutive_failures
+            && health.error_rate <= config.max_error_rate
+            && !lagging
+    }
+
+    /// 权重 × 成功率 / 延迟，延迟未知时按 100ms 计
+    fn score(&self, endpoint: &Endpoint, health: &Health) -> f64 {
+        endpoint.weight.max(0.0) * (1.0 - health.error_rate) / health.latency_ms.unwrap_or(100.0).max(1.0)
+    }
+
+    /// 本次请求依次尝试的节点:
+    /// 健康节点中按评分加权随机选出首选，其余健康节点按评分排在后面，不健康的节点作为最后手段
+    /// 没有节点承担该角色时退回到所有节点
+    fn route(&self, role: RpcRole) -> Vec<usize> {
+        let mut candidates: Vec<usize> = (0..self.endpoints.len())
+            .filter(|&i| self.endpoints[i].roles.contains(&role))
+            .collect();
+        if candidates.is_empty() {
+            candidates = (0..self.endpoints.len()).collect();
+        }
+        let max_slot = self.max_slot();
+        let mut healthy = Vec::new();
+        let mut unhealthy = Vec::new();
+        for i in candidates {
+            let endpoint = &self.endpoints[i];
+            let health = *endpoint.health.lock().unwrap();
+            let score = self.score(endpoint, &health);
+            if self.is_healthy(&health, max_slot) {
+                healthy.push((i, score));
+            } else {
+                unhealthy.push((i, score));
+            }
+        }
+        let by_score = |a: &(usize, f64), b: &(usize, f64)| b.1.total_cmp(&a.1);
+        healthy.sort_by(by_score);
+        unhealthy.sort_by(by_score);
+
+        let total: f64 = healthy.iter().map(|(_, s)| s).sum();
+        if total > 0.0 {
+            let mut pick = rand::thread_rng().gen_range(0.0..total);
+            let first = healthy
+                .iter()
+                .position(|(_, s)| {
+                    pick -= s;
+                    pick < 0.0
+                })
+                .unwrap_or(0);
+            let chosen = healthy.remove(first);
+            healthy.insert(0, chosen);
+        }
+        healthy.into_iter().chain(unhealthy).map(|(i, _)| i).collect()
+    }
+}
+
+/// 是否是节点本身的问题 (网络错误 / 节点不健康 / 缺少数据)，这类错误换一个节点重试
+/// 其他错误 (例如交易模拟失败) 与节点无关，直接返回给调用方
+fn is_endpoint_failure(err: &ClientError) -> bool {
+    match err.kind() {
+        ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcRequestError(_)) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => matches!(
+            *code,
+            JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY
+                | JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE
+                | JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE
+                | JSON_RPC_METHOD_NOT_FOUND
+        ),
+        _ => false,
+    }
+}
+
+/// 节点返回 429 时没有 Retry-After 的默认暂停时间
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+/// Retry-After 上限，避免异常的响应头让节点长时间不可用
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+enum CallError {
+    /// 节点限流 (429)，附带需要暂停的时间
+    Throttled(Duration),
+    Client(Box<ClientError>),
+}
+
+impl From<reqwest::Error> for CallError {
+    fn from(e: reqwest::Error) -> Self {
+        CallError::Client(Box::new(e.into()))
+    }
+}
+
+/// 单个节点的 JSON-RPC 连接
+/// 不使用 HttpSender: 它在内部按 Retry-After 重试 429，限速器看不到限流，也无法改投其他节点
+struct EndpointClient {
+    client: reqwest::Client,
+    url: String,
+    request_id: AtomicU64,
+}
+
+impl EndpointClient {
+    fn new(url: &str, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder().timeout(timeout).build().unwrap_or_else(|_| reqwest::Client::new()),
+            url: url.to_string(),
+            request_id: AtomicU64::new(0),
+        }
+    }
+
+    async fn call(&self, request: RpcRequest, params: serde_json::Value) -> Result<serde_json::Value, CallError> {
+        let id = self.request_id.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .client
+            .post(&self.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(request.build_request_json(id, params).to_string())
+            .send()
+            .await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(CallError::Throttled(retry_after(response.headers())));
+        }
+        let mut json = response.error_for_status()?.json::<serde_json::Value>().await?;
+        if json["error"].is_object() {
+            return Err(CallError::Client(Box::new(rpc_error(&json["error"]))));
+        }
+        Ok(json["result"].take())
+    }
+}
+
+fn retry_after(headers: &HeaderMap) -> Duration {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map_or(DEFAULT_RETRY_AFTER, Duration::from_secs)
+        .min(MAX_RETRY_AFTER)
+}
+
+/// 与 HttpSender 相同的错误解析，调用方 (例如预检失败的日志) 行为不变
+fn rpc_error(error: &serde_json::Value) -> ClientError {
+    let object = match serde_json::from_value::<RpcErrorObject>(error.clone()) {
+        Ok(object) => object,
+        Err(e) => return RpcError::RpcRequestError(format!("Failed to deserialize RPC error response: {} [{}]", error, e)).into(),
+    };
+    let data = match object.code {
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => {
+            match serde_json::from_value::<RpcSimulateTransactionResult>(error["data"].clone()) {
+                Ok(result) => RpcResponseErrorData::SendTransactionPreflightFailure(result),
+                Err(_) => RpcResponseErrorData::Empty,
+            }
+        }
+        JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => match serde_json::from_value::<NodeUnhealthyErrorData>(error["data"].clone()) {
+            Ok(NodeUnhealthyErrorData { num_slots_behind }) => RpcResponseErrorData::NodeUnhealthy { num_slots_behind },
+            Err(_) => RpcResponseErrorData::Empty,
+        },
+        _ => RpcResponseErrorData::Empty,
+    };
+    RpcError::RpcResponseError { code: object.code, message: object.message, data }.into()
+}
+
+/// 挂在 RpcClient 下面的传输层: 按请求类型路由到节点池，节点失败或限流时自动切换
+/// 每个 RpcClient 持有自己的节点连接 (阻塞版 RpcClient 运行在独立的 Runtime 上)，健康状态与限速令牌全池共享
+struct PoolSender {
+    pool: Arc<PoolInner>,
+    clients: Vec<EndpointClient>,
+    /// 该 RpcClient 上读请求的优先级；发送交易总是按执行优先级
+    priority: Priority,
+    stats: Mutex<RpcTransportStats>,
+}
+
+#[async_trait]
+impl RpcSender for PoolSender {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let class = MethodClass::of(request);
+        let priority = if class == MethodClass::Send { Priority::Execution } else { self.priority };
+        let route = self.pool.route(RpcRole::for_request(request));
+        let deadline = Instant::now() + Duration::from_secs(self.pool.config.request_timeout_secs);
+        let mut failed = vec![false; self.pool.endpoints.len()];
+        let mut last_err = None;
+        loop {
+            // 按路由顺序取第一个有令牌的节点；都没有令牌时等待最早可用的那个
+            let now = Instant::now();
+            let mut picked = None;
+            let mut wait: Option<Duration> = None;
+            for &i in route.iter().filter(|&&i| !failed[i]) {
+                match self.pool.endpoints[i].limiter.try_acquire(class, priority, now) {
+                    Ok(()) => {
+                        picked = Some(i);
+                        break;
+                    }
+                    Err(w) => wait = Some(wait.map_or(w, |min| min.min(w))),
+                }
+            }
+            let i = match (picked, wait) {
+                (Some(i), _) => i,
+                (None, Some(wait)) => {
+                    if now + wait > deadline {
+                        last_err = Some(RpcError::ForUser(format!("{} rate limited on all rpc endpoints", request)).into());
+                        break;
+                    }
+                    self.stats.lock().unwrap().rate_limited_time += wait;
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+                (None, None) => break,
+            };
+
+            let endpoint = &self.pool.endpoints[i];
+            let start = Instant::now();
+            let result = self.clients[i].call(request, params.clone()).await;
+            {
+                let mut stats = self.stats.lock().unwrap();
+                stats.request_count += 1;
+                stats.elapsed_time += start.elapsed();
+            }
+            match result {
+                Ok(value) => {
+                    endpoint.record(true);
+                    return Ok(value);
+                }
+                Err(CallError::Throttled(retry_after)) => {
+                    // 限流不计入失败率: 节点本身是好的，只是暂时不能再发
+                    endpoint.limiter.throttle(class, retry_after, Instant::now());
+                    warn!("🐢 [RPC] {} 对 {} 限流 (Retry-After {:?})，切换节点", endpoint.url, request, retry_after);
+                    last_err = Some(RpcError::ForUser(format!("{} rate limited by {}", request, endpoint.url)).into());
+                }
+                Err(CallError::Client(e)) if is_endpoint_failure(&e) => {
+                    endpoint.record(false);
+                    failed[i] = true;
+                    warn!("⚠️ [RPC] {} 请求 {} 失败，切换节点: {}", endpoint.url, request, e);
+                    last_err = Some(*e);
+                }
+                Err(CallError::Client(e)) => {
+                    endpoint.record(true);
+                    return Err(*e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| RpcError::ForUser("no rpc endpoint configured".to_string()).into()))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    fn url(&self) -> String {
+        self.pool.endpoints.iter().map(|e| e.url.as_str()).collect::<Vec<_>>().join(",")
+    }
+}
+
+/// 多 RPC 节点池: 按角色 (读 / 发送 / 历史) 路由，持续健康检查并自动切换；每个节点按方法类别和优先级限速
+/// 通过 `client()` / `client_for()` 得到普通的 RpcClient，现有调用方无需改动
+#[derive(Clone)]
+pub struct RpcPool {
+    inner: Arc<PoolInner>,
+    /// 按 Priority 顺序缓存的共享客户端
+    clients: Arc<[Arc<RpcClient>; 3]>,
+}
+
+impl RpcPool {
+    /// `rpc.endpoints` 为空时退回到 `network.rpc_url`，承担所有角色
+    pub fn new(network: &NetworkConfig, config: &RpcPoolConfig) -> Result<Self, String> {
+        let endpoints = if config.endpoints.is_empty() {
+            vec![RpcEndpointConfig { url: network.rpc_url.clone(), ..Default::default() }]
+        } else {
+            config.endpoints.clone()
+        };
+        let endpoints = endpoints
+            .iter()
+            .map(|e| {
+                if e.url.is_empty() {
+                    return Err("rpc endpoint url is empty".to_string());
+                }
+                Ok(Endpoint {
+                    url: e.url.clone(),
+                    roles: e.roles()?,
+                    weight: e.weight,
+                    health: Mutex::new(Health::default()),
+                    limiter: RateLimiter::new(e.rate_limit.as_ref().unwrap_or(&config.rate_limit)),
+                    requests: AtomicU64::new(0),
+                    failures: AtomicU64::new(0),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let inner = Arc::new(PoolInner { endpoints, config: config.clone() });
+        let client = |priority| {
+            Arc::new(RpcClient::new_sender(Self::sender(&inner, priority), RpcClientConfig::with_commitment(CommitmentConfig::default())))
+        };
+        let clients = Arc::new([client(Priority::Execution), client(Priority::HotPath), client(Priority::Background)]);
+        Ok(Self { inner, clients })
+    }
+
+    fn sender(inner: &Arc<PoolInner>, priority: Priority) -> PoolSender {
+        PoolSender {
+            pool: inner.clone(),
+            clients: Self::endpoint_clients(inner),
+            priority,
+            stats: Mutex::new(RpcTransportStats::default()),
+        }
+    }
+
+    fn endpoint_clients(inner: &PoolInner) -> Vec<EndpointClient> {
+        let timeout = Duration::from_secs(inner.config.request_timeout_secs);
+        inner.endpoints.iter().map(|e| EndpointClient::new(&e.url, timeout)).collect()
+    }
+
+    /// 共享的异步 RpcClient (热路径优先级)
+    pub fn client(&self) -> Arc<RpcClient> {
+        self.client_for(Priority::HotPath)
+    }
+
+    /// 指定读请求优先级的 RpcClient: 后台任务使用 Background，令牌紧张时给热路径和交易发送让路
+    pub fn client_for(&self, priority: Priority) -> Arc<RpcClient> {
+        self.clients[priority as usize].clone()
+    }
+
+    /// 阻塞版 RpcClient (启动阶段使用)
+    pub fn blocking_client(&self) -> BlockingRpcClient {
+        BlockingRpcClient::new_sender(Self::sender(&self.inner, Priority::HotPath), RpcClientConfig::with_commitment(CommitmentConfig::default()))
+    }
+
+    pub fn healthy_count(&self) -> usize {
+        let max_slot = self.inner.max_slot();
+        self.inner
+            .endpoints
+            .iter()
+            .filter(|e| self.inner.is_healthy(&e.health.lock().unwrap(), max_slot))
+            .count()
+    }
+
+    /// 对所有节点做一轮健康检查 (getSlot)，更新延迟、Slot 与失败计数
+    pub async fn check_health(&self) {
+        let clients = Self::endpoint_clients(&self.inner);
+        self.check_with(&clients).await;
+    }
+
+    async fn check_with(&self, clients: &[EndpointClient]) {
+        let max_slot = self.inner.max_slot();
+        let before: Vec<bool> = self
+            .inner
+            .endpoints
+            .iter()
+            .map(|e| self.inner.is_healthy(&e.health.lock().unwrap(), max_slot))
+            .collect();
+
+        // 健康检查是后台流量: 没有令牌时跳过本轮，限流不算失败
+        let params = serde_json::json!([CommitmentConfig::processed()]);
+        let checks = self.inner.endpoints.iter().zip(clients).map(|(endpoint, client)| {
+            let params = params.clone();
+            async move {
+                let class = MethodClass::of(RpcRequest::GetSlot);
+                endpoint.limiter.try_acquire(class, Priority::Background, Instant::now()).ok()?;
+                let start = Instant::now();
+                let slot = match client.call(RpcRequest::GetSlot, params).await {
+                    Ok(value) => serde_json::from_value::<u64>(value).map_err(|e| ClientError::from(e).to_string()),
+                    Err(CallError::Throttled(retry_after)) => {
+                        endpoint.limiter.throttle(class, retry_after, Instant::now());
+                        return None;
+                    }
+                    Err(CallError::Client(e)) => Err(e.to_string()),
+                };
+                Some((slot, start.elapsed()))
+            }
+        });
+        let results = futures::future::join_all(checks).await;
+
+        for (endpoint, result) in self.inner.endpoints.iter().zip(results) {
+            let (slot, elapsed) = match result {
+                Some(result) => result,
+                None => continue,
+            };
+            match slot {
+                Ok(slot) => {
+                    endpoint.record(true);
+                    let mut health = endpoint.health.lock().unwrap();
+                    let ms = elapsed.as_secs_f64() * 1000.0;
+                    health.latency_ms = Some(match health.latency_ms {
+                        Some(avg) => avg + LATENCY_ALPHA * (ms - avg),
+                        None => ms,
+                    });
+                    health.slot = health.slot.max(slot);
+                }
+                Err(e) => {
+                    endpoint.record(false);
+                    log::debug!("[RPC] 健康检查失败 {}: {}", endpoint.url, e);
+                }
+            }
+        }
+
+        let max_slot = self.inner.max_slot();
+        for (endpoint, was_healthy) in self.inner.endpoints.iter().zip(before) {
+            let health = *endpoint.health.lock().unwrap();
+            match (was_healthy, self.inner.is_healthy(&health, max_slot)) {
+                (true, false) => warn!("🔻 [RPC] 节点 {} 不健康 (落后 {} Slot / 错误率 {:.0}% / 连续失败 {})，请求将切换到其他节点",
+                    endpoint.url, max_slot.saturating_sub(health.slot), health.error_rate * 100.0, health.consecutive_failures),
+                (false, true) => info!("🔺 [RPC] 节点 {} 已恢复", endpoint.url),
+                _ => {}
+            }
+        }
+    }
+
+    /// 启动后台健康检查
+    pub fn spawn_health_checks(&self) -> JoinHandle<()> {
+        let pool = self.clone();
+        let interval = Duration::from_millis(self.inner.config.health_check_interval_ms.max(100));
+        tokio::spawn(async move {
+            let clients = Self::endpoint_clients(&pool.inner);
+            let mut tick = tokio::time::interval(interval);
+            tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut rounds = 0u64;
+            loop {
+                tick.tick().await;
+                pool.check_with(&clients).await;
+                rounds += 1;
+                if rounds % STATS_LOG_EVERY == 0 {
+                    pool.log_stats();
+                }
+            }
+        })
+    }
+
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        let max_slot = self.inner.max_slot();
+        self.inner
+            .endpoints
+            .iter()
+            .map(|e| {
+                let health = *e.health.lock().unwrap();
+                EndpointStats {
+                    url: e.url.clone(),
+                    healthy: self.inner.is_healthy(&health, max_slot),
+                    latency_ms: health.latency_ms,
+                    slot_lag: if health.slot > 0 { max_slot - health.slot } else { 0 },
+                    error_rate: health.error_rate,
+                    requests: e.requests.load(Ordering::Relaxed),
+                    failures: e.failures.load(Ordering::Relaxed),
+                    throttled: e.limiter.throttled(),
+                    deferred: e.limiter.deferred(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn log_stats(&self) {
+        for s in self.stats() {
+            info!("🛰️ [RPC] {} | {} | 延迟 {:?}ms | 落后 {} Slot | 错误率 {:.1}% | 请求 {} (失败 {}) | 限流 {} | 让路 {}",
+                s.url, if s.healthy { "健康" } else { "不健康" }, s.latency_ms.map(|l| l.round()),
+                s.slot_lag, s.error_rate * 100.0, s.requests, s.failures, s.throttled, s.deferred);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(url: &str, roles: &[&str]) -> RpcEndpointConfig {
+        RpcEndpointConfig {
+            url: url.to_string(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            weight: 1.0,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_route_by_role_and_health() {
+        let network = NetworkConfig { rpc_url: String::new(), ws_url: String::new(), grpc_url: String::new() };
+        let pool = RpcPool::new(&network, &RpcPoolConfig {
+            endpoints: vec![
+                endpoint("http://a", &["read", "send"]),
+                endpoint("http://b", &["read"]),
+                endpoint("http://c", &["read", "historical"]),
+            ],
+            ..Default::default()
+        })
+        .unwrap();
+        let inner = &pool.inner;
+        let set = |i: usize, latency: f64, slot: u64| {
+            let mut h = inner.endpoints[i].health.lock().unwrap();
+            h.latency_ms = Some(latency);
+            h.slot = slot;
+        };
+        set(0, 10.0, 1000);
+        set(1, 50.0, 1000);
+        set(2, 20.0, 900); // 落后 100 Slot
+
+        assert_eq!(inner.route(RpcRole::Send), vec![0]);
+        assert_eq!(pool.healthy_count(), 2);
+        // 落后的节点只作为最后手段
+        let read = inner.route(RpcRole::Read);
+        assert_eq!(read.len(), 3);
+        assert_eq!(read[2], 2);
+        // 历史请求只有 c 能处理，即使它不健康也要尝试
+        assert_eq!(inner.route(RpcRole::Historical), vec![2]);
+
+        // a 连续失败后发送请求仍路由到 a (唯一的 send 节点)，读请求优先走 b
+        for _ in 0..3 {
+            inner.endpoints[0].record(false);
+        }
+        assert_eq!(inner.route(RpcRole::Read), vec![1, 0, 2]);
+        assert_eq!(inner.route(RpcRole::Send), vec![0]);
+        assert_eq!(RpcRole::for_request(RpcRequest::GetTransaction), RpcRole::Historical);
+        assert_eq!(RpcRole::for_request(RpcRequest::GetMultipleAccounts), RpcRole::Read);
+
+        let node_unhealthy: ClientError = RpcError::RpcResponseError {
+            code: JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY,
+            message: "behind".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        }
+        .into();
+        assert!(is_endpoint_failure(&node_unhealthy));
+        assert!(!is_endpoint_failure(&RpcError::ForUser("bad params".to_string()).into()));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), DEFAULT_RETRY_AFTER);
+        headers.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(retry_after(&headers), Duration::from_secs(3));
+        headers.insert(RETRY_AFTER, "3600".parse().unwrap());
+        assert_eq!(retry_after(&headers), MAX_RETRY_AFTER);
+    }
+}
@@ -24,6 +24,7 @@ impl SwapInstructionData {
 }
 
 // 构建 Swap 指令
+#[allow(clippy::too_many_arguments)]
 pub fn swap(
     program_id: &Pubkey,
     amm_id: &Pubkey,
@@ -123,6 +124,7 @@ impl OrcaSwapInstructionData {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn build_orca_swap(
     program_id: &Pubkey,
     token_program: &Pubkey,
@@ -1,6 +1,9 @@
 pub mod amm;
 pub mod config;
 pub mod scout;
+pub mod bus;
 pub mod core;
 pub mod strategies;
 pub mod state;
+pub mod snapshot;
+pub mod token_registry;
@@ -1,12 +1,18 @@
 use clap::Parser;
-use scavenger_lib::{config, scout, core, state};
+use scavenger_lib::{bus, config, scout, core, state, snapshot, strategies};
+use scavenger_lib::core::accounts::AccountLoader;
+use scavenger_lib::core::blockhash::BlockhashCache;
+use scavenger_lib::core::latency::LatencyTracker;
+use scavenger_lib::core::leader::LeaderTracker;
+use scavenger_lib::core::rate_limit::Priority;
+use scavenger_lib::core::rpc_pool::RpcPool;
+use scavenger_lib::scout::backfill::{self, Until};
 use config::AppConfig;
 use scout::Scout;
 use state::Inventory;
 use log::{info, error, warn};
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::signature::{Keypair, Signer};
-use solana_client::rpc_client::RpcClient;
-use solana_client::nonblocking::rpc_client::RpcClient as NonBlockingRpcClient;
 use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use std::error::Error;
 use std::path::Path;
@@ -15,13 +21,21 @@ use std::sync::Arc;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// Strategy to run (e.g., "arb", "sniper")
+    /// Strategies to run, comma separated (e.g., "arb", "sniper", "arb,sniper")
     #[arg(short, long, default_value = "arb")]
     strategy: String,
 
     /// Path to config file
     #[arg(short, long, default_value = "config.toml")]
     config: String,
+
+    /// Backfill pools created while the bot was down, save the snapshot and exit
+    #[arg(long)]
+    backfill: bool,
+
+    /// Backfill back to this signature or slot instead of the saved checkpoint
+    #[arg(long)]
+    backfill_from: Option<String>,
 }
 
 #[tokio::main]
@@ -58,10 +72,23 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     };
     
-    // 3. 初始化 RPC 客户端并检查连接
-    info!("正在连接 RPC 节点: {}", config.network.rpc_url);
-    let rpc_client = Arc::new(RpcClient::new(config.network.rpc_url.clone()));
-    
+    // 3. 初始化 RPC 节点池并检查连接 (至少一个节点健康即可启动)
+    let rpc_pool = match RpcPool::new(&config.network, &config.rpc) {
+        Ok(pool) => pool,
+        Err(e) => {
+            error!("❌ RPC 配置无效: {}", e);
+            return Ok(());
+        }
+    };
+    rpc_pool.check_health().await;
+    rpc_pool.log_stats();
+    if rpc_pool.healthy_count() == 0 {
+        error!("❌ 没有可用的 RPC 节点");
+        return Ok(());
+    }
+    rpc_pool.spawn_health_checks();
+    let rpc_client = Arc::new(rpc_pool.blocking_client());
+
     match rpc_client.get_version() {
         Ok(v) => info!("✅ RPC 连接成功 (Version: {})", v.solana_core),
         Err(e) => {
@@ -104,22 +131,123 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // 鉴权钱包 (通常与交易钱包相同，或者是单独的)
     let auth_keypair = Arc::new(read_keypair_from_file(&config.jito.auth_keypair_path)?);
 
+    // API 池子列表缓存 (需在任何拉取之前初始化)
+    scout::pool_source::PoolSource::init(&config.pool_source);
+
     // 5. 初始化 Phase 2.5: 数据层 (Inventory)
     info!("🧠 正在构建全网代币索引 (Inventory)...");
     let inventory = Arc::new(Inventory::new());
 
+    // Warm Start: 优先从磁盘快照恢复，API 数据在后台对账
+    let snapshot_path = config.inventory.snapshot_path.clone();
+    if !snapshot::warm_start(&inventory, &snapshot_path) {
+        info!("🧊 未找到可用的 Inventory 快照，执行 Cold Start");
+    }
+    // 代币元数据注册表: 本地 Token List + 后台批量解析链上 Mint / Metadata
+    if let Some(path) = &config.inventory.token_list_path {
+        match inventory.tokens().load_token_list(path) {
+            Ok(n) => info!("🏷️ 已从 Token List 加载 {} 个代币名称", n),
+            Err(e) => warn!("⚠️ 加载 Token List 失败 {}: {}", path, e),
+        }
+    }
+    // Mint / Metadata 走后台连接，与策略的账户加载分开
+    let token_accounts = AccountLoader::new(
+        rpc_pool.client_for(Priority::Background),
+        &config.account_loader,
+        CommitmentConfig::confirmed(),
+    );
+    inventory.tokens().spawn_resolver(token_accounts, std::time::Duration::from_millis(200));
+
+    // 历史回填: --backfill 单独运行后退出；backfill.on_startup 时在后台与侦察系统并行
+    let backfill_from = match args.backfill_from.as_deref().map(str::parse::<Until>).transpose() {
+        Ok(until) => until,
+        Err(e) => {
+            error!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    if args.backfill {
+        match backfill::run(rpc_pool.client_for(Priority::Background), inventory.clone(), &config.backfill, &config.discovery, backfill_from).await {
+            Ok(_) => match snapshot::save(&inventory.to_snapshot(), &snapshot_path) {
+                Ok(()) => info!("💾 Inventory 快照已保存: {}", snapshot_path),
+                Err(e) => error!("❌ 保存 Inventory 快照失败: {}", e),
+            },
+            Err(e) => error!("❌ 回填失败: {}", e),
+        }
+        return Ok(());
+    }
+    if config.backfill.on_startup || backfill_from.is_some() {
+        let (client, inv) = (rpc_pool.client_for(Priority::Background), inventory.clone());
+        let (backfill_config, discovery_config) = (config.backfill.clone(), config.discovery.clone());
+        tokio::spawn(async move {
+            if let Err(e) = backfill::run(client, inv, &backfill_config, &discovery_config, backfill_from).await {
+                error!("❌ 回填失败: {}", e);
+            }
+        });
+    }
+
+    snapshot::spawn_writer(
+        inventory.clone(),
+        snapshot_path,
+        std::time::Duration::from_secs(config.inventory.snapshot_interval_secs),
+    );
+
     // 异步启动 Cold Start 全量加载
     let inv_clone = inventory.clone();
-    let rpc_url_clone = config.network.rpc_url.clone();
+    let rpc_client_nb = rpc_pool.client_for(Priority::Background);
     tokio::spawn(async move {
-        let rpc_client_nb = Arc::new(NonBlockingRpcClient::new(rpc_url_clone));
         scout::orca::load_all_whirlpools(rpc_client_nb, inv_clone).await;
     });
 
-    // 6. 启动 Phase 2: 侦察系统 (Scout)
+    // 6. 启动策略: 各策略在事件总线上订阅自己关心的事件 (需在侦察系统之前)
+    let bus = bus::EventBus::new(&config.bus);
+    let strategy_rpc = rpc_pool.client();
+    let leaders = match LeaderTracker::new(&config.leader) {
+        Ok(t) => t,
+        Err(e) => {
+            error!("❌ Leader 配置无效: {}", e);
+            return Ok(());
+        }
+    };
+    leaders.spawn(strategy_rpc.clone(), &bus);
+    let blockhash = BlockhashCache::new(&config.blockhash);
+    blockhash.spawn(strategy_rpc.clone());
+    // 对手盘状态按账户推送的确认级别读取，和推送来的一侧保持一致
+    let commitment = match config.commitment.resolve() {
+        Ok(c) => c,
+        Err(e) => {
+            error!("❌ Commitment 配置无效: {}", e);
+            return Ok(());
+        }
+    };
+    let accounts = AccountLoader::new(strategy_rpc.clone(), &config.account_loader, commitment.accounts);
+    accounts.spawn();
+    let latency = LatencyTracker::new(&config.latency);
+    latency.spawn_reporter();
+    let ctx = strategies::StrategyContext {
+        rpc_client: strategy_rpc,
+        keypair: auth_keypair.clone(),
+        config: Arc::new(config.strategy.clone()),
+        inventory: inventory.clone(),
+        leaders,
+        blockhash,
+        accounts,
+        latency,
+    };
+    for name in args.strategy.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match strategies::spawn(name, ctx.clone(), &bus) {
+            Ok(_) => info!("🧩 策略已启动: {}", name),
+            Err(e) => {
+                error!("❌ 无法启动策略: {}", e);
+                return Ok(());
+            }
+        }
+    }
+
+    // 7. 启动 Phase 2: 侦察系统 (Scout)
     info!("正在初始化侦察系统 (Phase 2)...");
     
-    let mut scout = Scout::new(&config, &auth_keypair, inventory, args.strategy).await?;
+    let mut scout = Scout::new(&config, rpc_pool.client(), inventory, bus).await?;
     scout.start().await;
     
     Ok(())
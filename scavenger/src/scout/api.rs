@@ -1,49 +1,28 @@
-use reqwest::Client;
 use serde_json::Value;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
-use log::{info, warn, error};
-use std::fs::File;
-use std::io::{Read, Write};
-use std::path::Path;
+use std::sync::Arc;
+use log::{info, warn};
+use crate::scout::pool_source::PoolSource;
 
 #[derive(Debug, Clone)]
 pub struct PoolInfo {
     pub address: Pubkey,
     pub token_a: Pubkey,
     pub token_b: Pubkey,
+    /// 池子 TVL (USD)，API 未提供时为 0
+    pub tvl: f64,
 }
 
 pub async fn fetch_raydium_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::Error + Send + Sync>> {
     info!("🌐 Fetching Raydium pools...");
-    let client = Client::new();
     let url = "https://api.raydium.io/v2/main/pairs";
-    let cache_file = "raydium_pairs.json";
-    
-    // Try to fetch from API
-    let json_result = client.get(url).send().await;
-    
-    let json: Value = match json_result {
-        Ok(resp) => {
-            match resp.json().await {
-                Ok(v) => {
-                    // Save to cache
-                    if let Ok(mut file) = File::create(cache_file) {
-                        if let Ok(content) = serde_json::to_string(&v) {
-                            let _ = file.write_all(content.as_bytes());
-                        }
-                    }
-                    v
-                },
-                Err(e) => {
-                    warn!("⚠️ Failed to parse Raydium API response: {}", e);
-                    load_from_cache(cache_file).await?
-                }
-            }
-        },
+
+    let json = match PoolSource::global().fetch_json("raydium_pairs", url).await {
+        Ok(v) => v,
         Err(e) => {
-            warn!("⚠️ Failed to fetch Raydium pools from API: {}. Trying cache...", e);
-            load_from_cache(cache_file).await?
+            warn!("⚠️ Failed to fetch Raydium pools: {}. Using hardcoded fallback for SOL/USDC.", e);
+            Arc::new(fallback_raydium_pools()?)
         }
     };
     
@@ -54,6 +33,7 @@ pub async fn fetch_raydium_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::
             let amm_id_str = pair.get("ammId").and_then(|v| v.as_str());
             let base_mint_str = pair.get("baseMint").and_then(|v| v.as_str());
             let quote_mint_str = pair.get("quoteMint").and_then(|v| v.as_str());
+            let tvl = pair.get("liquidity").and_then(|v| v.as_f64()).unwrap_or_default();
             
             if let (Some(addr), Some(mint_a), Some(mint_b)) = (amm_id_str, base_mint_str, quote_mint_str) {
                 if let (Ok(address), Ok(token_a), Ok(token_b)) = (
@@ -65,6 +45,7 @@ pub async fn fetch_raydium_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::
                         address,
                         token_a,
                         token_b,
+                        tvl,
                     });
                 }
             }
@@ -75,36 +56,23 @@ pub async fn fetch_raydium_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::
     Ok(pools)
 }
 
-async fn load_from_cache(path: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-    if Path::new(path).exists() {
-        let mut file = File::open(path)?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        let json: Value = serde_json::from_str(&contents)?;
-        info!("📂 Loaded Raydium pools from local cache: {}", path);
-        Ok(json)
-    } else {
-        warn!("⚠️ Cache file not found. Using hardcoded fallback for SOL/USDC.");
-        // Hardcoded fallback for SOL/USDC (Mainnet)
-        let fallback_json = r#"[
-            {
-                "ammId": "58oQChx4yWmvKdwLLZzBi4ChoCcTKqdJennsXZGhPG43",
-                "baseMint": "So11111111111111111111111111111111111111112",
-                "quoteMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
-            }
-        ]"#;
-        let json: Value = serde_json::from_str(fallback_json)?;
-        Ok(json)
-    }
+fn fallback_raydium_pools() -> Result<Value, serde_json::Error> {
+    // Hardcoded fallback for SOL/USDC (Mainnet)
+    let fallback_json = r#"[
+        {
+            "ammId": "58oQChx4yWmvKdwLLZzBi4ChoCcTKqdJennsXZGhPG43",
+            "baseMint": "So11111111111111111111111111111111111111112",
+            "quoteMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"
+        }
+    ]"#;
+    serde_json::from_str(fallback_json)
 }
 
 pub async fn fetch_orca_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::Error + Send + Sync>> {
     info!("🌐 Fetching Orca pools...");
-    let client = Client::new();
     let url = "https://api.mainnet.orca.so/v1/whirlpool/list";
     
-    let resp = client.get(url).send().await?;
-    let json: Value = resp.json().await?;
+    let json = PoolSource::global().fetch_json("orca_whirlpools", url).await?;
     
     let mut pools = Vec::new();
     
@@ -113,6 +81,7 @@ pub async fn fetch_orca_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::Err
             let address_str = pool.get("address").and_then(|v| v.as_str());
             let token_a_str = pool.get("tokenA").and_then(|v| v.get("mint")).and_then(|v| v.as_str());
             let token_b_str = pool.get("tokenB").and_then(|v| v.get("mint")).and_then(|v| v.as_str());
+            let tvl = pool.get("tvl").and_then(|v| v.as_f64()).unwrap_or_default();
             
             if let (Some(addr), Some(mint_a), Some(mint_b)) = (address_str, token_a_str, token_b_str) {
                 if let (Ok(address), Ok(token_a), Ok(token_b)) = (
@@ -124,6 +93,7 @@ pub async fn fetch_orca_pools() -> Result<Vec<PoolInfo>, Box<dyn std::error::Err
                         address,
                         token_a,
                         token_b,
+                        tvl,
                     });
                 }
             }
@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use futures::StreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::config::{BackfillConfig, DiscoveryConfig};
+use crate::scout::{orca, raydium, tx};
+use crate::state::Inventory;
+
+/// getSignaturesForAddress 单页上限
+const PAGE_LIMIT: usize = 1000;
+
+/// 回填进度: 上一次回填时每个地址最新的签名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cursor {
+    pub signature: String,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub raydium: Option<Cursor>,
+    pub orca: Option<Cursor>,
+}
+
+impl Checkpoint {
+    /// 文件不存在或损坏时从头开始 (回溯深度由 max_signatures 限定)
+    pub fn load(path: &str) -> Self {
+        if !Path::new(path).exists() {
+            return Self::default();
+        }
+        match fs::read(path).map_err(|e| e.to_string()).and_then(|b| serde_json::from_slice(&b).map_err(|e| e.to_string())) {
+            Ok(checkpoint) => checkpoint,
+            Err(e) => {
+                warn!("⚠️ 回填 Checkpoint 无法读取，忽略: {} - {}", path, e);
+                Self::default()
+            }
+        }
+    }
+
+    /// 先写临时文件再 rename，避免进程中途退出留下半个文件
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let tmp_path = format!("{}.tmp", path);
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
+
+/// 回溯终点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    Signature(Signature),
+    Slot(u64),
+}
+
+impl FromStr for Until {
+    type Err = String;
+
+    /// 纯数字按 Slot 解析，否则按签名解析
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(slot) = s.parse::<u64>() {
+            return Ok(Until::Slot(slot));
+        }
+        Signature::from_str(s)
+            .map(Until::Signature)
+            .map_err(|e| format!("invalid backfill checkpoint {}: {}", s, e))
+    }
+}
+
+impl Until {
+    /// Checkpoint 中的签名交给 RPC 截断；同时用 Slot 兜底，签名已被节点裁剪时不会一直回溯
+    fn bounds(cursor: Option<&Cursor>) -> (Option<Signature>, Option<u64>) {
+        match cursor {
+            Some(c) => (Signature::from_str(&c.signature).ok(), Some(c.slot)),
+            None => (None, None),
+        }
+    }
+}
+
+/// 回填出的池子 (Pool, TokenMintA, TokenMintB)
+type CreatedPool = (Pubkey, Pubkey, Pubkey);
+
+/// 回填结果
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BackfillReport {
+    pub scanned: usize,
+    pub raydium_pools: usize,
+    pub orca_pools: usize,
+    pub new_pairs: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dex {
+    Raydium,
+    Orca,
+}
+
+/// 保留一页中终点之后 (更新) 的成功交易；返回值第二项表示是否已到达终点
+fn take_until(
+    page: &[RpcConfirmedTransactionStatusWithSignature],
+    min_slot: Option<u64>,
+) -> (Vec<String>, bool) {
+    let mut kept = Vec::new();
+    for entry in page {
+        if min_slot.is_some_and(|slot| entry.slot < slot) {
+            return (kept, true);
+        }
+        if entry.err.is_none() {
+            kept.push(entry.signature.clone());
+        }
+    }
+    (kept, false)
+}
+
+/// 一次回溯的结果
+#[derive(Debug, Default)]
+struct Walk {
+    /// 需要解码的签名 (新到旧)
+    signatures: Vec<String>,
+    /// 本次看到的最新签名 (下次回填的终点)
+    newest: Option<Cursor>,
+    /// 是否回溯到了终点 (没有被 max_signatures 截断)
+    complete: bool,
+}
+
+impl Walk {
+    /// 可以把 Checkpoint 前移到 `newest` 的条件: 中间没有遗漏
+    /// - 有终点时必须回溯到终点；没有终点 (首次回填) 时 max_signatures 本来就是回溯深度
+    /// - 所有交易都拉取成功
+    fn advance_to(&self, bounded: bool, failed: usize) -> Option<&Cursor> {
+        if failed > 0 || (bounded && !self.complete) {
+            return None;
+        }
+        self.newest.as_ref()
+    }
+}
+
+/// 从最新往回翻页，直到终点签名 / Slot 或 max_signatures
+async fn walk(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+    until_signature: Option<Signature>,
+    min_slot: Option<u64>,
+    max_signatures: usize,
+) -> Result<Walk, Box<dyn std::error::Error + Send + Sync>> {
+    let mut walk = Walk::default();
+    let mut before = None;
+    let mut seen = 0;
+    loop {
+        let page = rpc_client
+            .get_signatures_for_address_with_config(address, GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: until_signature,
+                limit: Some(PAGE_LIMIT),
+                commitment: Some(CommitmentConfig::confirmed()),
+            })
+            .await?;
+        let last = match page.last() {
+            Some(last) => last,
+            None => {
+                walk.complete = true;
+                break;
+            }
+        };
+        if walk.newest.is_none() {
+            walk.newest = page.first().map(|e| Cursor { signature: e.signature.clone(), slot: e.slot });
+        }
+        seen += page.len();
+        let (kept, reached) = take_until(&page, min_slot);
+        walk.signatures.extend(kept);
+        if reached || page.len() < PAGE_LIMIT {
+            walk.complete = true;
+            break;
+        }
+        if seen >= max_signatures {
+            warn!("⚠️ [Backfill] {} 已回溯 {} 个签名仍未到达终点，停止 (可调大 backfill.max_signatures)", address, seen);
+            break;
+        }
+        before = Some(Signature::from_str(&last.signature)?);
+    }
+    Ok(walk)
+}
+
+/// 回填停机期间创建的池子:
+/// 对 Raydium / Orca 的扫描地址回溯 getSignaturesForAddress 到 Checkpoint (或 `until` 指定的签名 / Slot)，
+/// 用现有解析器解码建池交易，写入 Inventory，并保存新的 Checkpoint
+pub async fn run(
+    rpc_client: Arc<RpcClient>,
+    inventory: Arc<Inventory>,
+    config: &BackfillConfig,
+    discovery_config: &DiscoveryConfig,
+    until: Option<Until>,
+) -> Result<BackfillReport, Box<dyn std::error::Error + Send + Sync>> {
+    let quote_mints = discovery_config.quote_mints()?;
+    let mut checkpoint = Checkpoint::load(&config.checkpoint_path);
+    let mut report = BackfillReport::default();
+
+    // 先回填 Orca，Raydium 新池才能和回填出的 Orca 池子组成套利对
+    for dex in [Dex::Orca, Dex::Raydium] {
+        let (address, cursor) = match dex {
+            Dex::Raydium => (&config.raydium_address, checkpoint.raydium.as_ref()),
+            Dex::Orca => (&config.orca_address, checkpoint.orca.as_ref()),
+        };
+        let address = Pubkey::from_str(address).map_err(|e| format!("invalid backfill address {}: {}", address, e))?;
+        let (until_signature, min_slot) = match until {
+            Some(Until::Signature(sig)) => (Some(sig), None),
+            Some(Until::Slot(slot)) => (None, Some(slot)),
+            None => Until::bounds(cursor),
+        };
+
+        info!("⏪ [Backfill] {:?}: 回溯 {} (终点: 签名 {:?} / Slot {:?})", dex, address, until_signature, min_slot);
+        let mut walk = walk(&rpc_client, &address, until_signature, min_slot, config.max_signatures).await?;
+        report.scanned += walk.signatures.len();
+
+        // 拉取失败 (Err) 与拉到了但不是建池交易 (Ok(None)) 分开统计，前者会阻止 Checkpoint 前移
+        let results: Vec<Result<Option<CreatedPool>, String>> = futures::stream::iter(std::mem::take(&mut walk.signatures))
+            .map(|sig| {
+                let client = rpc_client.clone();
+                async move {
+                    let tx = match tx::fetch_transaction(client, &sig, 5, CommitmentConfig::confirmed()).await {
+                        Some(tx) => tx,
+                        None => return Err(sig),
+                    };
+                    Ok(match dex {
+                        Dex::Raydium => raydium::parse_transaction(&sig, &tx).map(|e| (e.pool_id, e.token_a, e.token_b)),
+                        Dex::Orca => orca::parse_transaction(&sig, &tx).map(|e| (e.pool_id, e.token_a, e.token_b)),
+                    })
+                }
+            })
+            .buffer_unordered(config.fetch_concurrency.max(1))
+            .collect()
+            .await;
+        let failed = results.iter().filter(|r| r.is_err()).count();
+        let found: Vec<CreatedPool> = results.into_iter().filter_map(|r| r.ok().flatten()).collect();
+
+        for (pool, token_a, token_b) in &found {
+            match dex {
+                Dex::Raydium => {
+                    let (base, quote) = if quote_mints.contains(token_a) { (*token_b, *token_a) } else { (*token_a, *token_b) };
+                    if inventory.pair_raydium_pool(base, quote, *pool) {
+                        report.new_pairs += 1;
+                    }
+                }
+                Dex::Orca => inventory.add_pool(*token_a, *token_b, *pool),
+            }
+        }
+        match dex {
+            Dex::Raydium => report.raydium_pools += found.len(),
+            Dex::Orca => report.orca_pools += found.len(),
+        }
+        info!("⏪ [Backfill] {:?}: 解码出 {} 个建池交易", dex, found.len());
+
+        // 回溯到终点且所有交易都拉取成功才前移 Checkpoint，否则保留旧的终点，下次重新覆盖这段区间
+        let bounded = until_signature.is_some() || min_slot.is_some();
+        match walk.advance_to(bounded, failed) {
+            Some(newest) => {
+                match dex {
+                    Dex::Raydium => checkpoint.raydium = Some(newest.clone()),
+                    Dex::Orca => checkpoint.orca = Some(newest.clone()),
+                }
+                checkpoint.save(&config.checkpoint_path)?;
+            }
+            None => warn!("⚠️ [Backfill] {:?}: 未回溯到终点 ({}) 或 {} 笔交易拉取失败，保留旧的 Checkpoint",
+                dex, if walk.complete { "已到达" } else { "被截断" }, failed),
+        }
+    }
+
+    info!("✅ [Backfill] 完成: 扫描 {} 笔交易，Raydium 新池 {} / Orca 新池 {} / 新套利对 {}",
+        report.scanned, report.raydium_pools, report.orca_pools, report.new_pairs);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+
+    fn entry(slot: u64, failed: bool) -> RpcConfirmedTransactionStatusWithSignature {
+        RpcConfirmedTransactionStatusWithSignature {
+            signature: format!("sig{}", slot),
+            slot,
+            err: failed.then_some(TransactionError::AccountInUse),
+            memo: None,
+            block_time: None,
+            confirmation_status: None,
+        }
+    }
+
+    #[test]
+    fn test_take_until_and_checkpoint_round_trip() {
+        // 新到旧，失败的交易不需要解码
+        let page = vec![entry(105, false), entry(104, true), entry(103, false), entry(99, false)];
+        assert_eq!(take_until(&page, Some(100)), (vec!["sig105".to_string(), "sig103".to_string()], true));
+        assert_eq!(take_until(&page, None).0.len(), 3);
+        assert!(!take_until(&page, None).1);
+
+        assert_eq!("250000000".parse::<Until>(), Ok(Until::Slot(250_000_000)));
+        let sig = Signature::new_unique();
+        assert_eq!(sig.to_string().parse::<Until>(), Ok(Until::Signature(sig)));
+        assert!("not-a-signature".parse::<Until>().is_err());
+
+        let path = std::env::temp_dir().join(format!("backfill-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert!(Checkpoint::load(path).raydium.is_none());
+        let checkpoint = Checkpoint { raydium: Some(Cursor { signature: sig.to_string(), slot: 42 }), orca: None };
+        checkpoint.save(path).unwrap();
+        let loaded = Checkpoint::load(path);
+        assert_eq!(Until::bounds(loaded.raydium.as_ref()), (Some(sig), Some(42)));
+        assert!(loaded.orca.is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cut_off_walk_keeps_checkpoint() {
+        // 第一页是满页 (1000 个)，max_signatures 到此为止，终点 Slot 1 还远没有到达
+        let page: Vec<RpcConfirmedTransactionStatusWithSignature> = (0..PAGE_LIMIT as u64)
+            .map(|i| RpcConfirmedTransactionStatusWithSignature {
+                signature: Signature::new_unique().to_string(),
+                ..entry(2_000 - i, false)
+            })
+            .collect();
+        let mocks = std::collections::HashMap::from([(
+            solana_client::rpc_request::RpcRequest::GetSignaturesForAddress,
+            serde_json::to_value(&page).unwrap(),
+        )]);
+        let rpc_client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        let walk = walk(&rpc_client, &Pubkey::new_unique(), None, Some(1), PAGE_LIMIT).await.unwrap();
+        assert_eq!(walk.signatures.len(), PAGE_LIMIT);
+        assert!(!walk.complete);
+        assert_eq!(walk.newest.as_ref().unwrap().slot, 2_000);
+        assert!(walk.advance_to(true, 0).is_none());
+        // 首次回填 (没有终点) 的深度本来就由 max_signatures 限定
+        assert!(walk.advance_to(false, 0).is_some());
+
+        let done = Walk { complete: true, ..walk };
+        assert_eq!(done.advance_to(true, 0).unwrap().slot, 2_000);
+        // 有交易拉取失败时不前移
+        assert!(done.advance_to(true, 1).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use log::info;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::config::DedupConfig;
+use crate::scout::events::{EventSource, SlotStatus, StreamEvent};
+
+/// 去重键: 交易按签名，账户按 (地址, Slot, 数据哈希)，Slot 按 (Slot, 确认级别)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DedupKey {
+    Transaction(Signature),
+    Account { pubkey: Pubkey, slot: u64, data_hash: u64 },
+    Slot { slot: u64, status: SlotStatus },
+}
+
+impl DedupKey {
+    fn of(event: &StreamEvent) -> (Self, u64) {
+        match event {
+            StreamEvent::Transaction(u) => (DedupKey::Transaction(u.signature), u.slot),
+            StreamEvent::Account(u) => {
+                let mut hasher = DefaultHasher::new();
+                u.data.hash(&mut hasher);
+                (DedupKey::Account { pubkey: u.pubkey, slot: u.slot, data_hash: hasher.finish() }, u.slot)
+            }
+            StreamEvent::Slot(u) => (DedupKey::Slot { slot: u.slot, status: u.status }, u.slot),
+        }
+    }
+}
+
+struct Entry {
+    key: DedupKey,
+    slot: u64,
+    at: Instant,
+}
+
+/// 单个数据源的抢先统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourceStats {
+    /// 最先送达的次数
+    pub wins: u64,
+    /// 其他数据源已送达后才到的次数
+    pub duplicates: u64,
+    /// 早于 Slot 窗口被丢弃的次数
+    pub stale: u64,
+}
+
+/// 事件去重: 同一笔交易 / 同一次账户写入可能从多条 WebSocket 连接、多个订阅 (Raydium / Orca 日志) 或 Geyser 重复到达
+/// 放在事件总线之前，只让第一次到达的事件进入后续处理；记录按时间和 Slot 双重限定，内存有上界
+pub struct Deduplicator {
+    config: DedupConfig,
+    seen: HashSet<DedupKey>,
+    /// 按到达顺序排列，用于淘汰
+    order: VecDeque<Entry>,
+    highest_slot: u64,
+    stats: HashMap<EventSource, SourceStats>,
+}
+
+impl Deduplicator {
+    pub fn new(config: &DedupConfig) -> Self {
+        Self {
+            config: config.clone(),
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            highest_slot: 0,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// 首次到达返回 true；重复或过期的事件返回 false
+    pub fn accept(&mut self, event: &StreamEvent) -> bool {
+        self.accept_at(event, Instant::now())
+    }
+
+    fn accept_at(&mut self, event: &StreamEvent, now: Instant) -> bool {
+        self.evict(now);
+        let (key, slot) = DedupKey::of(event);
+        let stats = self.stats.entry(event.source()).or_default();
+        if slot.saturating_add(self.config.max_slot_age) < self.highest_slot {
+            stats.stale += 1;
+            return false;
+        }
+        if !self.seen.insert(key) {
+            stats.duplicates += 1;
+            return false;
+        }
+        stats.wins += 1;
+        self.highest_slot = self.highest_slot.max(slot);
+        self.order.push_back(Entry { key, slot, at: now });
+        true
+    }
+
+    fn evict(&mut self, now: Instant) {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        while let Some(front) = self.order.front() {
+            let expired = now.duration_since(front.at) > ttl
+                || front.slot.saturating_add(self.config.max_slot_age) < self.highest_slot
+                || self.order.len() > self.config.max_entries;
+            if !expired {
+                break;
+            }
+            self.seen.remove(&front.key);
+            self.order.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn stats(&self) -> Vec<(EventSource, SourceStats)> {
+        self.stats.iter().map(|(source, stats)| (*source, *stats)).collect()
+    }
+
+    pub fn log_stats(&self) {
+        for (source, stats) in self.stats() {
+            let total = stats.wins + stats.duplicates;
+            let win_rate = if total > 0 { stats.wins as f64 / total as f64 * 100.0 } else { 0.0 };
+            info!("🪞 [Dedup] {:?} | 首达 {} ({:.1}%) | 重复 {} | 过期 {} | 记录 {}",
+                source, stats.wins, win_rate, stats.duplicates, stats.stale, self.len());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scout::events::{AccountUpdate, TransactionUpdate};
+
+    fn tx(signature: Signature, slot: u64, source: EventSource) -> StreamEvent {
+        StreamEvent::Transaction(TransactionUpdate {
+            signature,
+            slot,
+            account_keys: Vec::new(),
+            logs: Vec::new(),
+            is_failed: false,
+            source,
+            received_at: Instant::now(),
+        })
+    }
+
+    fn account(pubkey: Pubkey, slot: u64, data: Vec<u8>, source: EventSource) -> StreamEvent {
+        StreamEvent::Account(AccountUpdate { pubkey, owner: None, lamports: 1, data, slot, source, received_at: Instant::now() })
+    }
+
+    #[test]
+    fn test_dedup_by_key_slot_and_time() {
+        let mut dedup = Deduplicator::new(&DedupConfig { ttl_secs: 30, max_slot_age: 10, max_entries: 100 });
+        let now = Instant::now();
+        let sig = Signature::new_unique();
+        let pool = Pubkey::new_unique();
+
+        assert!(dedup.accept_at(&tx(sig, 100, EventSource::Geyser), now));
+        assert!(!dedup.accept_at(&tx(sig, 100, EventSource::WebSocket), now));
+        assert!(!dedup.accept_at(&tx(sig, 100, EventSource::WebSocket), now));
+
+        // 同一 Slot 内数据不同的写入都要保留
+        assert!(dedup.accept_at(&account(pool, 100, vec![1], EventSource::WebSocket), now));
+        assert!(!dedup.accept_at(&account(pool, 100, vec![1], EventSource::Geyser), now));
+        assert!(dedup.accept_at(&account(pool, 100, vec![2], EventSource::Geyser), now));
+
+        let stats: HashMap<_, _> = dedup.stats().into_iter().collect();
+        assert_eq!((stats[&EventSource::Geyser].wins, stats[&EventSource::Geyser].duplicates), (2, 1));
+        assert_eq!((stats[&EventSource::WebSocket].wins, stats[&EventSource::WebSocket].duplicates), (1, 2));
+
+        // Slot 前进后，窗口外的旧事件直接丢弃，对应记录被淘汰
+        assert!(dedup.accept_at(&tx(Signature::new_unique(), 120, EventSource::Geyser), now));
+        assert!(!dedup.accept_at(&tx(Signature::new_unique(), 105, EventSource::WebSocket), now));
+        assert_eq!(dedup.stats().into_iter().find(|(s, _)| *s == EventSource::WebSocket).unwrap().1.stale, 1);
+        assert_eq!(dedup.len(), 1);
+
+        // 超过保留时间的记录被淘汰，同一签名可以再次通过
+        let sig = Signature::new_unique();
+        assert!(dedup.accept_at(&tx(sig, 121, EventSource::Geyser), now));
+        assert!(dedup.accept_at(&tx(sig, 121, EventSource::Geyser), now + Duration::from_secs(31)));
+    }
+}
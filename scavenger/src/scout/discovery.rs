@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_client::rpc_request::MAX_MULTIPLE_ACCOUNTS;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use crate::amm::orca_whirlpool::Whirlpool;
+use crate::amm::raydium_v4::AmmState;
+use crate::amm::unix_now;
+use crate::config::DiscoveryConfig;
+use crate::core::raydium_keys::decode_raydium_keys_from_data;
+use crate::scout::api::{fetch_orca_pools, fetch_raydium_pools, PoolInfo};
+use crate::scout::monitor::{ORCA_WHIRLPOOL, RAYDIUM_AMM_V4};
+use crate::state::{Inventory, PoolKeys};
+use tokio::task::JoinHandle;
+
+type DiscoveryResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// 池子发现的数据来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscoverySource {
+    /// getProgramAccounts 直接从链上枚举
+    OnChain,
+    /// Raydium / Orca 官方 REST API
+    Api,
+}
+
+impl FromStr for DiscoverySource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "onchain" => Ok(DiscoverySource::OnChain),
+            "api" => Ok(DiscoverySource::Api),
+            other => Err(format!("unknown discovery source: {}", other)),
+        }
+    }
+}
+
+/// 一个数据源发现的池子
+#[derive(Debug, Default)]
+pub struct Discovered {
+    pub raydium: Vec<PoolInfo>,
+    pub orca: Vec<PoolInfo>,
+    /// 部分请求失败时为 false，这时不能据此删除 Inventory 中消失的池子
+    pub complete: bool,
+}
+
+/// 按配置的数据源发现池子并写入 Inventory
+/// 各数据源互相独立，只要有一个成功就继续；全部失败才返回错误
+pub async fn load_inventory(
+    rpc_client: Arc<RpcClient>,
+    inventory: &Inventory,
+    config: &DiscoveryConfig,
+) -> DiscoveryResult<usize> {
+    let quote_mints = config.quote_mints()?;
+    let mut ray_pools: HashMap<Pubkey, PoolInfo> = HashMap::new();
+    let mut orca_pools: HashMap<Pubkey, PoolInfo> = HashMap::new();
+    let mut succeeded = 0;
+    let mut complete = true;
+    let sources = config.sources()?;
+
+    for source in sources.iter().copied() {
+        let result = match source {
+            DiscoverySource::OnChain => discover_onchain(&rpc_client, inventory, &quote_mints).await,
+            DiscoverySource::Api => discover_api().await,
+        };
+        match result {
+            Ok(found) => {
+                info!("🔎 [Discovery] {:?}: {} Raydium / {} Orca 池子", source, found.raydium.len(), found.orca.len());
+                // 后面的数据源只补充缺失的池子或 TVL，不覆盖已有条目
+                merge(&mut ray_pools, found.raydium);
+                merge(&mut orca_pools, found.orca);
+                complete &= found.complete;
+                succeeded += 1;
+            }
+            Err(e) => {
+                warn!("⚠️ [Discovery] {:?} 数据源失败: {}", source, e);
+                complete = false;
+            }
+        }
+    }
+
+    if succeeded == 0 {
+        return Err("all discovery sources failed".into());
+    }
+
+    let normalize_all = |pools: HashMap<Pubkey, PoolInfo>| -> Vec<PoolInfo> {
+        pools.into_values().map(|p| normalize(p, &quote_mints)).collect()
+    };
+    // 只有所有数据源的所有请求都成功时才是完整列表，才能据此删除消失的套利对
+    Ok(inventory.ingest_pools(normalize_all(ray_pools), normalize_all(orca_pools), complete))
+}
+
+fn merge(into: &mut HashMap<Pubkey, PoolInfo>, pools: Vec<PoolInfo>) {
+    for p in pools {
+        let entry = into.entry(p.address).or_insert_with(|| p.clone());
+        if entry.tvl == 0.0 {
+            entry.tvl = p.tvl;
+        }
+    }
+}
+
+/// 调整 token_a / token_b 顺序，使 token_a 为 Base Token (非 Quote 一侧)
+fn normalize(mut pool: PoolInfo, quote_mints: &[Pubkey]) -> PoolInfo {
+    if quote_mints.contains(&pool.token_a) && !quote_mints.contains(&pool.token_b) {
+        std::mem::swap(&mut pool.token_a, &mut pool.token_b);
+    }
+    pool
+}
+
+async fn discover_api() -> DiscoveryResult<Discovered> {
+    let (ray_pools, orca_pools) = tokio::join!(fetch_raydium_pools(), fetch_orca_pools());
+    Ok(Discovered { raydium: ray_pools?, orca: orca_pools?, complete: true })
+}
+
+/// 链上发现: 按账户大小 + Mint 偏移的 memcmp 过滤，只拉取与 Quote Token 配对的池子
+/// 解析出的静态 Key 和生命周期会直接写入 Inventory
+/// 单个 getProgramAccounts 失败 (常见于超时) 只跳过这一组，其余照常发现
+pub async fn discover_onchain(
+    rpc_client: &RpcClient,
+    inventory: &Inventory,
+    quote_mints: &[Pubkey],
+) -> DiscoveryResult<Discovered> {
+    let raydium_program = Pubkey::from_str(RAYDIUM_AMM_V4)?;
+    let whirlpool_program = Pubkey::from_str(ORCA_WHIRLPOOL)?;
+
+    let mut ray_pools = Vec::new();
+    let mut orca_pools = Vec::new();
+    let mut failed = 0;
+    let mut fetch = |result: DiscoveryResult<Vec<(Pubkey, Account)>>, program: &str, quote: &Pubkey| match result {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            warn!("⚠️ [Discovery] getProgramAccounts 失败 ({} / Quote {}): {}", program, quote, e);
+            failed += 1;
+            Vec::new()
+        }
+    };
+
+    for quote in quote_mints {
+        // Quote Token 可能在任意一侧
+        for offset in [AmmState::PC_MINT_OFFSET, AmmState::COIN_MINT_OFFSET] {
+            let accounts = fetch(
+                fetch_program_accounts(rpc_client, &raydium_program, AmmState::LEN, offset, quote).await,
+                "Raydium",
+                quote,
+            );
+            for (address, account) in accounts {
+                let keys = match decode_raydium_keys_from_data(&address, &raydium_program, &account.data) {
+                    Some(k) => k,
+                    None => continue,
+                };
+                inventory.observe_pool_account(&address, &account.data);
+                ray_pools.push(PoolInfo {
+                    address,
+                    token_a: keys.base_mint,
+                    token_b: keys.quote_mint,
+                    tvl: 0.0,
+                });
+                inventory.set_pool_keys(PoolKeys::Raydium(Box::new(keys)));
+            }
+        }
+
+        for offset in [Whirlpool::TOKEN_MINT_B_OFFSET, Whirlpool::TOKEN_MINT_A_OFFSET] {
+            let accounts = fetch(
+                fetch_program_accounts(rpc_client, &whirlpool_program, Whirlpool::LEN, offset, quote).await,
+                "Whirlpool",
+                quote,
+            );
+            for (address, account) in accounts {
+                let keys = match Whirlpool::decode_keys(&address, &account.data) {
+                    Some(k) => k,
+                    None => continue,
+                };
+                inventory.observe_pool_account(&address, &account.data);
+                orca_pools.push(PoolInfo {
+                    address,
+                    token_a: keys.token_mint_a,
+                    token_b: keys.token_mint_b,
+                    tvl: 0.0,
+                });
+                inventory.set_pool_keys(PoolKeys::Whirlpool(keys));
+            }
+        }
+    }
+
+    if failed > 0 && ray_pools.is_empty() && orca_pools.is_empty() {
+        return Err(format!("all {} getProgramAccounts calls failed", failed).into());
+    }
+    Ok(Discovered { raydium: ray_pools, orca: orca_pools, complete: failed == 0 })
+}
+
+/// 定期重查 Disabled / Drained 的池子: 它们已被退订，只能主动拉取账户
+/// 恢复为可交易的池子会在下一轮排序时重新进入监控名单
+pub fn spawn_lifecycle_recheck(rpc_client: Arc<RpcClient>, inventory: Arc<Inventory>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        tick.tick().await;
+        loop {
+            tick.tick().await;
+            let pools = inventory.inactive_pools();
+            let mut revived = 0;
+            for chunk in pools.chunks(MAX_MULTIPLE_ACCOUNTS) {
+                let accounts = match rpc_client.get_multiple_accounts(chunk).await {
+                    Ok(accounts) => accounts,
+                    Err(e) => {
+                        warn!("⚠️ [Discovery] 重查池子状态失败: {}", e);
+                        continue;
+                    }
+                };
+                for (pool, account) in chunk.iter().zip(accounts) {
+                    let account = match account {
+                        Some(account) => account,
+                        None => continue,
+                    };
+                    if let Some(state) = inventory.observe_pool_account(pool, &account.data) {
+                        if state.is_tradable(unix_now()) {
+                            info!("♻️ [Discovery] 池子 {} 已恢复为 {:?}", pool, state);
+                            revived += 1;
+                        }
+                    }
+                }
+            }
+            if revived > 0 {
+                info!("♻️ [Discovery] {} / {} 个不可交易的池子已恢复", revived, pools.len());
+            }
+        }
+    })
+}
+
+async fn fetch_program_accounts(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    data_size: usize,
+    mint_offset: usize,
+    mint: &Pubkey,
+) -> DiscoveryResult<Vec<(Pubkey, Account)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(vec![
+            RpcFilterType::DataSize(data_size as u64),
+            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(mint_offset, mint.as_ref())),
+        ]),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(CommitmentConfig::confirmed()),
+            ..Default::default()
+        },
+        with_context: None,
+    };
+    Ok(rpc_client.get_program_accounts_with_config(program_id, config).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(address: Pubkey, token_a: Pubkey, token_b: Pubkey, tvl: f64) -> PoolInfo {
+        PoolInfo { address, token_a, token_b, tvl }
+    }
+
+    #[test]
+    fn test_merge_dedup_and_normalize() {
+        let (sol, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pools = HashMap::new();
+        // 链上发现没有 TVL
+        merge(&mut pools, vec![pool(a, sol, mint, 0.0), pool(b, mint, sol, 0.0), pool(a, sol, mint, 0.0)]);
+        assert_eq!(pools.len(), 2);
+        // API 补充 TVL，但不覆盖已有条目的其他字段；已有 TVL 的不被覆盖
+        merge(&mut pools, vec![pool(a, mint, sol, 1_000.0), pool(b, mint, sol, 5.0)]);
+        merge(&mut pools, vec![pool(a, mint, sol, 9_999.0)]);
+        assert_eq!(pools.len(), 2);
+        assert_eq!((pools[&a].token_a, pools[&a].tvl), (sol, 1_000.0));
+        assert_eq!(pools[&b].tvl, 5.0);
+
+        // Quote 在 token_a 一侧时交换顺序
+        let normalized = normalize(pools[&a].clone(), &[sol]);
+        assert_eq!((normalized.token_a, normalized.token_b), (mint, sol));
+        let normalized = normalize(pools[&b].clone(), &[sol]);
+        assert_eq!((normalized.token_a, normalized.token_b), (mint, sol));
+    }
+}
@@ -0,0 +1,88 @@
+use std::time::Instant;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+/// 各条数据流使用的确认级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamCommitment {
+    /// 账户推送 (WebSocket accountSubscribe / Geyser) 与重连补拉
+    pub accounts: CommitmentConfig,
+    /// 程序日志推送
+    pub logs: CommitmentConfig,
+    /// 拉取完整交易 (getTransaction 不支持 processed)
+    pub transactions: CommitmentConfig,
+}
+
+/// 事件来源 (WebSocket 与 Geyser 共用同一套事件类型)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventSource {
+    WebSocket,
+    Geyser,
+}
+
+/// Slot 所处的确认级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlotStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    /// 节点判定该 Slot 已死 (不会被确认)，目前只有 Geyser 推送
+    Dead,
+}
+
+/// 账户数据更新
+#[derive(Debug, Clone)]
+pub struct AccountUpdate {
+    pub pubkey: Pubkey,
+    /// WebSocket accountSubscribe 推送中不一定有 owner，此时为 None
+    pub owner: Option<Pubkey>,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub slot: u64,
+    pub source: EventSource,
+    /// 进程收到该事件的时间，用于端到端延迟统计
+    pub received_at: Instant,
+}
+
+/// 交易更新 (WebSocket logsSubscribe 只有签名和日志，Geyser 额外带账户列表)
+#[derive(Debug, Clone)]
+pub struct TransactionUpdate {
+    pub signature: Signature,
+    pub slot: u64,
+    pub account_keys: Vec<Pubkey>,
+    pub logs: Vec<String>,
+    pub is_failed: bool,
+    pub source: EventSource,
+    /// 进程收到该事件的时间，用于端到端延迟统计
+    pub received_at: Instant,
+}
+
+/// Slot 状态更新
+#[derive(Debug, Clone)]
+pub struct SlotUpdate {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    pub status: SlotStatus,
+    pub source: EventSource,
+    /// 进程收到该事件的时间，用于端到端延迟统计
+    pub received_at: Instant,
+}
+
+/// 侦察层产出的统一事件
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    Account(AccountUpdate),
+    Transaction(TransactionUpdate),
+    Slot(SlotUpdate),
+}
+
+impl StreamEvent {
+    pub fn source(&self) -> EventSource {
+        match self {
+            StreamEvent::Account(u) => u.source,
+            StreamEvent::Transaction(u) => u.source,
+            StreamEvent::Slot(u) => u.source,
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use log::{debug, info};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use crate::scout::events::{AccountUpdate, EventSource, SlotStatus, SlotUpdate, StreamEvent};
+
+/// 单个账户的已确认版本和尚未确认的各 Slot 版本
+#[derive(Default)]
+struct AccountHistory {
+    confirmed: Option<AccountUpdate>,
+    pending: BTreeMap<u64, AccountUpdate>,
+}
+
+impl AccountHistory {
+    /// 当前对外可见的版本 (最新的未确认版本，没有时为已确认版本)
+    fn view_slot(&self) -> Option<u64> {
+        self.pending.keys().next_back().copied().or(self.confirmed.as_ref().map(|c| c.slot))
+    }
+
+    fn view(&self) -> Option<&AccountUpdate> {
+        self.pending.values().next_back().or(self.confirmed.as_ref())
+    }
+}
+
+/// 分叉被放弃后需要回滚的账户
+#[derive(Debug, Clone)]
+pub struct Rollback {
+    pub pubkey: Pubkey,
+    /// 被放弃的版本所在 Slot
+    pub abandoned_slot: u64,
+    /// 回滚后的状态 (仍存活分叉上的最新版本或已确认版本)；账户只在被放弃的分叉上出现过时为 None
+    pub restored: Option<AccountUpdate>,
+}
+
+/// 分叉统计
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForkStats {
+    pub confirmed_slot: u64,
+    pub abandoned_slots: u64,
+    /// 被丢弃的账户版本 (含晚到的、属于已放弃分叉的推送)
+    pub dropped_updates: u64,
+    pub rollbacks: u64,
+    pub pending_accounts: usize,
+}
+
+/// 按 processed 推送账户时的分叉跟踪
+/// 每个账户按 Slot 保留未确认的版本；Slot 确认后沿父链判定哪些 Slot 已被放弃，
+/// 丢弃这些 Slot 上的版本，受影响的账户回滚到仍然有效的状态
+pub struct ForkTracker {
+    /// 超出该深度的未确认数据不再保留
+    max_pending_slots: u64,
+    /// processed Slot 的父 Slot
+    parents: BTreeMap<u64, u64>,
+    /// 确认链上的 Slot (窗口内)
+    canonical: BTreeSet<u64>,
+    /// 已知被放弃的 Slot (窗口内)
+    dead: BTreeSet<u64>,
+    confirmed_slot: u64,
+    accounts: HashMap<Pubkey, AccountHistory>,
+    stats: ForkStats,
+}
+
+impl ForkTracker {
+    pub fn new(max_pending_slots: u64) -> Self {
+        Self {
+            max_pending_slots: max_pending_slots.max(1),
+            parents: BTreeMap::new(),
+            canonical: BTreeSet::new(),
+            dead: BTreeSet::new(),
+            confirmed_slot: 0,
+            accounts: HashMap::new(),
+            stats: ForkStats::default(),
+        }
+    }
+
+    /// 沿父链向上，先遇到已放弃的 Slot 即属于被放弃的分叉
+    /// 遇到确认链或父链未知时按有效处理 (漏掉 Slot 推送不应导致丢数据)
+    fn is_abandoned(&self, slot: u64) -> bool {
+        let mut slot = slot;
+        loop {
+            if self.dead.contains(&slot) {
+                return true;
+            }
+            if self.canonical.contains(&slot) {
+                return false;
+            }
+            match self.parents.get(&slot) {
+                Some(parent) => slot = *parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// 记录账户推送；属于已放弃分叉的推送返回 false，不应再交给下游
+    pub fn observe_account(&mut self, update: &AccountUpdate) -> bool {
+        if self.is_abandoned(update.slot) {
+            self.stats.dropped_updates += 1;
+            return false;
+        }
+        let history = self.accounts.entry(update.pubkey).or_default();
+        if update.slot <= self.confirmed_slot {
+            // 确认链上晚到的推送直接作为已确认版本
+            if history.confirmed.as_ref().map_or(true, |c| c.slot <= update.slot) {
+                history.confirmed = Some(update.clone());
+            }
+        } else {
+            history.pending.insert(update.slot, update.clone());
+        }
+        true
+    }
+
+    /// 记录 Slot 推送；Slot 确认时返回需要回滚的账户
+    pub fn observe_slot(&mut self, update: &SlotUpdate) -> Vec<Rollback> {
+        if let Some(parent) = update.parent {
+            self.parents.insert(update.slot, parent);
+        }
+        match update.status {
+            SlotStatus::Confirmed | SlotStatus::Finalized if update.slot > self.confirmed_slot => self.confirm(update.slot),
+            SlotStatus::Dead if update.slot > self.confirmed_slot => self.abandon(update.slot),
+            _ => Vec::new(),
+        }
+    }
+
+    fn confirm(&mut self, slot: u64) -> Vec<Rollback> {
+        let previous = self.confirmed_slot;
+        // 沿父链走到上一个确认 Slot；父链中断时，断点之前的 Slot 无法判定
+        let mut chain = BTreeSet::from([slot]);
+        let mut floor = slot;
+        while let Some(&parent) = self.parents.get(&floor) {
+            if parent <= previous {
+                floor = previous + 1;
+                break;
+            }
+            chain.insert(parent);
+            floor = parent;
+        }
+        let dead: Vec<u64> = self
+            .parents
+            .range(floor..=slot)
+            .map(|(s, _)| *s)
+            .filter(|s| !chain.contains(s))
+            .collect();
+        if !dead.is_empty() {
+            debug!("[Forks] Slot {} 确认，放弃 Slot {:?}", slot, dead);
+        }
+        // 已死 Slot 的后代在报告 DEAD 时已经放弃过，不重复计数
+        self.stats.abandoned_slots += dead.iter().filter(|s| !self.is_abandoned(**s)).count() as u64;
+        self.dead.extend(dead);
+        self.canonical.extend(chain);
+        self.confirmed_slot = slot;
+        let rollbacks = self.settle(Some(slot));
+        self.prune();
+        rollbacks
+    }
+
+    /// 节点报告 Slot 已死 (如 Geyser 的 DEAD 状态)，不必等确认即可放弃它及其后代上的版本
+    fn abandon(&mut self, slot: u64) -> Vec<Rollback> {
+        if self.canonical.contains(&slot) || !self.dead.insert(slot) {
+            return Vec::new();
+        }
+        debug!("[Forks] Slot {} 已死，放弃该分叉", slot);
+        self.stats.abandoned_slots += 1;
+        self.settle(None)
+    }
+
+    /// 丢弃已放弃分叉上的版本，`confirmed` 时把确认链上的最新版本转为已确认版本
+    /// 对外可见的版本被丢弃的账户需要回滚
+    fn settle(&mut self, confirmed: Option<u64>) -> Vec<Rollback> {
+        let mut rollbacks = Vec::new();
+        let mut dropped = 0;
+        let accounts: Vec<Pubkey> = self.accounts.keys().copied().collect();
+        for pubkey in accounts {
+            let slots: Vec<u64> = self.accounts[&pubkey].pending.keys().copied().collect();
+            let view_before = self.accounts[&pubkey].view_slot();
+            let dead: Vec<u64> = slots.iter().copied().filter(|s| self.is_abandoned(*s)).collect();
+            let history = self.accounts.get_mut(&pubkey).unwrap();
+            for s in &dead {
+                history.pending.remove(s);
+            }
+            dropped += dead.len();
+            // 确认链上的最新版本成为已确认版本
+            if let Some(slot) = confirmed {
+                let confirmed: Vec<u64> = history.pending.range(..=slot).map(|(s, _)| *s).collect();
+                if let Some(&latest) = confirmed.last() {
+                    let update = history.pending.remove(&latest).unwrap();
+                    history.confirmed = Some(update);
+                    for s in &confirmed[..confirmed.len() - 1] {
+                        history.pending.remove(s);
+                    }
+                }
+            }
+            if let Some(view) = view_before.filter(|v| dead.contains(v)) {
+                rollbacks.push(Rollback { pubkey, abandoned_slot: view, restored: history.view().cloned() });
+            }
+        }
+        self.stats.dropped_updates += dropped as u64;
+        self.stats.rollbacks += rollbacks.len() as u64;
+        rollbacks
+    }
+
+    fn prune(&mut self) {
+        let horizon = self.confirmed_slot.saturating_sub(self.max_pending_slots);
+        self.parents = self.parents.split_off(&horizon);
+        self.canonical = self.canonical.split_off(&horizon);
+        self.dead = self.dead.split_off(&horizon);
+        for history in self.accounts.values_mut() {
+            history.pending = history.pending.split_off(&horizon);
+        }
+        // 已确认版本也只保留窗口内的，长期没有推送的账户不再占用内存
+        self.accounts.retain(|_, h| !h.pending.is_empty() || h.confirmed.as_ref().is_some_and(|c| c.slot >= horizon));
+    }
+
+    /// 账户退订后丢弃它的所有版本
+    pub fn unwatch(&mut self, pubkey: &Pubkey) {
+        self.accounts.remove(pubkey);
+    }
+
+    pub fn stats(&self) -> ForkStats {
+        ForkStats {
+            confirmed_slot: self.confirmed_slot,
+            pending_accounts: self.accounts.values().filter(|h| !h.pending.is_empty()).count(),
+            ..self.stats
+        }
+    }
+
+    pub fn log_stats(&self) {
+        let s = self.stats();
+        info!("🍴 [Forks] 确认 Slot {} | 放弃 Slot {} | 丢弃推送 {} | 回滚 {} | 待确认账户 {}",
+            s.confirmed_slot, s.abandoned_slots, s.dropped_updates, s.rollbacks, s.pending_accounts);
+    }
+}
+
+/// WebSocket slotSubscribe 只推送 processed Slot，定期查询 confirmed Slot 补上确认事件
+pub fn spawn_confirmed_slots(rpc_client: Arc<RpcClient>, interval: Duration, events: mpsc::Sender<StreamEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tick.tick().await;
+            let slot = match rpc_client.get_slot_with_commitment(CommitmentConfig::confirmed()).await {
+                Ok(slot) => slot,
+                Err(e) => {
+                    debug!("[Forks] 查询 confirmed Slot 失败: {}", e);
+                    continue;
+                }
+            };
+            let update = SlotUpdate {
+                slot,
+                parent: None,
+                status: SlotStatus::Confirmed,
+                source: EventSource::WebSocket,
+                received_at: Instant::now(),
+            };
+            if events.send(StreamEvent::Slot(update)).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(pubkey: Pubkey, slot: u64, data: u8) -> AccountUpdate {
+        AccountUpdate {
+            pubkey,
+            owner: None,
+            lamports: 1,
+            data: vec![data],
+            slot,
+            source: EventSource::Geyser,
+            received_at: Instant::now(),
+        }
+    }
+
+    fn slot(slot: u64, parent: Option<u64>, status: SlotStatus) -> SlotUpdate {
+        SlotUpdate { slot, parent, status, source: EventSource::Geyser, received_at: Instant::now() }
+    }
+
+    #[test]
+    fn test_rollback_abandoned_fork() {
+        let mut forks = ForkTracker::new(100);
+        let pool = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        // 10 已确认；之后分叉: 10 -> 11 -> 13 (最终确认) 与 10 -> 12 (被放弃)
+        forks.observe_slot(&slot(10, Some(9), SlotStatus::Processed));
+        assert!(forks.observe_account(&account(pool, 10, 1)));
+        assert!(forks.observe_slot(&slot(10, None, SlotStatus::Confirmed)).is_empty());
+        for (s, parent) in [(11, 10), (12, 10), (13, 11)] {
+            forks.observe_slot(&slot(s, Some(parent), SlotStatus::Processed));
+        }
+        assert!(forks.observe_account(&account(pool, 11, 2)));
+        assert!(forks.observe_account(&account(pool, 12, 3))); // 幽灵流动性
+        assert!(forks.observe_account(&account(other, 12, 9))); // 只在被放弃的分叉上出现
+
+        let rollbacks = forks.observe_slot(&slot(13, None, SlotStatus::Confirmed));
+        assert_eq!(rollbacks.len(), 2);
+        let pool_rollback = rollbacks.iter().find(|r| r.pubkey == pool).unwrap();
+        assert_eq!(pool_rollback.abandoned_slot, 12);
+        let restored = pool_rollback.restored.as_ref().unwrap();
+        assert_eq!((restored.slot, restored.data[0]), (11, 2));
+        assert!(rollbacks.iter().find(|r| r.pubkey == other).unwrap().restored.is_none());
+
+        // 被放弃分叉上晚到的推送直接丢弃；确认链上的照常接收
+        assert!(!forks.observe_account(&account(pool, 12, 4)));
+        assert!(forks.observe_account(&account(pool, 13, 5)));
+        let stats = forks.stats();
+        assert_eq!((stats.confirmed_slot, stats.abandoned_slots, stats.rollbacks), (13, 1, 2));
+        assert_eq!(stats.dropped_updates, 3);
+    }
+
+    #[test]
+    fn test_dead_slot_rolls_back_before_confirmation() {
+        let mut forks = ForkTracker::new(100);
+        let pool = Pubkey::new_unique();
+
+        forks.observe_slot(&slot(20, Some(19), SlotStatus::Processed));
+        assert!(forks.observe_account(&account(pool, 20, 1)));
+        assert!(forks.observe_slot(&slot(20, None, SlotStatus::Confirmed)).is_empty());
+        for (s, parent) in [(21, 20), (22, 21)] {
+            forks.observe_slot(&slot(s, Some(parent), SlotStatus::Processed));
+        }
+        assert!(forks.observe_account(&account(pool, 21, 2)));
+        assert!(forks.observe_account(&account(pool, 22, 3)));
+
+        // 21 被报告为死 Slot: 它和后代 22 上的版本立即回滚到已确认的 20，无需等待确认
+        let rollbacks = forks.observe_slot(&slot(21, None, SlotStatus::Dead));
+        assert_eq!(rollbacks.len(), 1);
+        assert_eq!(rollbacks[0].abandoned_slot, 22);
+        let restored = rollbacks[0].restored.as_ref().unwrap();
+        assert_eq!((restored.slot, restored.data[0]), (20, 1));
+        assert!(forks.observe_slot(&slot(21, None, SlotStatus::Dead)).is_empty());
+
+        // 死分叉上晚到的推送丢弃；之后的确认不会重复计入已放弃的 Slot
+        assert!(!forks.observe_account(&account(pool, 22, 4)));
+        forks.observe_slot(&slot(23, Some(20), SlotStatus::Processed));
+        assert!(forks.observe_slot(&slot(23, None, SlotStatus::Confirmed)).is_empty());
+        let stats = forks.stats();
+        assert_eq!((stats.abandoned_slots, stats.rollbacks, stats.dropped_updates), (1, 1, 3));
+    }
+
+    #[test]
+    fn test_prune_confirmed_by_age_and_unwatch() {
+        let mut forks = ForkTracker::new(10);
+        let (quiet, busy, removed) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        for pubkey in [quiet, busy, removed] {
+            assert!(forks.observe_account(&account(pubkey, 5, 1)));
+        }
+        forks.observe_slot(&slot(5, Some(4), SlotStatus::Confirmed));
+        assert_eq!(forks.accounts.len(), 3);
+
+        forks.unwatch(&removed);
+        assert!(!forks.accounts.contains_key(&removed));
+
+        // quiet 之后再没有推送，确认版本超出窗口后被清理；busy 持续更新保留
+        assert!(forks.observe_account(&account(busy, 14, 2)));
+        forks.observe_slot(&slot(14, Some(5), SlotStatus::Confirmed));
+        assert!(forks.accounts.contains_key(&quiet));
+        assert!(forks.observe_account(&account(busy, 20, 3)));
+        forks.observe_slot(&slot(20, Some(14), SlotStatus::Confirmed));
+        assert!(!forks.accounts.contains_key(&quiet));
+        assert_eq!(forks.accounts[&busy].confirmed.as_ref().map(|c| c.slot), Some(20));
+    }
+}
@@ -0,0 +1,912 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures::StreamExt;
+use log::{debug, info, warn, error};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel as SolanaCommitment};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::sync::{mpsc, watch};
+use tonic::codec::{ProstCodec, Streaming};
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Endpoint;
+use crate::config::GeyserConfig;
+use crate::scout::supervisor::{backoff, resync_accounts, ConnectionMetrics, ConnectionState, ConnectionStats, STABLE_CONNECTION};
+use crate::scout::events::{AccountUpdate, EventSource, SlotStatus, SlotUpdate, StreamEvent, TransactionUpdate};
+
+type GeyserResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+
+/// Yellowstone Geyser gRPC 协议 (只保留订阅需要的字段，字段号与 geyser.proto 保持一致)
+pub mod proto {
+    use std::collections::HashMap;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum CommitmentLevel {
+        Processed = 0,
+        Confirmed = 1,
+        Finalized = 2,
+    }
+
+    /// Slot 推送的状态，比 CommitmentLevel 多出中间状态和 DEAD
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+    #[repr(i32)]
+    pub enum SlotStatus {
+        SlotProcessed = 0,
+        SlotConfirmed = 1,
+        SlotFinalized = 2,
+        SlotFirstShredReceived = 3,
+        SlotCompleted = 4,
+        SlotCreatedBank = 5,
+        SlotDead = 6,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequest {
+        #[prost(map = "string, message", tag = "1")]
+        pub accounts: HashMap<String, SubscribeRequestFilterAccounts>,
+        #[prost(map = "string, message", tag = "2")]
+        pub slots: HashMap<String, SubscribeRequestFilterSlots>,
+        #[prost(map = "string, message", tag = "3")]
+        pub transactions: HashMap<String, SubscribeRequestFilterTransactions>,
+        #[prost(enumeration = "CommitmentLevel", optional, tag = "6")]
+        pub commitment: Option<i32>,
+        #[prost(message, optional, tag = "9")]
+        pub ping: Option<SubscribeRequestPing>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestFilterAccounts {
+        #[prost(string, repeated, tag = "2")]
+        pub account: Vec<String>,
+        #[prost(string, repeated, tag = "3")]
+        pub owner: Vec<String>,
+        #[prost(message, repeated, tag = "4")]
+        pub filters: Vec<SubscribeRequestFilterAccountsFilter>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestFilterAccountsFilter {
+        #[prost(oneof = "subscribe_request_filter_accounts_filter::Filter", tags = "1, 2")]
+        pub filter: Option<subscribe_request_filter_accounts_filter::Filter>,
+    }
+
+    pub mod subscribe_request_filter_accounts_filter {
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum Filter {
+            #[prost(message, tag = "1")]
+            Memcmp(super::SubscribeRequestFilterAccountsFilterMemcmp),
+            #[prost(uint64, tag = "2")]
+            Datasize(u64),
+        }
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestFilterAccountsFilterMemcmp {
+        #[prost(uint64, tag = "1")]
+        pub offset: u64,
+        #[prost(oneof = "subscribe_request_filter_accounts_filter_memcmp::Data", tags = "2")]
+        pub data: Option<subscribe_request_filter_accounts_filter_memcmp::Data>,
+    }
+
+    pub mod subscribe_request_filter_accounts_filter_memcmp {
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum Data {
+            #[prost(bytes, tag = "2")]
+            Bytes(Vec<u8>),
+        }
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestFilterSlots {
+        #[prost(bool, optional, tag = "1")]
+        pub filter_by_commitment: Option<bool>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestFilterTransactions {
+        #[prost(bool, optional, tag = "1")]
+        pub vote: Option<bool>,
+        #[prost(bool, optional, tag = "2")]
+        pub failed: Option<bool>,
+        #[prost(string, repeated, tag = "3")]
+        pub account_include: Vec<String>,
+        #[prost(string, repeated, tag = "4")]
+        pub account_exclude: Vec<String>,
+        #[prost(string, repeated, tag = "6")]
+        pub account_required: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeRequestPing {
+        #[prost(int32, tag = "1")]
+        pub id: i32,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdate {
+        #[prost(string, repeated, tag = "1")]
+        pub filters: Vec<String>,
+        #[prost(oneof = "subscribe_update::UpdateOneof", tags = "2, 3, 4, 6, 9")]
+        pub update_oneof: Option<subscribe_update::UpdateOneof>,
+    }
+
+    pub mod subscribe_update {
+        #[derive(Clone, PartialEq, ::prost::Oneof)]
+        pub enum UpdateOneof {
+            #[prost(message, tag = "2")]
+            Account(super::SubscribeUpdateAccount),
+            #[prost(message, tag = "3")]
+            Slot(super::SubscribeUpdateSlot),
+            #[prost(message, tag = "4")]
+            Transaction(super::SubscribeUpdateTransaction),
+            #[prost(message, tag = "6")]
+            Ping(super::SubscribeUpdatePing),
+            #[prost(message, tag = "9")]
+            Pong(super::SubscribeUpdatePong),
+        }
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateAccount {
+        #[prost(message, optional, tag = "1")]
+        pub account: Option<SubscribeUpdateAccountInfo>,
+        #[prost(uint64, tag = "2")]
+        pub slot: u64,
+        #[prost(bool, tag = "3")]
+        pub is_startup: bool,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateAccountInfo {
+        #[prost(bytes, tag = "1")]
+        pub pubkey: Vec<u8>,
+        #[prost(uint64, tag = "2")]
+        pub lamports: u64,
+        #[prost(bytes, tag = "3")]
+        pub owner: Vec<u8>,
+        #[prost(bool, tag = "4")]
+        pub executable: bool,
+        #[prost(uint64, tag = "5")]
+        pub rent_epoch: u64,
+        #[prost(bytes, tag = "6")]
+        pub data: Vec<u8>,
+        #[prost(uint64, tag = "7")]
+        pub write_version: u64,
+        #[prost(bytes, optional, tag = "8")]
+        pub txn_signature: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateSlot {
+        #[prost(uint64, tag = "1")]
+        pub slot: u64,
+        #[prost(uint64, optional, tag = "2")]
+        pub parent: Option<u64>,
+        #[prost(enumeration = "SlotStatus", tag = "3")]
+        pub status: i32,
+        #[prost(string, optional, tag = "4")]
+        pub dead_error: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateTransaction {
+        #[prost(message, optional, tag = "1")]
+        pub transaction: Option<SubscribeUpdateTransactionInfo>,
+        #[prost(uint64, tag = "2")]
+        pub slot: u64,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdateTransactionInfo {
+        #[prost(bytes, tag = "1")]
+        pub signature: Vec<u8>,
+        #[prost(bool, tag = "2")]
+        pub is_vote: bool,
+        #[prost(message, optional, tag = "3")]
+        pub transaction: Option<Transaction>,
+        #[prost(message, optional, tag = "4")]
+        pub meta: Option<TransactionStatusMeta>,
+        #[prost(uint64, tag = "5")]
+        pub index: u64,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdatePing {}
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct SubscribeUpdatePong {
+        #[prost(int32, tag = "1")]
+        pub id: i32,
+    }
+
+    // solana.storage.ConfirmedBlock
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Transaction {
+        #[prost(bytes, repeated, tag = "1")]
+        pub signatures: Vec<Vec<u8>>,
+        #[prost(message, optional, tag = "2")]
+        pub message: Option<Message>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Message {
+        #[prost(message, optional, tag = "1")]
+        pub header: Option<MessageHeader>,
+        #[prost(bytes, repeated, tag = "2")]
+        pub account_keys: Vec<Vec<u8>>,
+        #[prost(bytes, tag = "3")]
+        pub recent_blockhash: Vec<u8>,
+        #[prost(message, repeated, tag = "4")]
+        pub instructions: Vec<CompiledInstruction>,
+        #[prost(bool, tag = "5")]
+        pub versioned: bool,
+        #[prost(message, repeated, tag = "6")]
+        pub address_table_lookups: Vec<MessageAddressTableLookup>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MessageHeader {
+        #[prost(uint32, tag = "1")]
+        pub num_required_signatures: u32,
+        #[prost(uint32, tag = "2")]
+        pub num_readonly_signed_accounts: u32,
+        #[prost(uint32, tag = "3")]
+        pub num_readonly_unsigned_accounts: u32,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct CompiledInstruction {
+        #[prost(uint32, tag = "1")]
+        pub program_id_index: u32,
+        #[prost(bytes, tag = "2")]
+        pub accounts: Vec<u8>,
+        #[prost(bytes, tag = "3")]
+        pub data: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MessageAddressTableLookup {
+        #[prost(bytes, tag = "1")]
+        pub account_key: Vec<u8>,
+        #[prost(bytes, tag = "2")]
+        pub writable_indexes: Vec<u8>,
+        #[prost(bytes, tag = "3")]
+        pub readonly_indexes: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TransactionStatusMeta {
+        #[prost(message, optional, tag = "1")]
+        pub err: Option<TransactionError>,
+        #[prost(uint64, tag = "2")]
+        pub fee: u64,
+        #[prost(message, repeated, tag = "5")]
+        pub inner_instructions: Vec<InnerInstructions>,
+        #[prost(string, repeated, tag = "6")]
+        pub log_messages: Vec<String>,
+        #[prost(bytes, repeated, tag = "12")]
+        pub loaded_writable_addresses: Vec<Vec<u8>>,
+        #[prost(bytes, repeated, tag = "13")]
+        pub loaded_readonly_addresses: Vec<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TransactionError {
+        #[prost(bytes, tag = "1")]
+        pub err: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct InnerInstructions {
+        #[prost(uint32, tag = "1")]
+        pub index: u32,
+        #[prost(message, repeated, tag = "2")]
+        pub instructions: Vec<InnerInstruction>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct InnerInstruction {
+        #[prost(uint32, tag = "1")]
+        pub program_id_index: u32,
+        #[prost(bytes, tag = "2")]
+        pub accounts: Vec<u8>,
+        #[prost(bytes, tag = "3")]
+        pub data: Vec<u8>,
+        #[prost(uint32, optional, tag = "4")]
+        pub stack_height: Option<u32>,
+    }
+}
+
+use proto::subscribe_request_filter_accounts_filter::Filter;
+use proto::subscribe_request_filter_accounts_filter_memcmp::Data;
+use proto::subscribe_update::UpdateOneof;
+use proto::{CommitmentLevel, SubscribeRequest, SubscribeUpdate};
+
+/// 按 Owner (程序) 订阅账户，附带 dataSize / memcmp 过滤
+#[derive(Debug, Clone, Default)]
+pub struct OwnerFilter {
+    pub owner: Pubkey,
+    pub data_size: Option<u64>,
+    pub memcmp: Vec<(u64, Vec<u8>)>,
+}
+
+/// Geyser 订阅内容，可以在流存活期间整体替换
+#[derive(Debug, Clone)]
+pub struct GeyserFilter {
+    /// 按地址订阅的账户
+    pub accounts: Vec<Pubkey>,
+    /// 按 Owner 订阅的账户
+    pub owners: Vec<OwnerFilter>,
+    /// 订阅涉及这些账户 (程序) 的交易
+    pub transactions: Vec<Pubkey>,
+    /// 是否订阅 Slot 更新
+    pub slots: bool,
+    pub commitment: CommitmentLevel,
+}
+
+impl Default for GeyserFilter {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            owners: Vec::new(),
+            transactions: Vec::new(),
+            slots: false,
+            commitment: CommitmentLevel::Processed,
+        }
+    }
+}
+
+impl From<CommitmentConfig> for CommitmentLevel {
+    fn from(commitment: CommitmentConfig) -> Self {
+        match commitment.commitment {
+            SolanaCommitment::Finalized => CommitmentLevel::Finalized,
+            SolanaCommitment::Confirmed => CommitmentLevel::Confirmed,
+            _ => CommitmentLevel::Processed,
+        }
+    }
+}
+
+impl GeyserFilter {
+    pub fn to_request(&self) -> SubscribeRequest {
+        let mut request = SubscribeRequest {
+            commitment: Some(self.commitment as i32),
+            ..Default::default()
+        };
+
+        if !self.accounts.is_empty() {
+            request.accounts.insert(
+                "accounts".to_string(),
+                proto::SubscribeRequestFilterAccounts {
+                    account: self.accounts.iter().map(|k| k.to_string()).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        // 每个 Owner 的过滤条件不同，各自使用独立的过滤器名
+        for (i, owner) in self.owners.iter().enumerate() {
+            let mut filters = Vec::new();
+            if let Some(size) = owner.data_size {
+                filters.push(proto::SubscribeRequestFilterAccountsFilter {
+                    filter: Some(Filter::Datasize(size)),
+                });
+            }
+            for (offset, bytes) in &owner.memcmp {
+                filters.push(proto::SubscribeRequestFilterAccountsFilter {
+                    filter: Some(Filter::Memcmp(proto::SubscribeRequestFilterAccountsFilterMemcmp {
+                        offset: *offset,
+                        data: Some(Data::Bytes(bytes.clone())),
+                    })),
+                });
+            }
+            request.accounts.insert(
+                format!("owner_{}", i),
+                proto::SubscribeRequestFilterAccounts {
+                    owner: vec![owner.owner.to_string()],
+                    filters,
+                    ..Default::default()
+                },
+            );
+        }
+        if !self.transactions.is_empty() {
+            request.transactions.insert(
+                "transactions".to_string(),
+                proto::SubscribeRequestFilterTransactions {
+                    vote: Some(false),
+                    account_include: self.transactions.iter().map(|k| k.to_string()).collect(),
+                    ..Default::default()
+                },
+            );
+        }
+        if self.slots {
+            request.slots.insert("slots".to_string(), proto::SubscribeRequestFilterSlots::default());
+        }
+        request
+    }
+}
+
+/// 运行中的 Geyser 订阅句柄
+/// 流结束 / 异常 / 心跳超时后由后台任务按退避重连，并重新发送最新的订阅内容；
+/// 所有句柄 drop 后后台任务退出
+#[derive(Debug, Clone)]
+pub struct GeyserHandle {
+    endpoint: String,
+    filter: Arc<watch::Sender<GeyserFilter>>,
+    metrics: Arc<ConnectionMetrics>,
+}
+
+impl GeyserHandle {
+    /// 替换订阅内容 (Geyser 以最新一次请求为准，重连后同样使用最新内容)
+    pub fn update(&self, filter: &GeyserFilter) -> bool {
+        self.metrics.subscriptions.store(filter.accounts.len(), Ordering::Relaxed);
+        self.filter.send(filter.clone()).is_ok()
+    }
+
+    /// 后台任务已退出 (事件接收端已关闭)
+    pub fn is_closed(&self) -> bool {
+        self.filter.is_closed()
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        self.metrics.snapshot(&self.endpoint)
+    }
+}
+
+/// Yellowstone Geyser gRPC 客户端
+#[derive(Clone)]
+pub struct GeyserClient {
+    config: GeyserConfig,
+    // 重连后通过 getMultipleAccounts 补拉断线期间错过的账户更新
+    rpc_client: Arc<RpcClient>,
+    resync_commitment: CommitmentConfig,
+}
+
+impl GeyserClient {
+    pub fn new(config: GeyserConfig, rpc_client: Arc<RpcClient>, resync_commitment: CommitmentConfig) -> Self {
+        Self { config, rpc_client, resync_commitment }
+    }
+
+    /// 建立订阅流，事件转换为 StreamEvent 后发送到 `events`
+    /// 首次连接失败直接返回错误；之后的断线由后台任务负责重连
+    pub async fn subscribe(
+        &self,
+        filter: &GeyserFilter,
+        events: mpsc::Sender<StreamEvent>,
+    ) -> GeyserResult<GeyserHandle> {
+        let (outbound, stream) = self.connect(filter).await?;
+        info!("✅ Geyser 订阅已建立: {}", self.config.endpoint);
+
+        let metrics = Arc::new(ConnectionMetrics::new());
+        metrics.connected();
+        metrics.subscriptions.store(filter.accounts.len(), Ordering::Relaxed);
+        let (filter_tx, filter_rx) = watch::channel(filter.clone());
+        let handle = GeyserHandle {
+            endpoint: self.config.endpoint.clone(),
+            filter: Arc::new(filter_tx),
+            metrics: metrics.clone(),
+        };
+        tokio::spawn(self.clone().run(outbound, stream, filter_rx, events, metrics));
+        Ok(handle)
+    }
+
+    /// 建立一次 gRPC 连接并发送订阅请求，返回请求发送端 (用于替换订阅 / 回复 Ping) 和推送流
+    async fn connect(
+        &self,
+        filter: &GeyserFilter,
+    ) -> GeyserResult<(mpsc::UnboundedSender<SubscribeRequest>, Streaming<SubscribeUpdate>)> {
+        let channel = Endpoint::from_shared(self.config.endpoint.clone())?.connect().await?;
+        let mut grpc = tonic::client::Grpc::new(channel);
+        grpc.ready().await?;
+
+        let (req_tx, req_rx) = mpsc::unbounded_channel::<SubscribeRequest>();
+        req_tx.send(filter.to_request())?;
+        let outbound = futures::stream::unfold(req_rx, |mut rx| async move {
+            rx.recv().await.map(|req| (req, rx))
+        });
+
+        let mut request = tonic::Request::new(outbound);
+        if let Some(token) = &self.config.x_token {
+            request.metadata_mut().insert("x-token", token.parse()?);
+        }
+
+        let codec = ProstCodec::<SubscribeRequest, SubscribeUpdate>::default();
+        let stream = grpc
+            .streaming(request, PathAndQuery::from_static(SUBSCRIBE_PATH), codec)
+            .await?
+            .into_inner();
+        Ok((req_tx, stream))
+    }
+
+    /// 维护订阅流: 转发推送和订阅变更，流失效后按退避重连并恢复最新订阅
+    async fn run(
+        self,
+        mut outbound: mpsc::UnboundedSender<SubscribeRequest>,
+        mut stream: Streaming<SubscribeUpdate>,
+        mut filter_rx: watch::Receiver<GeyserFilter>,
+        events: mpsc::Sender<StreamEvent>,
+        metrics: Arc<ConnectionMetrics>,
+    ) {
+        let endpoint = self.config.endpoint.clone();
+        let heartbeat_timeout = Duration::from_secs(self.config.heartbeat_timeout_secs.max(1));
+        let mut attempt: u32 = 0;
+
+        loop {
+            let connected_at = Instant::now();
+            let mut check = tokio::time::interval(Duration::from_secs(1));
+            let shutdown = loop {
+                tokio::select! {
+                    changed = filter_rx.changed() => match changed {
+                        Ok(()) => {
+                            let request = filter_rx.borrow_and_update().to_request();
+                            let _ = outbound.send(request);
+                        }
+                        // 所有句柄已 drop
+                        Err(_) => break true,
+                    },
+                    message = stream.next() => match message {
+                        Some(Ok(update)) => {
+                            metrics.touch();
+                            match update.update_oneof {
+                                // 服务端心跳: 回复 Ping 保持连接 (经过负载均衡时必需)
+                                Some(UpdateOneof::Ping(_)) => {
+                                    let _ = outbound.send(SubscribeRequest {
+                                        ping: Some(proto::SubscribeRequestPing { id: 1 }),
+                                        ..Default::default()
+                                    });
+                                }
+                                Some(other) => {
+                                    if let Some(event) = convert_update(other) {
+                                        if events.send(event).await.is_err() {
+                                            break true;
+                                        }
+                                    }
+                                }
+                                None => {}
+                            }
+                        }
+                        Some(Err(status)) => {
+                            error!("❌ Geyser 流异常 {}: {}", endpoint, status);
+                            metrics.dead_streams.fetch_add(1, Ordering::Relaxed);
+                            break false;
+                        }
+                        None => {
+                            warn!("⚠️ Geyser 流已结束: {}", endpoint);
+                            metrics.dead_streams.fetch_add(1, Ordering::Relaxed);
+                            break false;
+                        }
+                    },
+                    _ = check.tick() => {
+                        if metrics.last_event_age() > heartbeat_timeout {
+                            warn!("⚠️ Geyser {} 心跳超时 ({:?} 无推送)", endpoint, heartbeat_timeout);
+                            metrics.heartbeat_timeouts.fetch_add(1, Ordering::Relaxed);
+                            break false;
+                        }
+                    }
+                }
+            };
+            drop(stream);
+            if shutdown {
+                break;
+            }
+
+            if connected_at.elapsed() >= STABLE_CONNECTION {
+                attempt = 0;
+            }
+            metrics.set_state(ConnectionState::Reconnecting);
+            // 重连直到成功，期间的订阅变更只保留最新一份
+            (outbound, stream) = loop {
+                let delay = backoff(self.config.reconnect_base_delay_ms, self.config.reconnect_max_delay_ms, attempt);
+                attempt += 1;
+                metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+                warn!("🔁 Geyser {} 将在 {:?} 后重连 (第 {} 次)", endpoint, delay, attempt);
+                tokio::time::sleep(delay).await;
+                if filter_rx.has_changed().is_err() || events.is_closed() {
+                    metrics.set_state(ConnectionState::Closed);
+                    return;
+                }
+                let filter = filter_rx.borrow_and_update().clone();
+                match self.connect(&filter).await {
+                    Ok(connection) => {
+                        metrics.connected();
+                        info!("✅ Geyser 已重连 {}，恢复 {} 个账户订阅", endpoint, filter.accounts.len());
+                        resync_accounts(&self.rpc_client, &filter.accounts, self.resync_commitment, EventSource::Geyser, &events).await;
+                        metrics.resyncs.fetch_add(1, Ordering::Relaxed);
+                        break connection;
+                    }
+                    Err(e) => warn!("⚠️ Geyser 重连 {} 失败: {}", endpoint, e),
+                }
+            };
+        }
+
+        metrics.set_state(ConnectionState::Closed);
+        info!("🔌 Geyser 订阅任务已退出: {}", endpoint);
+    }
+}
+
+/// 将 Geyser 推送转换为统一事件 (无法识别的内容返回 None)
+pub fn convert_update(update: UpdateOneof) -> Option<StreamEvent> {
+    match update {
+        UpdateOneof::Account(msg) => {
+            let info = msg.account?;
+            Some(StreamEvent::Account(AccountUpdate {
+                pubkey: Pubkey::try_from(info.pubkey.as_slice()).ok()?,
+                owner: Pubkey::try_from(info.owner.as_slice()).ok(),
+                lamports: info.lamports,
+                data: info.data,
+                slot: msg.slot,
+                source: EventSource::Geyser,
+                received_at: Instant::now(),
+            }))
+        }
+        UpdateOneof::Slot(msg) => {
+            // 中间状态对分叉判定没有帮助，只关心确认级别和 DEAD
+            let status = match proto::SlotStatus::from_i32(msg.status)? {
+                proto::SlotStatus::SlotProcessed => SlotStatus::Processed,
+                proto::SlotStatus::SlotConfirmed => SlotStatus::Confirmed,
+                proto::SlotStatus::SlotFinalized => SlotStatus::Finalized,
+                proto::SlotStatus::SlotDead => {
+                    debug!("[Geyser] Slot {} 已死: {}", msg.slot, msg.dead_error.as_deref().unwrap_or("-"));
+                    SlotStatus::Dead
+                }
+                _ => return None,
+            };
+            Some(StreamEvent::Slot(SlotUpdate {
+                slot: msg.slot,
+                parent: msg.parent,
+                status,
+                source: EventSource::Geyser,
+                received_at: Instant::now(),
+            }))
+        }
+        UpdateOneof::Transaction(msg) => {
+            let info = msg.transaction?;
+            let meta = info.meta.unwrap_or_default();
+            // 静态账户 + 地址查找表加载的账户 (顺序与交易中的账户索引一致)
+            let static_keys = info
+                .transaction
+                .and_then(|tx| tx.message)
+                .map(|m| m.account_keys)
+                .unwrap_or_default();
+            let account_keys = static_keys
+                .iter()
+                .chain(&meta.loaded_writable_addresses)
+                .chain(&meta.loaded_readonly_addresses)
+                .filter_map(|k| Pubkey::try_from(k.as_slice()).ok())
+                .collect();
+            Some(StreamEvent::Transaction(TransactionUpdate {
+                signature: Signature::try_from(info.signature.as_slice()).ok()?,
+                slot: msg.slot,
+                account_keys,
+                logs: meta.log_messages,
+                is_failed: meta.err.is_some(),
+                source: EventSource::Geyser,
+                received_at: Instant::now(),
+            }))
+        }
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+    use std::task::{Context, Poll};
+    use tonic::body::BoxBody;
+    use tonic::codegen::{http, BoxFuture, BoxStream, Service};
+    use tonic::server::NamedService;
+    use tonic::transport::{Body, Server};
+    use tonic::{Status, Streaming};
+
+    /// 本地 Mock Geyser: 记录收到的订阅请求，按顺序推送预设的更新后关闭流
+    #[derive(Clone)]
+    struct MockGeyser {
+        updates: Vec<SubscribeUpdate>,
+        requests: mpsc::UnboundedSender<SubscribeRequest>,
+    }
+
+    impl NamedService for MockGeyser {
+        const NAME: &'static str = "geyser.Geyser";
+    }
+
+    impl Service<http::Request<Body>> for MockGeyser {
+        type Response = http::Response<BoxBody>;
+        type Error = Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: http::Request<Body>) -> Self::Future {
+            let svc = SubscribeSvc(self.clone());
+            Box::pin(async move {
+                let mut grpc = tonic::server::Grpc::new(ProstCodec::<SubscribeUpdate, SubscribeRequest>::default());
+                Ok(grpc.streaming(svc, req).await)
+            })
+        }
+    }
+
+    struct SubscribeSvc(MockGeyser);
+
+    impl Service<tonic::Request<Streaming<SubscribeRequest>>> for SubscribeSvc {
+        type Response = tonic::Response<BoxStream<SubscribeUpdate>>;
+        type Error = Status;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, req: tonic::Request<Streaming<SubscribeRequest>>) -> Self::Future {
+            let mock = self.0.clone();
+            Box::pin(async move {
+                let mut inbound = req.into_inner();
+                if let Some(first) = inbound.message().await? {
+                    let _ = mock.requests.send(first);
+                }
+                let updates: BoxStream<SubscribeUpdate> = Box::pin(futures::stream::iter(mock.updates.into_iter().map(Ok)));
+                Ok(tonic::Response::new(updates))
+            })
+        }
+    }
+
+    async fn start_mock(updates: Vec<SubscribeUpdate>) -> (String, mpsc::UnboundedReceiver<SubscribeRequest>) {
+        let (req_tx, req_rx) = mpsc::unbounded_channel();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |l| async move {
+            Some((l.accept().await.map(|(s, _)| s), l))
+        });
+        let mock = MockGeyser { updates, requests: req_tx };
+        tokio::spawn(Server::builder().add_service(mock).serve_with_incoming(incoming));
+        (format!("http://{}", addr), req_rx)
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_against_mock_server() {
+        let pool = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let signature = Signature::new_unique();
+        let updates = vec![
+            SubscribeUpdate {
+                filters: vec!["accounts".to_string()],
+                update_oneof: Some(UpdateOneof::Account(proto::SubscribeUpdateAccount {
+                    account: Some(proto::SubscribeUpdateAccountInfo {
+                        pubkey: pool.to_bytes().to_vec(),
+                        owner: owner.to_bytes().to_vec(),
+                        lamports: 42,
+                        data: vec![1, 2, 3],
+                        ..Default::default()
+                    }),
+                    slot: 100,
+                    is_startup: false,
+                })),
+            },
+            SubscribeUpdate {
+                filters: vec!["slots".to_string()],
+                update_oneof: Some(UpdateOneof::Slot(proto::SubscribeUpdateSlot {
+                    slot: 101,
+                    parent: Some(100),
+                    status: proto::SlotStatus::SlotConfirmed as i32,
+                    dead_error: None,
+                })),
+            },
+            SubscribeUpdate {
+                filters: vec!["transactions".to_string()],
+                update_oneof: Some(UpdateOneof::Transaction(proto::SubscribeUpdateTransaction {
+                    transaction: Some(proto::SubscribeUpdateTransactionInfo {
+                        signature: signature.as_ref().to_vec(),
+                        transaction: Some(proto::Transaction {
+                            signatures: vec![signature.as_ref().to_vec()],
+                            message: Some(proto::Message {
+                                account_keys: vec![owner.to_bytes().to_vec()],
+                                ..Default::default()
+                            }),
+                        }),
+                        meta: Some(proto::TransactionStatusMeta {
+                            log_messages: vec!["Program log: Instruction: Swap".to_string()],
+                            loaded_writable_addresses: vec![pool.to_bytes().to_vec()],
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    }),
+                    slot: 101,
+                })),
+            },
+        ];
+        let (endpoint, mut requests) = start_mock(updates).await;
+
+        let filter = GeyserFilter {
+            accounts: vec![pool],
+            owners: vec![OwnerFilter { owner, data_size: Some(653), memcmp: vec![(101, vec![7; 32])] }],
+            transactions: vec![owner],
+            slots: true,
+            ..Default::default()
+        };
+        let (tx, mut rx) = mpsc::channel(16);
+        let config = GeyserConfig {
+            endpoint,
+            x_token: Some("token".to_string()),
+            reconnect_base_delay_ms: 10,
+            reconnect_max_delay_ms: 20,
+            ..Default::default()
+        };
+        // 补拉失败只记录日志，不影响重连
+        let rpc_client = Arc::new(RpcClient::new_mock("fails".to_string()));
+        let handle = GeyserClient::new(config, rpc_client, CommitmentConfig::processed())
+            .subscribe(&filter, tx)
+            .await
+            .unwrap();
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.accounts["accounts"].account, vec![pool.to_string()]);
+        assert_eq!(request.accounts["owner_0"].owner, vec![owner.to_string()]);
+        assert_eq!(request.accounts["owner_0"].filters.len(), 2);
+        assert!(request.slots.contains_key("slots"));
+        assert_eq!(request.transactions["transactions"].account_include, vec![owner.to_string()]);
+
+        match rx.recv().await.unwrap() {
+            StreamEvent::Account(u) => {
+                assert_eq!(u.pubkey, pool);
+                assert_eq!(u.owner, Some(owner));
+                assert_eq!(u.data, vec![1, 2, 3]);
+                assert_eq!(u.slot, 100);
+                assert_eq!(u.source, EventSource::Geyser);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            StreamEvent::Slot(u) => {
+                assert_eq!((u.slot, u.parent, u.status), (101, Some(100), SlotStatus::Confirmed));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            StreamEvent::Transaction(u) => {
+                assert_eq!(u.signature, signature);
+                assert_eq!(u.account_keys, vec![owner, pool]);
+                assert!(!u.is_failed);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        // 服务端关闭流后自动重连，并按最新的订阅内容重新订阅
+        let other = Pubkey::new_unique();
+        assert!(handle.update(&GeyserFilter { accounts: vec![other], ..filter.clone() }));
+        loop {
+            let request = requests.recv().await.unwrap();
+            if request.accounts.get("accounts").map(|f| f.account.clone()) == Some(vec![other.to_string()]) {
+                break;
+            }
+        }
+        match rx.recv().await.unwrap() {
+            StreamEvent::Account(u) => assert_eq!(u.pubkey, pool),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        let stats = handle.stats();
+        assert!(stats.reconnects >= 1 && stats.dead_streams >= 1);
+        assert!(!handle.is_closed());
+    }
+
+    #[test]
+    fn test_convert_slot_status() {
+        let update = |status: proto::SlotStatus| {
+            convert_update(UpdateOneof::Slot(proto::SubscribeUpdateSlot {
+                slot: 7,
+                parent: Some(6),
+                status: status as i32,
+                dead_error: Some("bank hash mismatch".to_string()),
+            }))
+        };
+        match update(proto::SlotStatus::SlotDead) {
+            Some(StreamEvent::Slot(u)) => assert_eq!((u.slot, u.status), (7, SlotStatus::Dead)),
+            other => panic!("unexpected event: {:?}", other),
+        }
+        // 中间状态不转发
+        assert!(update(proto::SlotStatus::SlotCompleted).is_none());
+    }
+}
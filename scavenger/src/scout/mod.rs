@@ -1,7 +1,6 @@
 use std::sync::Arc;
 // use tokio::sync::mpsc;
 use log::{info, error};
-use solana_sdk::signature::Keypair;
 // use jito_searcher_client::get_searcher_client_no_auth; 
 // use jito_protos::searcher::searcher_service_client::SearcherServiceClient;
 // use tonic::transport::Channel;
@@ -11,26 +10,43 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 
 mod monitor; // 引入监控模块
 pub mod raydium; // 引入 Raydium 解析模块
+pub mod tx; // 交易拉取与指令展开 (含 CPI 内层指令)
+pub mod swaps; // Raydium / Whirlpool 成交解码
 pub mod orca; // 引入 Orca 解析模块
 pub mod api; // 引入 API 模块
+pub mod watchlist; // 监控名单排序
+pub mod discovery; // 池子发现 (链上 / API)
+pub mod backfill; // 停机期间新池的历史回填
+pub mod pool_source; // API 池子列表缓存
+pub mod events; // WebSocket / Geyser 共用事件类型
+pub mod dedup; // 多连接 / 多数据源事件去重
+pub mod forks; // 分叉跟踪与回滚
+pub mod geyser; // Yellowstone Geyser gRPC 订阅
+pub mod supervisor; // WebSocket 断线重连与订阅恢复
+pub mod subscriptions; // 多连接订阅分片
 
 // use crate::strategy::engine; // 引入策略引擎 (removed unused import)
 
-use crate::config::StrategyConfig;
+use crate::bus::EventBus;
+use crate::config::{DedupConfig, DiscoveryConfig, GeyserConfig, ScoutConfig, StreamCommitmentConfig, WebSocketConfig};
 use crate::state::Inventory;
 
 pub struct Scout {
     // client: SearcherServiceClient<Channel>,
     rpc_client: Arc<RpcClient>, // 添加 RPC Client
     ws_url: String, 
-    keypair: Arc<Keypair>, // 保存 Keypair 用于传给 Strategy
-    strategy_config: StrategyConfig, // 保存策略配置
+    scout_config: ScoutConfig, // 订阅预算与排序配置
+    discovery_config: DiscoveryConfig, // 池子发现数据源
+    geyser_config: GeyserConfig, // Geyser gRPC 数据源
+    ws_config: WebSocketConfig, // WebSocket 重连参数
+    dedup_config: DedupConfig, // 事件去重窗口
+    commitment_config: StreamCommitmentConfig, // 各数据流确认级别与分叉跟踪
     inventory: Arc<Inventory>, // 全网代币索引
-    strategy_name: String, // 策略名称
+    bus: EventBus, // 侦察结果发布到事件总线，由策略自行订阅
 }
 
 impl Scout {
-    pub async fn new(config: &AppConfig, auth_keypair: &Arc<Keypair>, inventory: Arc<Inventory>, strategy_name: String) -> Result<Self, Box<dyn std::error::Error>> {
+    pub async fn new(config: &AppConfig, rpc_client: Arc<RpcClient>, inventory: Arc<Inventory>, bus: EventBus) -> Result<Self, Box<dyn std::error::Error>> {
         // info!("🔍 连接 Jito Block Engine: {}", config.jito.block_engine_url);
         
         // let endpoint = Endpoint::from_shared(config.jito.block_engine_url.clone())?;
@@ -39,50 +55,43 @@ impl Scout {
 
         // info!("✅ Jito Searcher Client 连接成功 (No Auth Mode)");
         info!("🚧 Jito Client 暂时禁用 (SDK Version Mismatch)，仅使用 RPC");
-        
-        // 初始化 RPC Client (Non-blocking)
-        let rpc_client = Arc::new(RpcClient::new(config.network.rpc_url.clone()));
-        
-        // Clone strategy config
-        let strategy_config = StrategyConfig {
-            wallet_path: config.strategy.wallet_path.clone(),
-            trade_amount_sol: config.strategy.trade_amount_sol,
-            static_tip_sol: config.strategy.static_tip_sol,
-            dynamic_tip_ratio: config.strategy.dynamic_tip_ratio,
-            max_tip_sol: config.strategy.max_tip_sol,
-        };
+
+        // 确认级别配置错误时直接启动失败
+        config.commitment.resolve()?;
         
         Ok(Self { 
             // client,
             rpc_client,
             ws_url: config.network.ws_url.clone(),
-            keypair: auth_keypair.clone(),
-            strategy_config,
+            scout_config: config.scout.clone(),
+            discovery_config: config.discovery.clone(),
+            geyser_config: config.geyser.clone(),
+            ws_config: config.websocket.clone(),
+            dedup_config: config.dedup.clone(),
+            commitment_config: config.commitment.clone(),
             inventory,
-            strategy_name,
+            bus,
         })
     }
 
     pub async fn start(&mut self) {
-        info!("👀 侦察兵已就位，开始监听全网新池子... [Mode: {}]", self.strategy_name);
+        info!("👀 侦察兵已就位，开始监听全网新池子...");
         
         // 启动 WebSocket 监听器 (在后台任务中运行)
         let ws_url = self.ws_url.clone();
         let rpc_client = self.rpc_client.clone();
-        let keypair = self.keypair.clone(); // Clone for task
-        let strategy_config = Arc::new(self.strategy_config.clone()); // Wrap in Arc
         let inventory = self.inventory.clone();
-        let strategy_name = self.strategy_name.clone();
+        let bus = self.bus.clone();
+        let scout_config = self.scout_config.clone();
+        let discovery_config = self.discovery_config.clone();
+        let geyser_config = self.geyser_config.clone();
+        let ws_config = self.ws_config.clone();
+        let dedup_config = self.dedup_config.clone();
+        let commitment_config = self.commitment_config.clone();
 
         tokio::spawn(async move {
-            // 我们需要修改 monitor 以接受 callback 或者 channel
-            // 这里为了简单，我们直接在 monitor 内部调用 strategy
-            // 但更好的做法是 monitor 只负责产出数据，通过 channel 发送给 engine
-            // 暂时保持 monitor 独立，我们在 monitor 内部集成 engine 调用
-            
-            // 实际上 monitor::start_monitoring 现在只打印日志
-            // 我们需要修改它来调用 engine::process_new_pool
-            if let Err(e) = monitor::start_monitoring(ws_url, rpc_client, keypair, strategy_config, inventory, strategy_name).await {
+            // monitor 只负责产出数据，通过事件总线发送给策略
+            if let Err(e) = monitor::start_monitoring(ws_url, rpc_client, scout_config, discovery_config, geyser_config, ws_config, dedup_config, commitment_config, inventory, bus).await {
                 error!("❌ WebSocket 监听器异常退出: {}", e);
             }
         });
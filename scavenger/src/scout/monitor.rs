@@ -1,204 +1,604 @@
-use log::{info, error};
-use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
-use solana_client::rpc_response::RpcLogsResponse;
-use solana_sdk::commitment_config::CommitmentConfig;
-use futures::StreamExt;
+use log::{info, warn, error};
 use crate::scout::raydium;
 use crate::scout::orca;
-use crate::strategies::arb; // 引入 Arb 策略
-use crate::strategies::sniper; // 引入 Sniper 策略
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::signature::Keypair;
 use std::sync::Arc;
-// use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, Semaphore};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
 
 // Raydium AMM V4 Program ID
 pub const RAYDIUM_AMM_V4: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
 // Orca Whirlpool Program ID
 pub const ORCA_WHIRLPOOL: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 
-use crate::config::StrategyConfig;
-use crate::state::Inventory;
-use crate::amm::orca_whirlpool::Whirlpool;
+// 成交解码需要逐笔拉取交易，限制同时进行的请求数，超出时直接丢弃
+const SWAP_FETCH_CONCURRENCY: usize = 16;
+
+use crate::bus::{BusEvent, Dex, EventBus, EventKind, NewPool};
+use crate::scout::dedup::Deduplicator;
+use crate::scout::{swaps, tx};
+use crate::config::{DedupConfig, DiscoveryConfig, GeyserConfig, ScoutConfig, StreamCommitmentConfig, WebSocketConfig};
+use crate::scout::discovery;
+use crate::scout::events::{AccountUpdate, StreamEvent, TransactionUpdate};
+use crate::scout::forks::{self, ForkTracker};
+use crate::scout::geyser::proto::CommitmentLevel;
+use crate::scout::geyser::{GeyserClient, GeyserFilter, GeyserHandle};
+use crate::scout::subscriptions::SubscriptionManager;
+use crate::scout::supervisor::{ConnectionStats, Subscription};
+use crate::scout::watchlist::WatchListRanker;
+use crate::state::{Inventory, InventoryChange};
+use crate::amm::orca_whirlpool::sqrt_price_x64_to_price;
+use crate::amm::unix_now;
 
+/// 侦察主循环: 只负责采集数据并发布到事件总线，不直接调用任何策略
+/// 采集哪些数据由总线上的订阅决定:
+/// - AccountUpdate: 发现套利对，按排序结果订阅池子账户
+/// - NewPool: 订阅 Raydium / Orca 程序日志，解析新池
+/// - Swap: 同样订阅程序日志，拉取含交换的交易解码成交
+#[allow(clippy::too_many_arguments)]
 pub async fn start_monitoring(
-    ws_url: String, 
+    ws_url: String,
     rpc_client: Arc<RpcClient>,
-    keypair: Arc<Keypair>,
-    config: Arc<StrategyConfig>,
+    scout_config: ScoutConfig,
+    discovery_config: DiscoveryConfig,
+    geyser_config: GeyserConfig,
+    ws_config: WebSocketConfig,
+    dedup_config: DedupConfig,
+    commitment_config: StreamCommitmentConfig,
     inventory: Arc<Inventory>,
-    strategy_name: String,
+    bus: EventBus,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let commitment = commitment_config.resolve()?;
     info!("🔌 连接 WebSocket: {}", ws_url);
-    
-    // 我们需要建立两个独立的 PubsubClient，或者在一个 Client 上建立两个 Subscription
-    // solana-client 的 PubsubClient 支持多个 subscription
-    
-    let pubsub_client = solana_client::nonblocking::pubsub_client::PubsubClient::new(&ws_url).await?;
-    info!("✅ WebSocket 连接成功");
-
-    if strategy_name == "arb" {
-        // === Arb 策略监听逻辑 ===
-        info!("🔄 [Arb] 正在从 API 加载白名单 (Common Pairs)...");
-        if let Err(e) = inventory.load_from_api().await {
-            error!("❌ Failed to load API data: {}", e);
-        }
-        let watch_list = inventory.get_watch_list();
-        let total = watch_list.len();
-        
-        // 限制订阅数量，防止 RPC 过载 (标准 RPC 限制通常较严)
-        // 如果有 Geyser，这里可以订阅全部
-        let max_subs = 50.min(total);
-        let target_accounts = &watch_list[0..max_subs];
-        
-        info!("👀 [Arb] 监控名单共 {} 个，当前订阅 Top {} 个账户进行实时监听...", total, max_subs);
-        
-        // 创建一个多路复用流
-        let mut streams = Vec::new();
-        
-        for pubkey in target_accounts {
-            let (stream, _unsub) = pubsub_client.account_subscribe(
-                pubkey,
-                None
-            ).await?;
-            // 将流映射为带 Pubkey 的事件，方便识别来源
-            streams.push(stream.map(move |data| (*pubkey, data)));
-        }
-        
-        // 合并所有流
-        let mut combined_stream = futures::stream::select_all(streams);
-        
-        info!("🚀 [Arb] 监听已启动，等待价格变动...");
-        
-        while let Some((pool_address, account_data)) = combined_stream.next().await {
-            // 这里收到的 account_data 是 UiAccount 或 EncodedAccount
-            // 我们需要解析它
-            // info!("🔔 [Update] Pool: {}", pool_address);
-            
-            // 将处理逻辑抛给 arb 策略模块
+    let watch_pairs = bus.wants(EventKind::AccountUpdate);
+    let scan_new_pools = bus.wants(EventKind::NewPool);
+    let scan_swaps = bus.wants(EventKind::Swap);
+    // 账户按 processed 推送时可能来自随后被放弃的分叉，需要按 Slot 确认情况回滚
+    let mut forks = (watch_pairs && commitment_config.track_forks && !commitment.accounts.is_at_least_confirmed())
+        .then(|| ForkTracker::new(commitment_config.max_pending_slots));
+    // WebSocket 每条连接自带 Slot 心跳；Geyser 需要在过滤器中显式订阅 (分叉跟踪也依赖 Slot 推送)
+    let watch_slots = bus.wants(EventKind::Slot) || forks.is_some();
+
+    // 所有订阅推送 (WebSocket / Geyser) 统一汇入这个通道
+    // WebSocket 由 Supervisor 负责断线检测、重连和订阅恢复，这里不需要关心连接状态
+    let (update_tx, mut update_rx) = mpsc::channel::<StreamEvent>(1024);
+    let ws_urls = if ws_config.urls.is_empty() { vec![ws_url.clone()] } else { ws_config.urls.clone() };
+    // 定期检查连接健康度，把失效连接上的订阅迁移到其他连接
+    let mut rebalance = tokio::time::interval(Duration::from_secs(ws_config.heartbeat_timeout_secs.max(1)));
+
+    // Inventory 的运行时变更 (新套利对 / 删除 / 池子失效) 直接驱动订阅增减，不等下一轮排序
+    let mut changes = inventory.subscribe_changes();
+
+    if watch_pairs {
+        if inventory.pair_count() > 0 {
+            // Warm Start: 快照中已有白名单，直接开始监听，最新数据在后台对账
+            info!("♻️ [Scout] 使用快照中的 {} 个套利对启动，后台对账...", inventory.pair_count());
+            let inv = inventory.clone();
             let client = rpc_client.clone();
-            let kp = keypair.clone();
-            let cfg = config.clone();
-            let inventory_clone = inventory.clone();
-            let data_vec = account_data.value.data.decode().unwrap_or_default();
-            
             tokio::spawn(async move {
-                arb::process_account_update(
-                    client,
-                    kp,
-                    pool_address,
-                    data_vec,
-                    cfg,
-                    inventory_clone
-                ).await;
+                if let Err(e) = discovery::load_inventory(client, &inv, &discovery_config).await {
+                    error!("❌ Failed to reconcile pool data: {}", e);
+                }
             });
+        } else {
+            info!("🔄 [Scout] 正在发现池子并构建白名单 (Common Pairs)...");
+            if let Err(e) = discovery::load_inventory(rpc_client.clone(), &inventory, &discovery_config).await {
+                error!("❌ Failed to load pool data: {}", e);
+            }
         }
+    }
 
-    } else {
-        // === Sniper 策略监听逻辑 (默认) ===
-        info!("🎯 [Sniper] 启动新池狙击模式 (Logs Subscribe)...");
-
-        // 1. 订阅 Raydium 日志
-        let (raydium_stream, _unsub_ray) = pubsub_client.logs_subscribe(
-            RpcTransactionLogsFilter::Mentions(vec![RAYDIUM_AMM_V4.to_string()]),
-            RpcTransactionLogsConfig {
-                commitment: Some(CommitmentConfig::processed()),
-            },
-        ).await?;
-        info!("👀 已订阅 Raydium AMM V4 日志");
-
-        // 2. 订阅 Orca 日志
-        let (orca_stream, _unsub_orca) = pubsub_client.logs_subscribe(
-            RpcTransactionLogsFilter::Mentions(vec![ORCA_WHIRLPOOL.to_string()]),
-            RpcTransactionLogsConfig {
-                commitment: Some(CommitmentConfig::processed()),
-            },
-        ).await?;
-        info!("👀 已订阅 Orca Whirlpool 日志");
-
-        info!("🚀 多路监控系统已启动，等待信号...");
-
-        // 使用 tokio::select! 或者合并流来同时处理
-        // 这里简单起见，我们 Spawn 两个独立的循环，或者用 select
-        
-        // 为了在一个函数里跑，我们可以用 futures::stream::select
-        let mut combined_stream = futures::stream::select(
-            raydium_stream.map(|log| (log, "Raydium")),
-            orca_stream.map(|log| (log, "Orca"))
+    if watch_pairs {
+        discovery::spawn_lifecycle_recheck(
+            rpc_client.clone(),
+            inventory.clone(),
+            Duration::from_secs(scout_config.lifecycle_recheck_secs.max(1)),
         );
+    }
 
-        let mut raydium_log_count = 0;
-
-        while let Some((response, source)) = combined_stream.next().await {
-            let logs_response: RpcLogsResponse = response.value;
-            let logs = &logs_response.logs;
-            let signature = &logs_response.signature;
-
-            if source == "Raydium" {
-                raydium_log_count += 1;
-                
-                if let Some(event) = raydium::parse_log_for_new_pool(signature, logs) {
-                    // 仅周期性打印日志，减少刷屏
-                    if raydium_log_count % 50 == 0 {
-                         info!("✨ [Raydium] 监测中... 已扫描 {} 条相关日志. 最新潜在活动 Tx: https://solscan.io/tx/{}", raydium_log_count, event.signature);
-                    } else {
-                         // 使用 debug 级别记录详细日志
-                         log::debug!("✨ [Raydium] 发现潜在活动! Tx: https://solscan.io/tx/{}", event.signature);
+    let mut feed = if geyser_config.enabled {
+        let client = GeyserClient::new(geyser_config.clone(), rpc_client.clone(), commitment.accounts);
+        let level: CommitmentLevel = commitment.accounts.into();
+        let filter = GeyserFilter { slots: watch_slots, commitment: level, ..Default::default() };
+        let handle = client.subscribe(&filter, update_tx.clone()).await?;
+        info!("📡 [Scout] 账户更新使用 Geyser gRPC: {}", geyser_config.endpoint);
+        AccountFeed::Geyser { handle, accounts: HashSet::new(), slots: watch_slots, commitment: level, dirty: false }
+    } else {
+        if forks.is_some() {
+            // slotSubscribe 只有 processed Slot，确认进度靠轮询
+            forks::spawn_confirmed_slots(
+                rpc_client.clone(),
+                Duration::from_millis(commitment_config.confirmed_poll_ms.max(50)),
+                update_tx.clone(),
+            );
+        }
+        AccountFeed::WebSocket {
+            manager: SubscriptionManager::new(&ws_urls, rpc_client.clone(), &ws_config, commitment, update_tx.clone()),
+            accounts: HashSet::new(),
+        }
+    };
+    if forks.is_some() {
+        info!("🍴 [Scout] 账户按 {:?} 推送，已开启分叉跟踪", commitment.accounts.commitment);
+    }
+    // 订阅预算不超过所有连接的总容量；扫描到的新池从中划出固定的一份，其余交给排序
+    let budget = scout_config.max_subscriptions.min(feed.capacity());
+    let new_pool_budget = match (scan_new_pools, watch_pairs) {
+        (false, _) => 0,
+        (true, true) => scout_config.new_pool_slots.min(budget),
+        (true, false) => budget,
+    };
+    let pair_budget = budget - new_pool_budget;
+    let new_pool_ttl = Duration::from_secs(scout_config.new_pool_ttl_secs.max(1));
+    let mut ranker = WatchListRanker::new(ScoutConfig {
+        max_subscriptions: pair_budget,
+        ..scout_config.clone()
+    });
+
+    // 按 TVL / 活跃度 / 价差排序，在订阅预算内选出监听名单
+    // 定期重新排序，把冷门池子换成活跃池子，不需要重启
+    let mut rerank = tokio::time::interval(Duration::from_secs(scout_config.rerank_interval_secs.max(1)));
+
+    // 新池 / 成交扫描: 日志推送量大，走独立的连接，避免拖慢账户更新
+    let mut logs = if scan_new_pools || scan_swaps {
+        let mut manager = SubscriptionManager::new(&ws_urls, rpc_client.clone(), &ws_config, commitment, update_tx.clone());
+        manager.subscribe(Subscription::Logs(Pubkey::from_str(RAYDIUM_AMM_V4)?));
+        manager.subscribe(Subscription::Logs(Pubkey::from_str(ORCA_WHIRLPOOL)?));
+        info!("👀 已订阅 Raydium AMM V4 / Orca Whirlpool 日志");
+        Some(manager)
+    } else {
+        None
+    };
+    // 扫描到的新池单独订阅账户跟踪状态，不参与监控名单排序
+    // 占用的订阅数有上限，到期后由排序流程统一退订 (值为订阅时间)
+    let (discovered_tx, mut discovered_rx) = mpsc::channel::<Pubkey>(64);
+    let mut new_pools: HashMap<Pubkey, Instant> = HashMap::new();
+    let mut scanner = LogScanner {
+        rpc_client: rpc_client.clone(),
+        inventory: inventory.clone(),
+        bus: bus.clone(),
+        discovered: discovered_tx,
+        scan_new_pools,
+        scan_swaps,
+        tx_commitment: commitment.transactions,
+        swap_permits: Arc::new(Semaphore::new(SWAP_FETCH_CONCURRENCY)),
+        raydium_log_count: 0,
+        swaps_dropped: 0,
+    };
+    let mut dedup = Deduplicator::new(&dedup_config);
+    let mut stats_tick = tokio::time::interval(Duration::from_secs(60));
+
+    info!("🚀 侦察系统已启动 (套利对监听: {} / 新池扫描: {} / 成交解码: {})，等待信号...",
+        watch_pairs, scan_new_pools, scan_swaps);
+
+    loop {
+        tokio::select! {
+            _ = rerank.tick(), if watch_pairs || scan_new_pools => {
+                let current = feed.subscribed();
+                let targets = if watch_pairs { ranker.rank(&inventory, &current) } else { Vec::new() };
+                let target_set: HashSet<Pubkey> = targets.iter().copied().collect();
+
+                // 进入监控名单的新池改由排序管理；到期的新池随本轮一起退订
+                new_pools.retain(|pool, since| !target_set.contains(pool) && since.elapsed() < new_pool_ttl);
+                let removed: Vec<Pubkey> = current
+                    .difference(&target_set)
+                    .filter(|p| !new_pools.contains_key(p))
+                    .copied()
+                    .collect();
+                for pubkey in &removed {
+                    feed.unsubscribe(pubkey);
+                    if let Some(forks) = forks.as_mut() {
+                        forks.unwatch(pubkey);
+                    }
+                }
+                let mut added = 0;
+                for pubkey in targets {
+                    if feed.subscribe(pubkey) {
+                        added += 1;
                     }
-                    
-                    let client = rpc_client.clone();
-                    let kp = keypair.clone();
-                    let cfg = config.clone();
-                    let sig = event.signature.clone();
-                    let inventory_clone = inventory.clone();
-                    
-                    tokio::spawn(async move {
-                        if let Some(full_event) = raydium::fetch_and_parse_tx(client.clone(), &sig).await {
-                            info!("🎉 [Raydium] 成功解析池子详情: Pool: {}, TokenA: {}, TokenB: {}", 
-                                full_event.pool_id, full_event.token_a, full_event.token_b);
-                            
-                            // Sniper Logic
-                            sniper::execute(client, kp, cfg, inventory_clone).await;
+                }
+                feed.flush();
+                if added > 0 || !removed.is_empty() {
+                    let stats = inventory.stats();
+                    info!("👀 [Scout] 监控名单已更新: 共 {} 个套利对，订阅 {} 个账户 (+{} / -{}) | 池子状态: Active {} / PendingOpen {} / Disabled {} / Drained {}",
+                        stats.pairs, feed.len(), added, removed.len(),
+                        stats.active, stats.pending_open, stats.disabled, stats.drained);
+                }
+            }
+            _ = stats_tick.tick() => {
+                for ws in feed.connection_stats() {
+                    log_connection_stats(&ws);
+                }
+                if let Some(manager) = &logs {
+                    for ws in manager.stats() {
+                        log_connection_stats(&ws);
+                    }
+                }
+                dedup.log_stats();
+                if let Some(forks) = &forks {
+                    forks.log_stats();
+                }
+                bus.log_stats();
+                if scanner.swaps_dropped > 0 {
+                    warn!("⚠️ [Scout] 成交解码繁忙，本周期丢弃 {} 笔交易", scanner.swaps_dropped);
+                    scanner.swaps_dropped = 0;
+                }
+            }
+            _ = rebalance.tick() => {
+                feed.rebalance();
+                if let Some(manager) = logs.as_mut() {
+                    manager.rebalance();
+                }
+            }
+            change = changes.recv() => match change {
+                Ok(InventoryChange::PairAdded(pair)) => {
+                    // 预算还有余量时立即订阅，否则交给下一轮排序决定是否替换
+                    let pools = pair.pools();
+                    let ranked = feed.len().saturating_sub(new_pools.len());
+                    if watch_pairs && inventory.is_pair_tradable(&pair) && ranked + pools.len() <= pair_budget {
+                        let mut added = 0;
+                        for pool in pools {
+                            if feed.subscribe(pool) {
+                                added += 1;
+                            }
+                        }
+                        feed.flush();
+                        if added > 0 {
+                            info!("➕ [Scout] 新套利对 {} 已加入监控名单 (+{})", pair.token_mint, added);
+                        }
+                    }
+                }
+                Ok(InventoryChange::PairRemoved(pair)) => {
+                    // 池子可能已经被挂到别的套利对上，只退订不再属于任何套利对的池子
+                    let mut removed = 0;
+                    for pool in pair.pools() {
+                        if feed.accounts().contains(&pool)
+                            && !new_pools.contains_key(&pool)
+                            && inventory.find_pair_by_pool(&pool).is_none()
+                        {
+                            feed.unsubscribe(&pool);
+                            if let Some(forks) = forks.as_mut() {
+                                forks.unwatch(&pool);
+                            }
+                            removed += 1;
                         }
-                    });
-                }
-            } else if source == "Orca" {
-                // Orca 日志全量打印
-                if let Some(event) = orca::parse_log_for_event(signature, logs) {
-                    info!("🌊 [Orca] 发现潜在活动! Tx: https://solscan.io/tx/{}", event.signature);
-                    
-                    let client = rpc_client.clone();
-                    let _kp = keypair.clone();
-                    let _cfg = config.clone();
-                    let sig = event.signature.clone();
-                    let inventory_clone = inventory.clone();
-
-                    tokio::spawn(async move {
-                        if let Some(full_event) = orca::fetch_and_parse_tx(client.clone(), &sig).await {
-                            info!("🌊 [Orca] 成功解析池子详情: Pool: {}, TokenA: {}, TokenB: {}", 
-                                full_event.pool_id, full_event.token_a, full_event.token_b);
-                            
-                            // 实时更新 Inventory
-                            inventory_clone.add_pool(full_event.token_a, full_event.token_b, full_event.pool_id);
-
-                            // 尝试获取池子当前价格
-                            match client.get_account_data(&full_event.pool_id).await {
-                                Ok(data) => {
-                                    if let Some(price_info) = Whirlpool::decode_current_price(&data) {
-                                         info!("💲 [Orca Pricing] Pool: {} | Price: {:.6} | Tick: {} | Liquidity: {}", 
-                                            full_event.pool_id, price_info.price, price_info.tick, price_info.liquidity);
-                                    }
-                                },
-                                Err(e) => {
-                                    info!("⚠️ [Orca Pricing] 获取账户数据失败: {}", e);
-                                }
+                    }
+                    feed.flush();
+                    if removed > 0 {
+                        info!("➖ [Scout] 套利对 {} 已删除，退订 {} 个账户", pair.token_mint, removed);
+                    }
+                }
+                Ok(InventoryChange::PoolDisabled { pool, state }) => {
+                    // 池子变为不可交易 (禁用 / 抽空) 时立即退订整个套利对
+                    let pools = inventory
+                        .find_pair_by_pool(&pool)
+                        .map(|p| p.pools())
+                        .unwrap_or_else(|| vec![pool]);
+                    let watched = pools.iter().any(|p| feed.accounts().contains(p));
+                    for p in &pools {
+                        feed.unsubscribe(p);
+                        new_pools.remove(p);
+                        if let Some(forks) = forks.as_mut() {
+                            forks.unwatch(p);
+                        }
+                    }
+                    feed.flush();
+                    if watched {
+                        info!("🚫 [Scout] 池子 {} 进入 {:?} 状态，已移出监控名单", pool, state);
+                    }
+                }
+                // 全量加载 Orca 池子时也会产生 PoolAdded，这里不据此订阅；扫描到的新池走 discovered 通道
+                Ok(InventoryChange::PoolAdded { .. }) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("⚠️ [Scout] 丢失 {} 条 Inventory 变更通知，立即重新排序", n);
+                    rerank.reset_immediately();
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            Some(pool) = discovered_rx.recv() => {
+                if new_pool_budget == 0 || new_pools.contains_key(&pool) {
+                    continue;
+                }
+                // 新池名额已满时替换最早订阅的新池
+                if new_pools.len() >= new_pool_budget {
+                    let oldest = new_pools.iter().min_by_key(|(_, since)| **since).map(|(p, _)| *p);
+                    if let Some(oldest) = oldest {
+                        new_pools.remove(&oldest);
+                        if inventory.find_pair_by_pool(&oldest).is_none() {
+                            feed.unsubscribe(&oldest);
+                            if let Some(forks) = forks.as_mut() {
+                                forks.unwatch(&oldest);
                             }
                         }
-                    });
+                    }
                 }
+                if feed.subscribe(pool) {
+                    new_pools.insert(pool, Instant::now());
+                    info!("👀 [Scout] 已订阅新池 {} ({} / {} 个新池名额，共 {} 个订阅)", pool, new_pools.len(), new_pool_budget, feed.len());
+                }
+                feed.flush();
             }
+            event = update_rx.recv() => match event {
+                // 多连接 / 多数据源的重复推送只处理第一次
+                Some(event) if !dedup.accept(&event) => {}
+                // 已被放弃的分叉上的账户状态不再交给下游
+                Some(StreamEvent::Account(update))
+                    if forks.as_mut().is_some_and(|forks| !forks.observe_account(&update)) => {}
+                Some(StreamEvent::Account(update)) => {
+                    // 记录活跃度，供下一轮排序使用
+                    inventory.record_pool_update(&update.pubkey);
+                    publish_pool_account(&inventory, &bus, update);
+                }
+                Some(StreamEvent::Transaction(update)) => {
+                    scanner.scan(&update);
+                }
+                Some(StreamEvent::Slot(update)) => {
+                    if let Some(forks) = forks.as_mut() {
+                        for rollback in forks.observe_slot(&update) {
+                            warn!("↩️ [Forks] 池子 {} 在 Slot {} 的状态所在分叉已被放弃，回滚到 {}",
+                                rollback.pubkey, rollback.abandoned_slot,
+                                rollback.restored.as_ref().map_or("未知状态".to_string(), |r| format!("Slot {}", r.slot)));
+                            // 重新发布仍然有效的状态，覆盖下游缓存的幽灵流动性
+                            if let Some(restored) = rollback.restored {
+                                publish_pool_account(&inventory, &bus, AccountUpdate { received_at: Instant::now(), ..restored });
+                            }
+                        }
+                    }
+                    bus.publish(BusEvent::Slot(update));
+                }
+                None => break,
+            },
         }
     }
 
     Ok(())
 }
+
+/// 更新池子状态并发布；池子变为不可交易时不再发布，退订由 PoolDisabled 变更通知处理
+fn publish_pool_account(inventory: &Inventory, bus: &EventBus, update: AccountUpdate) {
+    let disabled = matches!(
+        inventory.observe_pool_account(&update.pubkey, &update.data),
+        Some(state) if !state.is_tradable(unix_now())
+    );
+    if !disabled {
+        bus.publish(BusEvent::AccountUpdate(update));
+    }
+}
+
+/// 程序日志扫描: 识别新池与成交，拉取完整交易解码后发布到总线
+struct LogScanner {
+    rpc_client: Arc<RpcClient>,
+    inventory: Arc<Inventory>,
+    bus: EventBus,
+    /// 扫描到的新池，回传给主循环订阅账户
+    discovered: mpsc::Sender<Pubkey>,
+    scan_new_pools: bool,
+    scan_swaps: bool,
+    /// 拉取交易的确认级别 (日志可能按 processed 推送，交易要等确认后才能拉到)
+    tx_commitment: CommitmentConfig,
+    swap_permits: Arc<Semaphore>,
+    raydium_log_count: u64,
+    swaps_dropped: u64,
+}
+
+impl LogScanner {
+    fn scan(&mut self, update: &TransactionUpdate) {
+        if update.is_failed {
+            return;
+        }
+        let logs = &update.logs;
+        let signature = &update.signature.to_string();
+        // 两个程序的日志共用一条通道，按日志中出现的程序区分来源
+        let mentions = |program: &str| logs.iter().any(|l| l.contains(program));
+
+        if mentions(RAYDIUM_AMM_V4) {
+            self.raydium_log_count += 1;
+            // 仅周期性打印扫描进度，减少刷屏
+            if self.raydium_log_count % 1000 == 0 {
+                info!("✨ [Raydium] 监测中... 已扫描 {} 条相关日志", self.raydium_log_count);
+            }
+            if self.scan_new_pools {
+                if let Some(sig) = raydium::parse_log_for_new_pool(signature, logs) {
+                    self.spawn_raydium_new_pool(sig);
+                }
+            }
+        }
+        if mentions(ORCA_WHIRLPOOL) && self.scan_new_pools {
+            if let Some(sig) = orca::parse_log_for_event(signature, logs) {
+                self.spawn_orca_new_pool(sig);
+            }
+        }
+        if self.scan_swaps && swaps::mentions_swap(logs) {
+            self.spawn_swap_decode(signature.clone());
+        }
+    }
+
+    fn spawn_raydium_new_pool(&self, sig: String) {
+        info!("✨ [Raydium] 发现 initialize2! Tx: https://solscan.io/tx/{}", sig);
+        let client = self.rpc_client.clone();
+        let bus = self.bus.clone();
+        let commitment = self.tx_commitment;
+
+        tokio::spawn(async move {
+            if let Some(full_event) = raydium::fetch_and_parse_tx(client, &sig, commitment).await {
+                info!("🎉 [Raydium] 成功解析池子详情: Pool: {}, Coin: {}, PC: {}, Market: {}, 开放时间: {}, 初始流动性: {} / {}",
+                    full_event.pool_id, full_event.token_a, full_event.token_b, full_event.market,
+                    full_event.open_time, full_event.init_coin_amount, full_event.init_pc_amount);
+
+                bus.publish(BusEvent::NewPool(NewPool {
+                    dex: Dex::Raydium,
+                    signature: full_event.signature,
+                    pool: full_event.pool_id,
+                    token_a: full_event.token_a,
+                    token_b: full_event.token_b,
+                    open_time: Some(full_event.open_time).filter(|t| *t > 0),
+                }));
+            }
+        });
+    }
+
+    fn spawn_orca_new_pool(&self, sig: String) {
+        info!("🌊 [Orca] 发现 InitializePool! Tx: https://solscan.io/tx/{}", sig);
+        let client = self.rpc_client.clone();
+        let bus = self.bus.clone();
+        let inventory = self.inventory.clone();
+        let discovered = self.discovered.clone();
+        let commitment = self.tx_commitment;
+
+        tokio::spawn(async move {
+            if let Some(full_event) = orca::fetch_and_parse_tx(client, &sig, commitment).await {
+                info!("🌊 [Orca] 成功解析池子详情 ({:?}): Pool: {}, TokenA: {}, TokenB: {}, TickSpacing: {}, Fee: {:?}, 初始价格: {:.6}",
+                    full_event.variant, full_event.pool_id, full_event.token_a, full_event.token_b,
+                    full_event.tick_spacing, full_event.fee_rate, sqrt_price_x64_to_price(full_event.initial_sqrt_price));
+
+                // 实时更新 Inventory
+                inventory.add_pool(full_event.token_a, full_event.token_b, full_event.pool_id);
+                let _ = discovered.send(full_event.pool_id).await;
+
+                bus.publish(BusEvent::NewPool(NewPool {
+                    dex: Dex::Orca,
+                    signature: full_event.signature,
+                    pool: full_event.pool_id,
+                    token_a: full_event.token_a,
+                    token_b: full_event.token_b,
+                    open_time: None,
+                }));
+            }
+        });
+    }
+
+    fn spawn_swap_decode(&mut self, sig: String) {
+        let permit = match self.swap_permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                self.swaps_dropped += 1;
+                return;
+            }
+        };
+        let client = self.rpc_client.clone();
+        let bus = self.bus.clone();
+        let commitment = self.tx_commitment;
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Some(resolved) = tx::fetch_transaction(client, &sig, 2, commitment).await {
+                for swap in swaps::decode_swaps(&resolved) {
+                    log::debug!("💱 [{:?}] Pool: {} | a_to_b: {} | {} -> {} | Signer: {}",
+                        swap.dex, swap.pool, swap.a_to_b, swap.amount_in, swap.amount_out, swap.signer);
+                    bus.publish(BusEvent::Swap(swap));
+                }
+            }
+        });
+    }
+}
+
+/// 池子账户数据源:
+/// WebSocket 由 SubscriptionManager 分散到多条连接；Geyser 共用一条流，名单变化时整体替换过滤器
+enum AccountFeed {
+    WebSocket {
+        manager: SubscriptionManager,
+        accounts: HashSet<Pubkey>,
+    },
+    Geyser {
+        handle: GeyserHandle,
+        accounts: HashSet<Pubkey>,
+        slots: bool,
+        commitment: CommitmentLevel,
+        dirty: bool,
+    },
+}
+
+impl AccountFeed {
+    fn accounts(&self) -> &HashSet<Pubkey> {
+        match self {
+            AccountFeed::WebSocket { accounts, .. } => accounts,
+            AccountFeed::Geyser { accounts, .. } => accounts,
+        }
+    }
+
+    fn subscribed(&self) -> HashSet<Pubkey> {
+        self.accounts().clone()
+    }
+
+    fn len(&self) -> usize {
+        self.accounts().len()
+    }
+
+    /// 可同时订阅的账户数 (Geyser 没有限制)
+    fn capacity(&self) -> usize {
+        match self {
+            AccountFeed::WebSocket { manager, .. } => manager.capacity(),
+            AccountFeed::Geyser { .. } => usize::MAX,
+        }
+    }
+
+    /// 返回 true 表示新增了订阅
+    fn subscribe(&mut self, pubkey: Pubkey) -> bool {
+        match self {
+            AccountFeed::WebSocket { manager, accounts } => {
+                if accounts.contains(&pubkey) || !manager.subscribe(Subscription::Account(pubkey)) {
+                    return false;
+                }
+                accounts.insert(pubkey)
+            }
+            AccountFeed::Geyser { accounts, dirty, .. } => {
+                let added = accounts.insert(pubkey);
+                *dirty |= added;
+                added
+            }
+        }
+    }
+
+    fn unsubscribe(&mut self, pubkey: &Pubkey) {
+        match self {
+            AccountFeed::WebSocket { manager, accounts } => {
+                if accounts.remove(pubkey) {
+                    manager.unsubscribe(&Subscription::Account(*pubkey));
+                }
+            }
+            AccountFeed::Geyser { accounts, dirty, .. } => {
+                *dirty |= accounts.remove(pubkey);
+            }
+        }
+    }
+
+    /// 提交累积的变更 (Geyser 需要重新发送完整的订阅请求)
+    fn flush(&mut self) {
+        if let AccountFeed::Geyser { handle, accounts, slots, commitment, dirty } = self {
+            if *dirty {
+                let filter = GeyserFilter {
+                    accounts: accounts.iter().copied().collect(),
+                    slots: *slots,
+                    commitment: *commitment,
+                    ..Default::default()
+                };
+                if !handle.update(&filter) {
+                    error!("❌ Geyser 订阅任务已退出，无法更新订阅");
+                }
+                *dirty = false;
+            }
+        }
+    }
+
+    fn rebalance(&mut self) {
+        if let AccountFeed::WebSocket { manager, .. } = self {
+            manager.rebalance();
+        }
+    }
+
+    fn connection_stats(&self) -> Vec<ConnectionStats> {
+        match self {
+            AccountFeed::WebSocket { manager, .. } => manager.stats(),
+            AccountFeed::Geyser { handle, .. } => vec![handle.stats()],
+        }
+    }
+}
+
+fn log_connection_stats(stats: &ConnectionStats) {
+    info!("📶 {} | {:?} | 订阅 {} | 重连 {} (断流 {} / 心跳超时 {}) | 单独重订阅 {} | 补拉 {} | 最后推送 {:?} 前 | 已连接 {:?}",
+        stats.url, stats.state, stats.subscriptions, stats.reconnects, stats.dead_streams,
+        stats.heartbeat_timeouts, stats.resubscribes, stats.resyncs, stats.last_event_age, stats.uptime);
+}
@@ -1,161 +1,241 @@
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Signature;
 use std::str::FromStr;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::sync::Arc;
-use solana_transaction_status::{EncodedTransaction, UiMessage};
 use crate::state::Inventory;
+use crate::amm::orca_whirlpool::Whirlpool;
+use crate::scout::api::fetch_orca_pools;
+use crate::scout::tx::{self, InstructionView, ResolvedTransaction};
 // use solana_client::rpc_config::{RpcProgramAccountsConfig, RpcAccountInfoConfig};
 // use solana_client::rpc_filter::{RpcFilterType, Memcmp};
 // use solana_sdk::commitment_config::CommitmentConfig;
 // use solana_account_decoder::UiAccountEncoding;
-use log::{info, error};
+use log::{info, warn, error};
 
 // Orca Whirlpool Program ID
 pub const ORCA_WHIRLPOOL_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
 
-#[derive(Debug)]
+/// Anchor 指令 discriminator: sha256("global:<name>")[..8]
+const INITIALIZE_POOL: [u8; 8] = [95, 180, 10, 172, 84, 174, 232, 40];
+const INITIALIZE_POOL_V2: [u8; 8] = [207, 45, 87, 242, 27, 63, 204, 67];
+const INITIALIZE_POOL_WITH_ADAPTIVE_FEE: [u8; 8] = [143, 94, 96, 76, 172, 124, 119, 199];
+
+/// FeeTier 账户: discriminator(8) + whirlpools_config(32) + tick_spacing(2) + default_fee_rate(2)
+const FEE_TIER_DEFAULT_FEE_RATE_OFFSET: usize = 42;
+
+/// 池子初始化指令的变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitializePoolVariant {
+    /// initialize_pool: 只支持 SPL Token
+    V1,
+    /// initialize_pool_v2: 支持 Token-2022 (多了 token_badge / token_program_a/b)
+    V2,
+    /// initialize_pool_with_adaptive_fee: 动态费率池，tick_spacing 由 AdaptiveFeeTier 决定
+    AdaptiveFee,
+}
+
+/// 各变体中用到的账户位置
+struct AccountLayout {
+    config: usize,
+    mint_a: usize,
+    mint_b: usize,
+    whirlpool: usize,
+    vault_a: usize,
+    vault_b: usize,
+    fee_tier: usize,
+    len: usize,
+}
+
+impl InitializePoolVariant {
+    fn from_discriminator(data: &[u8]) -> Option<Self> {
+        match data.get(..8)? {
+            d if d == INITIALIZE_POOL => Some(Self::V1),
+            d if d == INITIALIZE_POOL_V2 => Some(Self::V2),
+            d if d == INITIALIZE_POOL_WITH_ADAPTIVE_FEE => Some(Self::AdaptiveFee),
+            _ => None,
+        }
+    }
+
+    fn layout(self) -> AccountLayout {
+        match self {
+            // config, mint_a, mint_b, funder, whirlpool, vault_a, vault_b, fee_tier, token_program, system_program, rent
+            Self::V1 => AccountLayout { config: 0, mint_a: 1, mint_b: 2, whirlpool: 4, vault_a: 5, vault_b: 6, fee_tier: 7, len: 11 },
+            // config, mint_a, mint_b, badge_a, badge_b, funder, whirlpool, vault_a, vault_b, fee_tier,
+            // token_program_a, token_program_b, system_program, rent
+            Self::V2 => AccountLayout { config: 0, mint_a: 1, mint_b: 2, whirlpool: 6, vault_a: 7, vault_b: 8, fee_tier: 9, len: 14 },
+            // config, mint_a, mint_b, badge_a, badge_b, funder, initialize_pool_authority, whirlpool, oracle,
+            // vault_a, vault_b, adaptive_fee_tier, token_program_a, token_program_b, system_program, rent
+            Self::AdaptiveFee => AccountLayout { config: 0, mint_a: 1, mint_b: 2, whirlpool: 7, vault_a: 9, vault_b: 10, fee_tier: 11, len: 16 },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct OrcaPoolEvent {
     pub signature: String,
     pub pool_id: Pubkey,
     pub token_a: Pubkey,
     pub token_b: Pubkey,
+    pub variant: InitializePoolVariant,
+    pub whirlpools_config: Pubkey,
+    /// FeeTier (AdaptiveFee 变体为 AdaptiveFeeTier) 账户
+    pub fee_tier: Pubkey,
+    pub token_vault_a: Pubkey,
+    pub token_vault_b: Pubkey,
+    /// AdaptiveFee 变体的指令参数中没有 tick_spacing，需从池子账户补全 (补全前为 0)
+    pub tick_spacing: u16,
+    /// 初始价格 (Q64.64)
+    pub initial_sqrt_price: u128,
+    /// 费率 (百万分之一)，取自 FeeTier 的默认费率，拉取账户失败时为 None
+    pub fee_rate: Option<u16>,
 }
 
-pub fn parse_log_for_event(signature: &str, logs: &[String]) -> Option<OrcaPoolEvent> {
-    // 简单的日志过滤
-    // Orca 的 InitializePool 日志通常不如 Raydium 明显
-    // 我们主要关注 "Program log: Instruction: InitializePool"
-    
-    let mut is_orca_init = false;
-    for log in logs {
-        if log.contains(ORCA_WHIRLPOOL_ID) && log.contains("InitializePool") {
-            is_orca_init = true;
-            break;
-        }
-    }
-    
-    if is_orca_init {
-        Some(OrcaPoolEvent {
-            signature: signature.to_string(),
-            pool_id: Pubkey::default(), // 需进一步解析
-            token_a: Pubkey::default(),
-            token_b: Pubkey::default(),
-        })
-    } else {
-        None
-    }
+/// 日志预筛: Anchor 会打印 "Instruction: InitializePool" / "InitializePoolV2" / "InitializePoolWithAdaptiveFee"
+/// 程序 ID 出现在 invoke 日志中，不在同一行，因此这里只匹配指令名，精确校验由 `fetch_and_parse_tx` 完成
+pub fn parse_log_for_event(signature: &str, logs: &[String]) -> Option<String> {
+    logs.iter()
+        .any(|log| log.starts_with("Program log: Instruction: InitializePool"))
+        .then(|| signature.to_string())
 }
 
 // 异步获取并解析交易，提取 Pool 信息
-pub async fn fetch_and_parse_tx(rpc_client: Arc<RpcClient>, signature: &str) -> Option<OrcaPoolEvent> {
-    let sig = match Signature::from_str(signature) {
-        Ok(s) => s,
-        Err(_) => return None,
+pub async fn fetch_and_parse_tx(rpc_client: Arc<RpcClient>, signature: &str, commitment: CommitmentConfig) -> Option<OrcaPoolEvent> {
+    let tx = tx::fetch_transaction(rpc_client.clone(), signature, 3, commitment).await?;
+    let mut event = parse_transaction(signature, &tx)?;
+    resolve_fee_tier(&rpc_client, &mut event).await;
+    Some(event)
+}
+
+/// 从已拉取的交易中找出池子初始化指令 (不查询 Fee Tier，失败的交易返回 None)
+pub fn parse_transaction(signature: &str, tx: &ResolvedTransaction) -> Option<OrcaPoolEvent> {
+    if tx.failed {
+        return None;
+    }
+    let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_ID).ok()?;
+    // 包括通过 CPI 创建的池子
+    tx.instructions
+        .iter()
+        .filter(|ix| ix.program_id == program_id)
+        .find_map(|ix| decode_initialize_pool(signature, ix))
+}
+
+/// 解码任一池子初始化指令，其他指令返回 None
+pub fn decode_initialize_pool(signature: &str, ix: &InstructionView) -> Option<OrcaPoolEvent> {
+    let variant = InitializePoolVariant::from_discriminator(&ix.data)?;
+    let layout = variant.layout();
+    if ix.accounts.len() < layout.len {
+        return None;
+    }
+    let args = &ix.data[8..];
+    let u16_at = |offset: usize| Some(u16::from_le_bytes(args.get(offset..offset + 2)?.try_into().ok()?));
+    let u128_at = |offset: usize| Some(u128::from_le_bytes(args.get(offset..offset + 16)?.try_into().ok()?));
+
+    let (tick_spacing, initial_sqrt_price) = match variant {
+        // bumps(1) + tick_spacing(2) + initial_sqrt_price(16)
+        InitializePoolVariant::V1 => (u16_at(1)?, u128_at(3)?),
+        // tick_spacing(2) + initial_sqrt_price(16)
+        InitializePoolVariant::V2 => (u16_at(0)?, u128_at(2)?),
+        // initial_sqrt_price(16) + trade_enable_timestamp(Option<u64>)
+        InitializePoolVariant::AdaptiveFee => (0, u128_at(0)?),
     };
 
-    // 重试机制
-    for _ in 0..3 {
-        match rpc_client.get_transaction_with_config(&sig, solana_client::rpc_config::RpcTransactionConfig {
-            encoding: Some(solana_transaction_status::UiTransactionEncoding::Json),
-            commitment: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
-            max_supported_transaction_version: Some(0),
-        }).await {
-            Ok(tx) => {
-                if let Some(_transaction) = tx.transaction.transaction.decode() {
-                    match tx.transaction.transaction {
-                        EncodedTransaction::Json(ui_tx) => {
-                            let message = ui_tx.message;
-                            match message {
-                                UiMessage::Raw(msg) => {
-                                    let account_keys = msg.account_keys;
-                                    for ix in msg.instructions {
-                                        let program_id_index = ix.program_id_index as usize;
-                                        if program_id_index < account_keys.len() {
-                                            let program_id = &account_keys[program_id_index];
-                                            if program_id == ORCA_WHIRLPOOL_ID {
-                                                // InitializePool Instruction Accounts (Simplified):
-                                                // 0. Configs
-                                                // 1. TokenMintA
-                                                // 2. TokenMintB
-                                                // 3. Funder
-                                                // 4. Whirlpool (Pool ID)
-                                                
-                                                if ix.accounts.len() >= 5 {
-                                                    let token_a_idx = ix.accounts[1] as usize;
-                                                    let token_b_idx = ix.accounts[2] as usize;
-                                                    let pool_idx = ix.accounts[4] as usize;
-                                                    
-                                                    if pool_idx < account_keys.len() {
-                                                        return Some(OrcaPoolEvent {
-                                                            signature: signature.to_string(),
-                                                            pool_id: Pubkey::from_str(&account_keys[pool_idx]).unwrap_or_default(),
-                                                            token_a: Pubkey::from_str(&account_keys[token_a_idx]).unwrap_or_default(),
-                                                            token_b: Pubkey::from_str(&account_keys[token_b_idx]).unwrap_or_default(),
-                                                        });
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-                return None;
-            },
-            Err(_) => {
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            }
+    let key = |i: usize| ix.accounts[i];
+    Some(OrcaPoolEvent {
+        signature: signature.to_string(),
+        pool_id: key(layout.whirlpool),
+        token_a: key(layout.mint_a),
+        token_b: key(layout.mint_b),
+        variant,
+        whirlpools_config: key(layout.config),
+        fee_tier: key(layout.fee_tier),
+        token_vault_a: key(layout.vault_a),
+        token_vault_b: key(layout.vault_b),
+        tick_spacing,
+        initial_sqrt_price,
+        fee_rate: None,
+    })
+}
+
+/// 补全费率 (FeeTier 默认费率) 和 AdaptiveFee 变体的 tick_spacing
+/// AdaptiveFeeTier 的布局不同，此时费率直接取池子初始化后的 fee_rate
+async fn resolve_fee_tier(rpc_client: &RpcClient, event: &mut OrcaPoolEvent) {
+    let accounts = match rpc_client.get_multiple_accounts(&[event.fee_tier, event.pool_id]).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            warn!("⚠️ [Orca] 获取 FeeTier 失败 {}: {}", event.fee_tier, e);
+            return;
         }
+    };
+    let read_u16 = |data: &[u8], offset: usize| {
+        data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let fee_tier = accounts.first().and_then(|a| a.as_ref());
+    let pool = accounts.get(1).and_then(|a| a.as_ref());
+
+    if event.variant != InitializePoolVariant::AdaptiveFee {
+        event.fee_rate = fee_tier.and_then(|a| read_u16(&a.data, FEE_TIER_DEFAULT_FEE_RATE_OFFSET));
+    } else if let Some(pool) = pool {
+        event.fee_rate = read_u16(&pool.data, Whirlpool::FEE_RATE_OFFSET);
+        event.tick_spacing = read_u16(&pool.data, Whirlpool::TICK_SPACING_OFFSET).unwrap_or_default();
     }
-    None
 }
 
-use reqwest;
-use serde_json::Value;
-
 /// Cold Start: 全量加载 Orca Whirlpool 账户到内存 (Via REST API)
 pub async fn load_all_whirlpools(_rpc_client: Arc<RpcClient>, inventory: Arc<Inventory>) {
     info!("🔄 开始全量加载 Orca Whirlpool 账户 (Via Orca API)...");
     
-    let url = "https://api.mainnet.orca.so/v1/whirlpool/list";
-    
-    match reqwest::get(url).await {
-        Ok(resp) => {
-            match resp.json::<Value>().await {
-                Ok(json) => {
-                    if let Some(whirlpools) = json.get("whirlpools").and_then(|v| v.as_array()) {
-                        info!("✅ 成功获取 {} 个 Orca Whirlpool 信息", whirlpools.len());
-                        let mut count = 0;
-                        for pool in whirlpools {
-                            let address_str = pool.get("address").and_then(|v| v.as_str());
-                            let token_a_str = pool.get("tokenA").and_then(|v| v.get("mint")).and_then(|v| v.as_str());
-                            let token_b_str = pool.get("tokenB").and_then(|v| v.get("mint")).and_then(|v| v.as_str());
-                            
-                            if let (Some(addr), Some(mint_a), Some(mint_b)) = (address_str, token_a_str, token_b_str) {
-                                if let (Ok(pool_pk), Ok(token_a), Ok(token_b)) = (
-                                    Pubkey::from_str(addr),
-                                    Pubkey::from_str(mint_a),
-                                    Pubkey::from_str(mint_b)
-                                ) {
-                                    inventory.add_pool(token_a, token_b, pool_pk);
-                                    count += 1;
-                                }
-                            }
-                        }
-                        info!("📥 已索引 {} 个 Orca 池子到内存数据库", count);
-                    } else {
-                        error!("❌ Orca API 返回格式错误: 找不到 'whirlpools' 数组");
-                    }
-                },
-                Err(e) => error!("❌ 解析 Orca API JSON 失败: {}", e),
+    // 与 Discovery 共用 PoolSource 缓存，启动时不会重复下载
+    match fetch_orca_pools().await {
+        Ok(pools) => {
+            for pool in &pools {
+                inventory.add_pool(pool.token_a, pool.token_b, pool.address);
             }
+            info!("📥 已索引 {} 个 Orca 池子到内存数据库", pools.len());
         },
         Err(e) => error!("❌ 请求 Orca API 失败: {}", e),
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_initialize_pool_variants() {
+        let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_ID).unwrap();
+        let sqrt_price: u128 = 1 << 64;
+        let ix = |data: Vec<u8>, n: usize| InstructionView {
+            program_id,
+            accounts: (0..n).map(|_| Pubkey::new_unique()).collect(),
+            data,
+            outer_index: 0,
+            inner: true,
+        };
+
+        let mut v1 = INITIALIZE_POOL.to_vec();
+        v1.push(255);
+        v1.extend_from_slice(&64u16.to_le_bytes());
+        v1.extend_from_slice(&sqrt_price.to_le_bytes());
+        let v1 = ix(v1, 11);
+        let event = decode_initialize_pool("sig", &v1).unwrap();
+        assert_eq!(event.variant, InitializePoolVariant::V1);
+        assert_eq!((event.pool_id, event.fee_tier, event.token_vault_b), (v1.accounts[4], v1.accounts[7], v1.accounts[6]));
+        assert_eq!((event.tick_spacing, event.initial_sqrt_price), (64, sqrt_price));
+
+        let mut v2 = INITIALIZE_POOL_V2.to_vec();
+        v2.extend_from_slice(&8u16.to_le_bytes());
+        v2.extend_from_slice(&sqrt_price.to_le_bytes());
+        let v2 = ix(v2, 14);
+        let event = decode_initialize_pool("sig", &v2).unwrap();
+        assert_eq!(event.variant, InitializePoolVariant::V2);
+        assert_eq!((event.token_a, event.pool_id, event.token_vault_a), (v2.accounts[1], v2.accounts[6], v2.accounts[7]));
+        assert_eq!(event.tick_spacing, 8);
+
+        // 账户数不足 / 其他指令不应被误判
+        let mut swap = vec![248, 198, 158, 145, 225, 117, 135, 200];
+        swap.extend_from_slice(&[0; 32]);
+        assert!(decode_initialize_pool("sig", &ix(swap, 11)).is_none());
+        assert!(decode_initialize_pool("sig", &ix(v2.data.clone(), 11)).is_none());
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use log::{info, warn};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use crate::amm::unix_now;
+use crate::config::PoolSourceConfig;
+
+type SourceResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// 缓存文件格式版本，结构变化时递增 (旧版本文件会被当作未命中)
+pub const CACHE_SCHEMA_VERSION: u32 = 1;
+
+static GLOBAL: OnceLock<PoolSource> = OnceLock::new();
+
+/// 磁盘缓存条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    schema_version: u32,
+    url: String,
+    fetched_at: u64,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: Arc<Value>,
+}
+
+impl CacheEntry {
+    fn is_fresh(&self, ttl: Duration, now: u64) -> bool {
+        now.saturating_sub(self.fetched_at) < ttl.as_secs()
+    }
+}
+
+/// 池子列表数据源 (Raydium / Orca REST API 的统一入口)
+/// - 按名称缓存到 cache_dir，TTL 内直接使用缓存
+/// - 过期后带 ETag / If-Modified-Since 条件请求，304 时复用缓存
+/// - 同一名称的并发请求只会发出一次网络请求
+/// - 网络失败时回退到过期缓存
+pub struct PoolSource {
+    client: Client,
+    cache_dir: PathBuf,
+    ttl: Duration,
+    // 每个名称一把锁: 持锁期间完成 "查缓存 -> 请求 -> 写缓存"，后来者直接命中内存缓存
+    entries: Mutex<HashMap<String, Arc<Mutex<Option<CacheEntry>>>>>,
+}
+
+impl PoolSource {
+    pub fn new(config: &PoolSourceConfig) -> Self {
+        Self {
+            client: Client::new(),
+            cache_dir: PathBuf::from(&config.cache_dir),
+            ttl: Duration::from_secs(config.ttl_secs),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 使用配置初始化全局实例 (只能初始化一次，需在第一次拉取前调用)
+    pub fn init(config: &PoolSourceConfig) {
+        if GLOBAL.set(PoolSource::new(config)).is_err() {
+            warn!("⚠️ PoolSource 已初始化，忽略新的配置");
+        }
+    }
+
+    /// 全局实例，未初始化时使用默认配置
+    pub fn global() -> &'static PoolSource {
+        GLOBAL.get_or_init(|| PoolSource::new(&PoolSourceConfig::default()))
+    }
+
+    /// 获取 JSON 列表，name 同时作为缓存文件名
+    pub async fn fetch_json(&self, name: &str, url: &str) -> SourceResult<Arc<Value>> {
+        let slot = self
+            .entries
+            .lock()
+            .await
+            .entry(name.to_string())
+            .or_default()
+            .clone();
+        let mut cached = slot.lock().await;
+
+        let path = self.cache_path(name);
+        if cached.is_none() {
+            *cached = read_cache(&path, url);
+        }
+
+        let now = unix_now();
+        if let Some(entry) = cached.as_ref() {
+            if entry.is_fresh(self.ttl, now) {
+                return Ok(entry.body.clone());
+            }
+        }
+
+        match self.request(url, cached.as_ref()).await {
+            Ok(Some(entry)) => {
+                info!("🌐 [PoolSource] {} 已更新", name);
+                self.write_cache(&path, &entry).await;
+                let body = entry.body.clone();
+                *cached = Some(entry);
+                Ok(body)
+            }
+            Ok(None) => {
+                // 304 Not Modified: 续期缓存
+                let entry = cached.as_mut().ok_or("304 response without cached entry")?;
+                entry.fetched_at = now;
+                info!("📂 [PoolSource] {} 未变化 (304)，沿用缓存", name);
+                let entry = entry.clone();
+                self.write_cache(&path, &entry).await;
+                Ok(entry.body)
+            }
+            Err(e) => match cached.as_ref() {
+                Some(entry) => {
+                    warn!("⚠️ [PoolSource] 请求 {} 失败: {}，使用过期缓存", name, e);
+                    Ok(entry.body.clone())
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// 发起 (条件) 请求，返回 None 表示 304
+    async fn request(&self, url: &str, cached: Option<&CacheEntry>) -> SourceResult<Option<CacheEntry>> {
+        let mut req = self.client.get(url);
+        if let Some(entry) = cached {
+            if let Some(etag) = &entry.etag {
+                req = req.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                req = req.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let resp = req.send().await?;
+        if resp.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(None);
+        }
+        let resp = resp.error_for_status()?;
+        let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body: Value = resp.json().await?;
+
+        Ok(Some(CacheEntry {
+            schema_version: CACHE_SCHEMA_VERSION,
+            url: url.to_string(),
+            fetched_at: unix_now(),
+            etag,
+            last_modified,
+            body: Arc::new(body),
+        }))
+    }
+
+    fn cache_path(&self, name: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", name))
+    }
+
+    async fn write_cache(&self, path: &Path, entry: &CacheEntry) {
+        let path = path.to_path_buf();
+        let entry = entry.clone();
+        match tokio::task::spawn_blocking(move || write_cache(&path, &entry)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("⚠️ [PoolSource] 写入缓存失败: {}", e),
+            Err(e) => warn!("⚠️ [PoolSource] 写入缓存任务失败: {}", e),
+        }
+    }
+}
+
+/// 读取磁盘缓存，版本或 URL 不一致时视为未命中
+fn read_cache(path: &Path, url: &str) -> Option<CacheEntry> {
+    let content = std::fs::read(path).ok()?;
+    let entry: CacheEntry = match serde_json::from_slice(&content) {
+        Ok(e) => e,
+        Err(e) => {
+            warn!("⚠️ [PoolSource] 缓存文件损坏 {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if entry.schema_version != CACHE_SCHEMA_VERSION || entry.url != url {
+        info!("🧹 [PoolSource] 忽略不兼容的缓存文件 {}", path.display());
+        return None;
+    }
+    Some(entry)
+}
+
+fn write_cache(path: &Path, entry: &CacheEntry) -> SourceResult<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // 先写临时文件再 rename，避免中途崩溃留下半个文件
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(entry)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_schema_and_ttl() {
+        let dir = std::env::temp_dir().join(format!("pool_source_test_{}", std::process::id()));
+        let path = dir.join("orca.json");
+        let url = "https://example.com/pools";
+        let mut entry = CacheEntry {
+            schema_version: CACHE_SCHEMA_VERSION,
+            url: url.to_string(),
+            fetched_at: 1_000,
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+            body: Arc::new(serde_json::json!({"whirlpools": []})),
+        };
+        write_cache(&path, &entry).unwrap();
+
+        let loaded = read_cache(&path, url).unwrap();
+        assert_eq!(loaded.etag.as_deref(), Some("\"abc\""));
+        assert!(loaded.is_fresh(Duration::from_secs(60), 1_059));
+        assert!(!loaded.is_fresh(Duration::from_secs(60), 1_060));
+        // URL 变化或版本不一致都视为未命中
+        assert!(read_cache(&path, "https://example.com/other").is_none());
+
+        entry.schema_version = CACHE_SCHEMA_VERSION + 1;
+        write_cache(&path, &entry).unwrap();
+        assert!(read_cache(&path, url).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,97 +1,157 @@
 use solana_sdk::pubkey::Pubkey;
-use solana_sdk::signature::Signature;
 use std::str::FromStr;
 use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::sync::Arc;
-use solana_transaction_status::{EncodedTransaction, UiMessage};
+use crate::scout::tx::{self, InstructionView, ResolvedTransaction};
 
-#[derive(Debug)]
+// Raydium AMM V4 Program ID
+pub const RAYDIUM_AMM_V4_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+
+/// initialize2 指令: tag(1) + nonce(1) + open_time(8) + init_pc_amount(8) + init_coin_amount(8)
+const INITIALIZE2_TAG: u8 = 1;
+const INITIALIZE2_DATA_LEN: usize = 26;
+
+/// initialize2 的账户列表 (共 21 个)，只列出用到的位置
+mod accounts {
+    pub const AMM: usize = 4;
+    pub const AUTHORITY: usize = 5;
+    pub const OPEN_ORDERS: usize = 6;
+    pub const LP_MINT: usize = 7;
+    pub const COIN_MINT: usize = 8;
+    pub const PC_MINT: usize = 9;
+    pub const COIN_VAULT: usize = 10;
+    pub const PC_VAULT: usize = 11;
+    pub const TARGET_ORDERS: usize = 12;
+    pub const MARKET_PROGRAM: usize = 15;
+    pub const MARKET: usize = 16;
+    pub const LEN: usize = 21;
+}
+
+#[derive(Debug, Clone)]
 pub struct NewPoolEvent {
     pub signature: String,
     pub pool_id: Pubkey,
+    /// Coin (Base) Mint
     pub token_a: Pubkey,
+    /// PC (Quote) Mint
     pub token_b: Pubkey,
+    /// 开放交易时间 (Unix 秒)，0 表示立即开放
     pub open_time: u64,
+    pub authority: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    pub lp_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub market_program: Pubkey,
+    pub market: Pubkey,
+    pub nonce: u8,
+    /// 初始注入的流动性 (最小单位)
+    pub init_coin_amount: u64,
+    pub init_pc_amount: u64,
+}
+
+/// 日志预筛: Raydium 在执行 initialize2 时会打印 "initialize2: InitializeInstruction2 { .. }"
+/// 只返回签名，池子详情需拉取交易后由 `fetch_and_parse_tx` 解码
+pub fn parse_log_for_new_pool(signature: &str, logs: &[String]) -> Option<String> {
+    logs.iter()
+        .any(|log| log.starts_with("Program log: initialize2"))
+        .then(|| signature.to_string())
+}
+
+// 异步获取并解析交易，提取 Pool 信息
+pub async fn fetch_and_parse_tx(rpc_client: Arc<RpcClient>, signature: &str, commitment: CommitmentConfig) -> Option<NewPoolEvent> {
+    let tx = tx::fetch_transaction(rpc_client, signature, 5, commitment).await?;
+    parse_transaction(signature, &tx)
 }
 
-pub fn parse_log_for_new_pool(signature: &str, logs: &[String]) -> Option<NewPoolEvent> {
-    // 简单的日志过滤
-    let mut is_raydium_init = false;
-    for log in logs {
-        if log.contains("Initialize2") || log.contains("Initialize") {
-            is_raydium_init = true;
-            break;
-        }
+/// 从已拉取的交易中找出 initialize2 (失败的交易返回 None)
+pub fn parse_transaction(signature: &str, tx: &ResolvedTransaction) -> Option<NewPoolEvent> {
+    if tx.failed {
+        return None;
     }
-    
-    if is_raydium_init {
-        Some(NewPoolEvent {
-            signature: signature.to_string(),
-            pool_id: Pubkey::default(), // 需进一步解析
-            token_a: Pubkey::default(),
-            token_b: Pubkey::default(),
-            open_time: 0,
-        })
-    } else {
-        None
+    let program_id = Pubkey::from_str(RAYDIUM_AMM_V4_ID).ok()?;
+    // 包括通过 CPI (例如 Launchpad / 聚合器) 创建的池子
+    tx.instructions
+        .iter()
+        .filter(|ix| ix.program_id == program_id)
+        .find_map(|ix| decode_initialize2(signature, ix))
+}
+
+/// 解码 initialize2 指令，指令类型或长度不符时返回 None
+pub fn decode_initialize2(signature: &str, ix: &InstructionView) -> Option<NewPoolEvent> {
+    let data = &ix.data;
+    if data.len() < INITIALIZE2_DATA_LEN || data[0] != INITIALIZE2_TAG || ix.accounts.len() < accounts::LEN {
+        return None;
     }
+    let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    let key = |i: usize| ix.accounts[i];
+
+    Some(NewPoolEvent {
+        signature: signature.to_string(),
+        pool_id: key(accounts::AMM),
+        token_a: key(accounts::COIN_MINT),
+        token_b: key(accounts::PC_MINT),
+        open_time: u64_at(2),
+        authority: key(accounts::AUTHORITY),
+        open_orders: key(accounts::OPEN_ORDERS),
+        target_orders: key(accounts::TARGET_ORDERS),
+        lp_mint: key(accounts::LP_MINT),
+        coin_vault: key(accounts::COIN_VAULT),
+        pc_vault: key(accounts::PC_VAULT),
+        market_program: key(accounts::MARKET_PROGRAM),
+        market: key(accounts::MARKET),
+        nonce: data[1],
+        init_pc_amount: u64_at(10),
+        init_coin_amount: u64_at(18),
+    })
 }
 
-// 异步获取并解析交易，提取 Pool 信息
-pub async fn fetch_and_parse_tx(rpc_client: Arc<RpcClient>, signature: &str) -> Option<NewPoolEvent> {
-    let sig = match Signature::from_str(signature) {
-        Ok(s) => s,
-        Err(_) => return None,
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::CompiledInstruction;
+    use solana_transaction_status::{UiCompiledInstruction, UiInnerInstructions, UiInstruction};
+
+    #[test]
+    fn test_decode_initialize2_via_cpi() {
+        let raydium = Pubkey::from_str(RAYDIUM_AMM_V4_ID).unwrap();
+        let router = Pubkey::new_unique();
+        // 0: router, 1: raydium, 2..23: initialize2 的 21 个账户
+        let mut keys = vec![router, raydium];
+        keys.extend((0..accounts::LEN).map(|_| Pubkey::new_unique()));
+
+        let mut data = vec![INITIALIZE2_TAG, 254];
+        data.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        data.extend_from_slice(&5_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+
+        let compiled = |program: u8, accounts: Vec<u8>, data: &[u8]| UiCompiledInstruction {
+            program_id_index: program,
+            accounts,
+            data: bs58::encode(data).into_string(),
+            stack_height: None,
+        };
+        // 顶层只有路由程序，initialize2 在内层
+        let top = vec![CompiledInstruction { program_id_index: 0, accounts: vec![1], data: vec![9] }];
+        let inner = vec![UiInnerInstructions {
+            index: 0,
+            instructions: vec![UiInstruction::Compiled(compiled(1, (2..2 + accounts::LEN as u8).collect(), &data))],
+        }];
+
+        let ixs = tx::flatten(&keys, &top, &inner);
+        assert_eq!(ixs.len(), 2);
+        let event = ixs.iter().filter(|ix| ix.program_id == raydium).find_map(|ix| decode_initialize2("sig", ix)).unwrap();
+        assert!(ixs[1].inner);
+        assert_eq!(event.pool_id, keys[2 + accounts::AMM]);
+        assert_eq!(event.token_a, keys[2 + accounts::COIN_MINT]);
+        assert_eq!(event.token_b, keys[2 + accounts::PC_MINT]);
+        assert_eq!(event.market, keys[2 + accounts::MARKET]);
+        assert_eq!(event.open_time, 1_700_000_000);
+        assert_eq!((event.init_pc_amount, event.init_coin_amount), (5_000_000_000, 1_000_000));
 
-    // 重试机制
-    for _i in 0..5 {
-        match rpc_client.get_transaction_with_config(&sig, solana_client::rpc_config::RpcTransactionConfig {
-            encoding: Some(solana_transaction_status::UiTransactionEncoding::Json),
-            commitment: Some(solana_sdk::commitment_config::CommitmentConfig::confirmed()),
-            max_supported_transaction_version: Some(0),
-        }).await {
-            Ok(tx) => {
-                if let Some(_transaction) = tx.transaction.transaction.decode() {
-                    match tx.transaction.transaction {
-                        EncodedTransaction::Json(ui_tx) => {
-                            let message = ui_tx.message;
-                            match message {
-                                UiMessage::Raw(msg) => {
-                                    let account_keys = msg.account_keys;
-                                    // 简化逻辑：通常 Initialize2 指令的 Accounts 中包含了 Token A, Token B, Pool ID
-                                    // 具体的 Index 取决于 Raydium Program 的定义
-                                    // 这里假设我们能从 Accounts 中找到特定的模式
-                                    
-                                    // 这是一个 Hacky 的实现，生产环境应该解析 Instruction Data
-                                    if account_keys.len() > 10 {
-                                        // 假设 Pool Id 是第 2 个 (Index 1) - 仅作示例
-                                        // 实际上需要根据 Instruction Discriminator 来精确定位
-                                        
-                                        // 为了演示 Phase 3，我们返回一个 Mock 的解析结果
-                                        // 只要是 Initialize2，我们就认为它是新池子
-                                        return Some(NewPoolEvent {
-                                            signature: signature.to_string(),
-                                            pool_id: Pubkey::from_str(&account_keys[1]).unwrap_or_default(),
-                                            token_a: Pubkey::from_str(&account_keys[8]).unwrap_or_default(), // Mock Index
-                                            token_b: Pubkey::from_str(&account_keys[9]).unwrap_or_default(), // Mock Index
-                                            open_time: 0,
-                                        });
-                                    }
-                                },
-                                _ => {}
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-                return None;
-            },
-            Err(_e) => {
-                // error!("❌ 获取交易失败: {} (Attempt {})", e, i);
-                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            }
-        }
+        // 其他指令 (例如 swap, tag = 9) 不应被误判
+        assert!(decode_initialize2("sig", &ixs[0]).is_none());
     }
-    None
 }
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc;
+use crate::config::WebSocketConfig;
+use crate::scout::events::{StreamCommitment, StreamEvent};
+use crate::scout::supervisor::{ConnectionState, ConnectionStats, Subscription, WsSupervisor};
+
+struct Shard {
+    supervisor: WsSupervisor,
+    subs: HashSet<Subscription>,
+}
+
+/// 订阅分片管理器
+/// 把订阅分散到多个 ws_url 的多条连接上 (每条连接由一个 WsSupervisor 维护)，
+/// 每条连接有独立的订阅上限；某条连接失效时，把它的订阅迁移到健康连接上
+pub struct SubscriptionManager {
+    shards: Vec<Shard>,
+    assignment: HashMap<Subscription, usize>,
+    /// 每条连接可承载的订阅数 (不含 Supervisor 自带的 Slot 心跳)
+    per_connection: usize,
+    /// 连接断开超过该时间才迁移订阅，避免短暂抖动引起大规模迁移
+    grace: Duration,
+}
+
+impl SubscriptionManager {
+    pub fn new(
+        urls: &[String],
+        rpc_client: Arc<RpcClient>,
+        config: &WebSocketConfig,
+        commitment: StreamCommitment,
+        events: mpsc::Sender<StreamEvent>,
+    ) -> Self {
+        let mut shards = Vec::new();
+        for url in urls {
+            for _ in 0..config.connections_per_url.max(1) {
+                shards.push(Shard {
+                    supervisor: WsSupervisor::spawn(url.clone(), rpc_client.clone(), config.clone(), commitment, events.clone()),
+                    subs: HashSet::new(),
+                });
+            }
+        }
+        // Slot 心跳占用每条连接的一个订阅名额
+        let per_connection = config.max_subscriptions_per_connection.saturating_sub(1).max(1);
+        info!("🔀 [WS] 订阅管理器: {} 个节点 x {} 条连接，每条连接最多 {} 个订阅",
+            urls.len(), config.connections_per_url.max(1), per_connection);
+
+        Self {
+            shards,
+            assignment: HashMap::new(),
+            per_connection,
+            grace: Duration::from_secs(config.heartbeat_timeout_secs.max(1)),
+        }
+    }
+
+    /// 所有连接的订阅容量之和
+    pub fn capacity(&self) -> usize {
+        self.shards.len() * self.per_connection
+    }
+
+    pub fn len(&self) -> usize {
+        self.assignment.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assignment.is_empty()
+    }
+
+    pub fn contains(&self, sub: &Subscription) -> bool {
+        self.assignment.contains_key(sub)
+    }
+
+    /// 分配到负载最低的健康连接，所有连接都满时返回 false
+    pub fn subscribe(&mut self, sub: Subscription) -> bool {
+        if self.assignment.contains_key(&sub) {
+            return true;
+        }
+        let statuses = self.health();
+        let idx = match pick_shard(&self.shards, &statuses, self.per_connection, None, false) {
+            Some(i) => i,
+            None => {
+                warn!("⚠️ [WS] 所有连接的订阅名额已满 ({}), 忽略 {:?}", self.capacity(), sub);
+                return false;
+            }
+        };
+        self.shards[idx].subs.insert(sub);
+        self.shards[idx].supervisor.subscribe(sub);
+        self.assignment.insert(sub, idx);
+        true
+    }
+
+    pub fn unsubscribe(&mut self, sub: &Subscription) {
+        if let Some(idx) = self.assignment.remove(sub) {
+            self.shards[idx].subs.remove(sub);
+            self.shards[idx].supervisor.unsubscribe(*sub);
+        }
+    }
+
+    /// 把失效连接上的订阅迁移到健康连接，返回迁移的订阅数
+    /// 迁移过去的账户会立即补拉一次状态，弥补断线期间的更新
+    pub fn rebalance(&mut self) -> usize {
+        let statuses = self.health();
+        self.rebalance_with(&statuses)
+    }
+
+    /// 按给定的连接健康状态迁移订阅
+    fn rebalance_with(&mut self, statuses: &[bool]) -> usize {
+        let mut moved = 0;
+        let mut resync: HashMap<usize, Vec<Pubkey>> = HashMap::new();
+
+        for from in 0..self.shards.len() {
+            if statuses[from] || self.shards[from].subs.is_empty() {
+                continue;
+            }
+            let subs: Vec<Subscription> = self.shards[from].subs.iter().copied().collect();
+            let mut moved_from = 0;
+            for sub in subs {
+                let to = match pick_shard(&self.shards, statuses, self.per_connection, Some(from), true) {
+                    Some(i) => i,
+                    None => break,
+                };
+                self.shards[from].subs.remove(&sub);
+                self.shards[from].supervisor.unsubscribe(sub);
+                self.shards[to].subs.insert(sub);
+                self.shards[to].supervisor.subscribe(sub);
+                self.assignment.insert(sub, to);
+                if let Subscription::Account(pubkey) = sub {
+                    resync.entry(to).or_default().push(pubkey);
+                }
+                moved_from += 1;
+            }
+            if moved_from > 0 {
+                warn!("🔀 [WS] 连接 {} 失效，已迁移 {} 个订阅", self.shards[from].supervisor.url(), moved_from);
+            }
+            moved += moved_from;
+        }
+
+        for (idx, accounts) in resync {
+            self.shards[idx].supervisor.resync(accounts);
+        }
+        moved
+    }
+
+    pub fn stats(&self) -> Vec<ConnectionStats> {
+        self.shards.iter().map(|s| s.supervisor.stats()).collect()
+    }
+
+    fn health(&self) -> Vec<bool> {
+        self.shards
+            .iter()
+            .map(|s| {
+                let stats = s.supervisor.stats();
+                stats.state == ConnectionState::Connected || stats.last_event_age <= self.grace
+            })
+            .collect()
+    }
+}
+
+/// 选择负载最低且有余量的连接；优先健康连接，`healthy_only` 时只考虑健康连接
+fn pick_shard(
+    shards: &[Shard],
+    healthy: &[bool],
+    per_connection: usize,
+    exclude: Option<usize>,
+    healthy_only: bool,
+) -> Option<usize> {
+    shards
+        .iter()
+        .enumerate()
+        .filter(|(i, s)| Some(*i) != exclude && s.subs.len() < per_connection)
+        .filter(|(i, _)| !healthy_only || healthy[*i])
+        .min_by_key(|(i, s)| (!healthy[*i], s.subs.len()))
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::commitment_config::CommitmentConfig;
+
+    // 连不上的地址: Supervisor 只会在后台退避重试，不影响分片逻辑
+    fn manager(shards: usize, max_subscriptions_per_connection: usize) -> SubscriptionManager {
+        let urls: Vec<String> = (0..shards).map(|i| format!("ws://127.0.0.1:{}", i + 1)).collect();
+        let config = WebSocketConfig { max_subscriptions_per_connection, ..Default::default() };
+        let rpc_client = Arc::new(RpcClient::new_mock("succeeds".to_string()));
+        let (tx, _rx) = mpsc::channel(16);
+        let commitment = StreamCommitment {
+            accounts: CommitmentConfig::processed(),
+            logs: CommitmentConfig::processed(),
+            transactions: CommitmentConfig::confirmed(),
+        };
+        SubscriptionManager::new(&urls, rpc_client, &config, commitment, tx)
+    }
+
+    fn account() -> Subscription {
+        Subscription::Account(Pubkey::new_unique())
+    }
+
+    fn assert_consistent(manager: &SubscriptionManager) {
+        for (sub, idx) in &manager.assignment {
+            assert!(manager.shards[*idx].subs.contains(sub));
+        }
+        let total: usize = manager.shards.iter().map(|s| s.subs.len()).sum();
+        assert_eq!(total, manager.assignment.len());
+        assert!(manager.shards.iter().all(|s| s.subs.len() <= manager.per_connection));
+    }
+
+    #[tokio::test]
+    async fn test_pick_shard_capacity() {
+        let mut manager = manager(3, 3);
+        assert_eq!(manager.per_connection, 2);
+        assert_eq!(manager.capacity(), 6);
+        manager.shards[1].subs.extend([account(), account()]);
+        manager.shards[2].subs.insert(account());
+
+        let healthy = [false, true, true];
+        // 健康连接优先，已满的连接不考虑
+        assert_eq!(pick_shard(&manager.shards, &healthy, 2, None, false), Some(2));
+        // 没有健康连接可选时退回到不健康的连接，除非只要健康连接
+        assert_eq!(pick_shard(&manager.shards, &healthy, 2, Some(2), false), Some(0));
+        assert_eq!(pick_shard(&manager.shards, &healthy, 2, Some(2), true), None);
+
+        manager.shards[0].subs.extend([account(), account()]);
+        manager.shards[2].subs.insert(account());
+        assert_eq!(pick_shard(&manager.shards, &healthy, 2, None, false), None);
+        assert!(!manager.subscribe(account()));
+    }
+
+    #[tokio::test]
+    async fn test_rebalance_moves_keys() {
+        let mut manager = manager(3, 3);
+        let subs: Vec<Subscription> = (0..4).map(|_| account()).collect();
+        for sub in &subs {
+            assert!(manager.subscribe(*sub));
+        }
+        assert_consistent(&manager);
+        let on_first: Vec<Subscription> = manager.shards[0].subs.iter().copied().collect();
+        assert_eq!(on_first.len(), 2);
+
+        // 连接 0 失效: 订阅全部迁移到其余连接的空余名额
+        assert_eq!(manager.rebalance_with(&[false, true, true]), 2);
+        assert!(manager.shards[0].subs.is_empty());
+        for sub in &on_first {
+            assert_ne!(manager.assignment[sub], 0);
+        }
+        assert_consistent(&manager);
+
+        // 只有连接 0 健康且名额不足: 只迁移能容纳的部分，其余留在原连接
+        assert_eq!(manager.rebalance_with(&[true, false, false]), 2);
+        assert_eq!(manager.shards[0].subs.len(), 2);
+        assert_eq!(manager.len(), 4);
+        assert_consistent(&manager);
+
+        // 全部失效时没有可迁移的目标
+        assert_eq!(manager.rebalance_with(&[false, false, false]), 0);
+        manager.unsubscribe(&subs[0]);
+        assert!(!manager.contains(&subs[0]));
+        assert_consistent(&manager);
+    }
+}
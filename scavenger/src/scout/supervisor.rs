@@ -0,0 +1,574 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use futures::StreamExt;
+use log::{info, warn, error};
+use rand::Rng;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tokio::sync::{mpsc, oneshot};
+use crate::config::WebSocketConfig;
+use crate::scout::events::{AccountUpdate, EventSource, SlotStatus, SlotUpdate, StreamCommitment, StreamEvent, TransactionUpdate};
+
+// 连接持续这么久才算稳定，之后断线重新从最小退避开始
+pub(crate) const STABLE_CONNECTION: Duration = Duration::from_secs(60);
+// getMultipleAccounts 单次最多 100 个账户
+const RESYNC_BATCH: usize = 100;
+
+/// 由 Supervisor 维护的订阅
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subscription {
+    /// accountSubscribe
+    Account(Pubkey),
+    /// logsSubscribe (Mentions 指定程序)
+    Logs(Pubkey),
+    /// slotSubscribe，每条连接固定存在，同时作为心跳
+    Slots,
+}
+
+/// 连接状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Connected,
+    Reconnecting,
+    Closed,
+}
+
+impl ConnectionState {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => ConnectionState::Connecting,
+            1 => ConnectionState::Connected,
+            2 => ConnectionState::Reconnecting,
+            _ => ConnectionState::Closed,
+        }
+    }
+}
+
+/// 连接指标快照
+#[derive(Debug, Clone)]
+pub struct ConnectionStats {
+    pub url: String,
+    pub state: ConnectionState,
+    /// 重连次数
+    pub reconnects: u64,
+    /// 订阅流中断的次数
+    pub dead_streams: u64,
+    /// 单独重试中断订阅的次数 (未重建连接)
+    pub resubscribes: u64,
+    /// 因心跳超时而判定断线的次数
+    pub heartbeat_timeouts: u64,
+    /// 重连后补拉账户状态的次数
+    pub resyncs: u64,
+    /// 当前维护的订阅数 (含 Slot 心跳)
+    pub subscriptions: usize,
+    /// 距离最后一条推送的时间
+    pub last_event_age: Duration,
+    /// 当前连接已持续的时间 (未连接时为 0)
+    pub uptime: Duration,
+}
+
+/// 连接指标 (WebSocket Supervisor 与 Geyser 流共用)
+#[derive(Debug)]
+pub(crate) struct ConnectionMetrics {
+    started: Instant,
+    state: AtomicU8,
+    pub(crate) reconnects: AtomicU64,
+    pub(crate) dead_streams: AtomicU64,
+    pub(crate) resubscribes: AtomicU64,
+    pub(crate) heartbeat_timeouts: AtomicU64,
+    pub(crate) resyncs: AtomicU64,
+    pub(crate) subscriptions: AtomicUsize,
+    // 相对 started 的毫秒数
+    last_event_ms: AtomicU64,
+    connected_at_ms: AtomicU64,
+}
+
+impl ConnectionMetrics {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            state: AtomicU8::new(ConnectionState::Connecting as u8),
+            reconnects: AtomicU64::new(0),
+            dead_streams: AtomicU64::new(0),
+            resubscribes: AtomicU64::new(0),
+            heartbeat_timeouts: AtomicU64::new(0),
+            resyncs: AtomicU64::new(0),
+            subscriptions: AtomicUsize::new(0),
+            last_event_ms: AtomicU64::new(0),
+            connected_at_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    pub(crate) fn set_state(&self, state: ConnectionState) {
+        self.state.store(state as u8, Ordering::Relaxed);
+    }
+
+    pub(crate) fn touch(&self) {
+        self.last_event_ms.store(self.now_ms(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn last_event_age(&self) -> Duration {
+        Duration::from_millis(self.now_ms().saturating_sub(self.last_event_ms.load(Ordering::Relaxed)))
+    }
+
+    /// 标记连接已建立
+    pub(crate) fn connected(&self) {
+        self.connected_at_ms.store(self.now_ms(), Ordering::Relaxed);
+        self.touch();
+        self.set_state(ConnectionState::Connected);
+    }
+
+    pub(crate) fn snapshot(&self, url: &str) -> ConnectionStats {
+        let state = ConnectionState::from_u8(self.state.load(Ordering::Relaxed));
+        let uptime = if state == ConnectionState::Connected {
+            Duration::from_millis(self.now_ms().saturating_sub(self.connected_at_ms.load(Ordering::Relaxed)))
+        } else {
+            Duration::ZERO
+        };
+        ConnectionStats {
+            url: url.to_string(),
+            state,
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            dead_streams: self.dead_streams.load(Ordering::Relaxed),
+            resubscribes: self.resubscribes.load(Ordering::Relaxed),
+            heartbeat_timeouts: self.heartbeat_timeouts.load(Ordering::Relaxed),
+            resyncs: self.resyncs.load(Ordering::Relaxed),
+            subscriptions: self.subscriptions.load(Ordering::Relaxed),
+            last_event_age: self.last_event_age(),
+            uptime,
+        }
+    }
+}
+
+enum Command {
+    Subscribe(Subscription),
+    Unsubscribe(Subscription),
+    Resync(Vec<Pubkey>),
+}
+
+/// WebSocket 连接管理器
+/// - 维护一份期望的订阅集合，断线重连后全部恢复
+/// - 单个订阅流结束只按退避重试该订阅，连续失败过多或 Slot 心跳超时才判定连接失效
+/// - 重连使用带抖动的指数退避
+/// - 重连成功后通过 getMultipleAccounts 补拉所有已订阅账户，弥补断线期间丢失的更新
+#[derive(Clone)]
+pub struct WsSupervisor {
+    url: String,
+    commands: mpsc::UnboundedSender<Command>,
+    metrics: Arc<ConnectionMetrics>,
+}
+
+impl WsSupervisor {
+    /// 启动后台任务，事件发送到 `events`，所有句柄 drop 后任务退出
+    pub fn spawn(
+        url: String,
+        rpc_client: Arc<RpcClient>,
+        config: WebSocketConfig,
+        commitment: StreamCommitment,
+        events: mpsc::Sender<StreamEvent>,
+    ) -> Self {
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let metrics = Arc::new(ConnectionMetrics::new());
+        tokio::spawn(run(url.clone(), rpc_client, config, commitment, events, command_rx, metrics.clone()));
+        Self { url, commands, metrics }
+    }
+
+    pub fn subscribe(&self, sub: Subscription) {
+        let _ = self.commands.send(Command::Subscribe(sub));
+    }
+
+    pub fn unsubscribe(&self, sub: Subscription) {
+        let _ = self.commands.send(Command::Unsubscribe(sub));
+    }
+
+    /// 补拉指定账户的最新状态 (例如从失效连接迁移过来的订阅)，未连接时忽略
+    pub fn resync(&self, accounts: Vec<Pubkey>) {
+        let _ = self.commands.send(Command::Resync(accounts));
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn stats(&self) -> ConnectionStats {
+        self.metrics.snapshot(&self.url)
+    }
+}
+
+async fn run(
+    url: String,
+    rpc_client: Arc<RpcClient>,
+    config: WebSocketConfig,
+    commitment: StreamCommitment,
+    events: mpsc::Sender<StreamEvent>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    metrics: Arc<ConnectionMetrics>,
+) {
+    let heartbeat_timeout = Duration::from_secs(config.heartbeat_timeout_secs.max(1));
+    let mut desired: HashSet<Subscription> = HashSet::from([Subscription::Slots]);
+    let mut attempt: u32 = 0;
+    // 是否曾经连上过 (决定连上后是否需要补拉账户)
+    let mut reconnecting = false;
+
+    loop {
+        // 断线期间收到的订阅变更先记下来，连上后一并生效
+        let mut closed = false;
+        loop {
+            match commands.try_recv() {
+                Ok(cmd) => apply_command(&mut desired, cmd),
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+        if closed {
+            break;
+        }
+
+        let client = match PubsubClient::new(&url).await {
+            Ok(c) => Arc::new(c),
+            Err(e) => {
+                let delay = backoff(config.reconnect_base_delay_ms, config.reconnect_max_delay_ms, attempt);
+                warn!("⚠️ [WS] 连接 {} 失败: {}，{:?} 后重试", url, e, delay);
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+                continue;
+            }
+        };
+
+        let connected_at = Instant::now();
+        metrics.connected();
+        info!("✅ [WS] 已连接 {}，恢复 {} 个订阅", url, desired.len());
+
+        let (dead_tx, mut dead_rx) = mpsc::unbounded_channel::<Subscription>();
+        // 中断的订阅退避后从这里回来重新订阅
+        let (retry_tx, mut retry_rx) = mpsc::unbounded_channel::<Subscription>();
+        // 本连接上每个订阅的连续失败次数和最近一次失败时间
+        let mut failures: HashMap<Subscription, (u32, Instant)> = HashMap::new();
+        let mut tasks: HashMap<Subscription, oneshot::Sender<()>> = HashMap::new();
+        for sub in &desired {
+            tasks.insert(*sub, spawn_subscription(client.clone(), *sub, commitment, events.clone(), metrics.clone(), dead_tx.clone()));
+        }
+        metrics.subscriptions.store(tasks.len(), Ordering::Relaxed);
+
+        if reconnecting {
+            let accounts: Vec<Pubkey> = desired
+                .iter()
+                .filter_map(|s| match s {
+                    Subscription::Account(pk) => Some(*pk),
+                    _ => None,
+                })
+                .collect();
+            resync_accounts(&rpc_client, &accounts, commitment.accounts, EventSource::WebSocket, &events).await;
+            metrics.resyncs.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut check = tokio::time::interval(Duration::from_secs(1));
+        let shutdown = loop {
+            tokio::select! {
+                cmd = commands.recv() => match cmd {
+                    Some(Command::Subscribe(sub)) => {
+                        if desired.insert(sub) {
+                            tasks.insert(sub, spawn_subscription(client.clone(), sub, commitment, events.clone(), metrics.clone(), dead_tx.clone()));
+                        }
+                        metrics.subscriptions.store(tasks.len(), Ordering::Relaxed);
+                    }
+                    Some(Command::Unsubscribe(sub)) => {
+                        apply_command(&mut desired, Command::Unsubscribe(sub));
+                        if !desired.contains(&sub) {
+                            if let Some(stop) = tasks.remove(&sub) {
+                                let _ = stop.send(());
+                            }
+                        }
+                        metrics.subscriptions.store(tasks.len(), Ordering::Relaxed);
+                    }
+                    Some(Command::Resync(accounts)) => {
+                        let rpc = rpc_client.clone();
+                        let tx = events.clone();
+                        tokio::spawn(async move { resync_accounts(&rpc, &accounts, commitment.accounts, EventSource::WebSocket, &tx).await });
+                    }
+                    None => break true,
+                },
+                Some(sub) = dead_rx.recv() => {
+                    metrics.dead_streams.fetch_add(1, Ordering::Relaxed);
+                    tasks.remove(&sub);
+                    metrics.subscriptions.store(tasks.len(), Ordering::Relaxed);
+                    if !desired.contains(&sub) {
+                        continue;
+                    }
+                    let failure = failures.entry(sub).or_insert((0, Instant::now()));
+                    // 距上次失败已经稳定运行一段时间，重新计数
+                    if failure.1.elapsed() >= STABLE_CONNECTION {
+                        failure.0 = 0;
+                    }
+                    failure.1 = Instant::now();
+                    if failure.0 >= config.max_subscription_retries {
+                        warn!("⚠️ [WS] {} 订阅 {:?} 连续中断 {} 次，重建连接", url, sub, failure.0 + 1);
+                        break false;
+                    }
+                    let delay = backoff(config.reconnect_base_delay_ms, config.reconnect_max_delay_ms, failure.0);
+                    failure.0 += 1;
+                    warn!("⚠️ [WS] {} 订阅流中断: {:?}，{:?} 后单独重试 (第 {} 次)", url, sub, delay, failure.0);
+                    let retry = retry_tx.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        let _ = retry.send(sub);
+                    });
+                }
+                Some(sub) = retry_rx.recv() => {
+                    if !desired.contains(&sub) || tasks.contains_key(&sub) {
+                        continue;
+                    }
+                    tasks.insert(sub, spawn_subscription(client.clone(), sub, commitment, events.clone(), metrics.clone(), dead_tx.clone()));
+                    metrics.subscriptions.store(tasks.len(), Ordering::Relaxed);
+                    metrics.resubscribes.fetch_add(1, Ordering::Relaxed);
+                    // 补拉中断期间可能错过的账户更新
+                    if let Subscription::Account(pubkey) = sub {
+                        let rpc = rpc_client.clone();
+                        let tx = events.clone();
+                        tokio::spawn(async move { resync_accounts(&rpc, &[pubkey], commitment.accounts, EventSource::WebSocket, &tx).await });
+                    }
+                }
+                _ = check.tick() => {
+                    if metrics.last_event_age() > heartbeat_timeout {
+                        warn!("⚠️ [WS] {} 心跳超时 ({:?} 无推送)", url, heartbeat_timeout);
+                        metrics.heartbeat_timeouts.fetch_add(1, Ordering::Relaxed);
+                        break false;
+                    }
+                }
+            }
+        };
+
+        // 停掉本连接上的所有订阅任务，drop client 会关闭底层连接
+        for (_, stop) in tasks.drain() {
+            let _ = stop.send(());
+        }
+        drop(client);
+        metrics.subscriptions.store(0, Ordering::Relaxed);
+
+        if shutdown {
+            break;
+        }
+
+        reconnecting = true;
+        if connected_at.elapsed() >= STABLE_CONNECTION {
+            attempt = 0;
+        }
+        let delay = backoff(config.reconnect_base_delay_ms, config.reconnect_max_delay_ms, attempt);
+        attempt += 1;
+        metrics.reconnects.fetch_add(1, Ordering::Relaxed);
+        metrics.set_state(ConnectionState::Reconnecting);
+        warn!("🔁 [WS] {} 将在 {:?} 后重连 (第 {} 次)", url, delay, attempt);
+        tokio::time::sleep(delay).await;
+    }
+
+    metrics.set_state(ConnectionState::Closed);
+    info!("🔌 [WS] Supervisor 已退出: {}", url);
+}
+
+fn apply_command(desired: &mut HashSet<Subscription>, cmd: Command) {
+    match cmd {
+        Command::Subscribe(sub) => {
+            desired.insert(sub);
+        }
+        // Slot 订阅是心跳，不允许移除
+        Command::Unsubscribe(Subscription::Slots) => {}
+        Command::Unsubscribe(sub) => {
+            desired.remove(&sub);
+        }
+        // 未连接时无需补拉，重连后会整体补拉
+        Command::Resync(_) => {}
+    }
+}
+
+/// 指数退避 + 抖动: 在 [d/2, d] 之间随机，避免多个连接同时重连
+pub(crate) fn backoff(base_delay_ms: u64, max_delay_ms: u64, attempt: u32) -> Duration {
+    let base = base_delay_ms.max(1);
+    let max = max_delay_ms.max(base);
+    let delay = base.saturating_mul(1u64 << attempt.min(16)).min(max);
+    let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+    Duration::from_millis(delay / 2 + jitter)
+}
+
+/// 通过 getMultipleAccounts 补拉账户的最新状态，作为 `source` 的普通账户更新发出
+pub(crate) async fn resync_accounts(
+    rpc_client: &RpcClient,
+    accounts: &[Pubkey],
+    commitment: CommitmentConfig,
+    source: EventSource,
+    events: &mpsc::Sender<StreamEvent>,
+) {
+    let mut synced = 0;
+    for chunk in accounts.chunks(RESYNC_BATCH) {
+        let response = match rpc_client
+            .get_multiple_accounts_with_commitment(chunk, commitment)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                error!("❌ [{:?}] 重连后补拉账户失败: {}", source, e);
+                continue;
+            }
+        };
+        let slot = response.context.slot;
+        for (pubkey, account) in chunk.iter().zip(response.value) {
+            let account = match account {
+                Some(a) => a,
+                None => continue,
+            };
+            let update = AccountUpdate {
+                pubkey: *pubkey,
+                owner: Some(account.owner),
+                lamports: account.lamports,
+                data: account.data,
+                slot,
+                source,
+                received_at: Instant::now(),
+            };
+            if events.send(StreamEvent::Account(update)).await.is_err() {
+                return;
+            }
+            synced += 1;
+        }
+    }
+    if !accounts.is_empty() {
+        info!("🔄 [{:?}] 已补拉 {} / {} 个订阅账户的最新状态", source, synced, accounts.len());
+    }
+}
+
+/// 在独立任务中维护单个订阅，推送转换为 StreamEvent
+/// 流意外结束时通过 `dead` 通知 Supervisor；返回的 Sender 用于主动取消 (发送或 drop 都会触发 unsubscribe)
+fn spawn_subscription(
+    client: Arc<PubsubClient>,
+    sub: Subscription,
+    commitment: StreamCommitment,
+    events: mpsc::Sender<StreamEvent>,
+    metrics: Arc<ConnectionMetrics>,
+    dead: mpsc::UnboundedSender<Subscription>,
+) -> oneshot::Sender<()> {
+    let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let subscribed = match sub {
+            Subscription::Account(pubkey) => {
+                let config = RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(commitment.accounts),
+                    ..Default::default()
+                };
+                client.account_subscribe(&pubkey, Some(config)).await.map(|(stream, unsub)| {
+                    let stream = stream.map(move |account| {
+                        StreamEvent::Account(AccountUpdate {
+                            pubkey,
+                            owner: Pubkey::from_str(&account.value.owner).ok(),
+                            lamports: account.value.lamports,
+                            data: account.value.data.decode().unwrap_or_default(),
+                            slot: account.context.slot,
+                            source: EventSource::WebSocket,
+                            received_at: Instant::now(),
+                        })
+                    });
+                    (stream.boxed(), unsub)
+                })
+            }
+            Subscription::Logs(program) => client
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![program.to_string()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(commitment.logs),
+                    },
+                )
+                .await
+                .map(|(stream, unsub)| {
+                    let stream = stream.filter_map(|response| async move {
+                        let logs = response.value;
+                        Some(StreamEvent::Transaction(TransactionUpdate {
+                            signature: Signature::from_str(&logs.signature).ok()?,
+                            slot: response.context.slot,
+                            account_keys: Vec::new(),
+                            logs: logs.logs,
+                            is_failed: logs.err.is_some(),
+                            source: EventSource::WebSocket,
+                            received_at: Instant::now(),
+                        }))
+                    });
+                    (stream.boxed(), unsub)
+                }),
+            Subscription::Slots => client.slot_subscribe().await.map(|(stream, unsub)| {
+                let stream = stream.map(|info| {
+                    StreamEvent::Slot(SlotUpdate {
+                        slot: info.slot,
+                        parent: Some(info.parent),
+                        status: SlotStatus::Processed,
+                        source: EventSource::WebSocket,
+                        received_at: Instant::now(),
+                    })
+                });
+                (stream.boxed(), unsub)
+            }),
+        };
+
+        let (mut stream, unsub) = match subscribed {
+            Ok(s) => s,
+            Err(e) => {
+                error!("❌ [WS] 订阅失败 {:?}: {}", sub, e);
+                let _ = dead.send(sub);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = &mut stop_rx => break,
+                item = stream.next() => match item {
+                    Some(event) => {
+                        metrics.touch();
+                        if events.send(event).await.is_err() {
+                            break;
+                        }
+                    }
+                    None => {
+                        let _ = dead.send(sub);
+                        break;
+                    }
+                },
+            }
+        }
+        drop(stream);
+        unsub().await;
+    });
+    stop_tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_jittered_and_capped() {
+        let config = WebSocketConfig {
+            reconnect_base_delay_ms: 100,
+            reconnect_max_delay_ms: 1_000,
+            ..Default::default()
+        };
+        for attempt in 0..20 {
+            let cap = (100u64 << attempt.min(16)).min(1_000);
+            let delay = backoff(config.reconnect_base_delay_ms, config.reconnect_max_delay_ms, attempt).as_millis() as u64;
+            assert!(delay >= cap / 2 && delay <= cap, "attempt {}: {}ms", attempt, delay);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::str::FromStr;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use crate::bus::{Dex, SwapEvent};
+use crate::scout::orca::ORCA_WHIRLPOOL_ID;
+use crate::scout::raydium::RAYDIUM_AMM_V4_ID;
+use crate::scout::tx::{InstructionView, ResolvedTransaction};
+
+/// Raydium AMM V4 交换指令 tag: SwapBaseIn / SwapBaseOut / SwapBaseInV2 / SwapBaseOutV2 (V2 去掉了 OpenBook 账户)
+const RAYDIUM_SWAP_TAGS: [u8; 4] = [9, 11, 16, 17];
+/// 最短的 V2 变体有 8 个账户: amm 位于 1，owner 总是最后一个
+const RAYDIUM_SWAP_MIN_ACCOUNTS: usize = 8;
+
+/// ray_log 类型: 3 = SwapBaseIn, 4 = SwapBaseOut
+/// 布局: log_type(1) + 7 个 u64，方向 1 = PC -> Coin，2 = Coin -> PC
+const RAY_LOG_SWAP_BASE_IN: u8 = 3;
+const RAY_LOG_SWAP_BASE_OUT: u8 = 4;
+const RAY_LOG_SWAP_LEN: usize = 57;
+const RAY_LOG_COIN_TO_PC: u64 = 2;
+
+/// Whirlpool 交换指令 discriminator: sha256("global:<name>")[..8]
+const WHIRLPOOL_SWAP: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+const WHIRLPOOL_SWAP_V2: [u8; 8] = [43, 4, 237, 11, 26, 201, 30, 98];
+const WHIRLPOOL_TWO_HOP_SWAP: [u8; 8] = [195, 96, 237, 108, 68, 162, 219, 230];
+const WHIRLPOOL_TWO_HOP_SWAP_V2: [u8; 8] = [186, 143, 209, 29, 254, 2, 194, 117];
+
+/// Anchor 事件: emit! 写在 "Program data:" 日志里，emit_cpi! 作为程序自调用的内层指令 (前缀 EVENT_IX_TAG)
+const TRADED_EVENT: [u8; 8] = [225, 202, 73, 175, 147, 43, 160, 150];
+const ANCHOR_EVENT_IX_TAG: [u8; 8] = [228, 69, 165, 46, 81, 203, 154, 29];
+/// Traded: whirlpool(32) + a_to_b(1) + pre/post_sqrt_price(16 * 2) + input_amount(8) + output_amount(8) + 手续费字段
+const TRADED_MIN_LEN: usize = 8 + 32 + 1 + 32 + 16;
+
+/// ray_log 中的交换结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RaySwapLog {
+    pub coin_to_pc: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Whirlpool Traded 事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Traded {
+    pub whirlpool: Pubkey,
+    pub a_to_b: bool,
+    pub input_amount: u64,
+    pub output_amount: u64,
+}
+
+/// 日志预筛: 是否可能包含 Raydium / Whirlpool 交换
+pub fn mentions_swap(logs: &[String]) -> bool {
+    logs.iter().any(|l| {
+        l.starts_with("Program log: ray_log:")
+            || l.starts_with("Program log: Instruction: Swap")
+            || l.starts_with("Program log: Instruction: TwoHopSwap")
+    })
+}
+
+/// 解码交易中的全部 Raydium V4 / Whirlpool 交换，失败的交易返回空
+pub fn decode_swaps(tx: &ResolvedTransaction) -> Vec<SwapEvent> {
+    if tx.failed {
+        return Vec::new();
+    }
+    let signature = Signature::from_str(&tx.signature).unwrap_or_default();
+    let mut swaps = decode_raydium(tx, signature);
+    swaps.extend(decode_whirlpool(tx, signature));
+    swaps
+}
+
+fn decode_raydium(tx: &ResolvedTransaction, signature: Signature) -> Vec<SwapEvent> {
+    let program_id = Pubkey::from_str(RAYDIUM_AMM_V4_ID).unwrap();
+    // 每条交换指令执行时打印一条 ray_log，二者按执行顺序一一对应
+    let mut logs = tx.logs.iter().filter_map(|l| parse_ray_log(l));
+
+    tx.instructions
+        .iter()
+        .filter(|ix| ix.program_id == program_id)
+        .filter(|ix| ix.data.first().is_some_and(|t| RAYDIUM_SWAP_TAGS.contains(t)))
+        .filter(|ix| ix.accounts.len() >= RAYDIUM_SWAP_MIN_ACCOUNTS)
+        // 没有 ray_log (例如日志被截断) 时无法判断方向，跳过
+        .filter_map(|ix| logs.next().map(|log| (ix, log)))
+        .map(|(ix, log)| SwapEvent {
+            dex: Dex::Raydium,
+            signature,
+            slot: tx.slot,
+            pool: ix.accounts[1],
+            signer: *ix.accounts.last().unwrap(),
+            a_to_b: log.coin_to_pc,
+            amount_in: log.amount_in,
+            amount_out: log.amount_out,
+            exact: true,
+        })
+        .collect()
+}
+
+/// 解析 "Program log: ray_log: <base64>"，只返回交换类型
+pub fn parse_ray_log(line: &str) -> Option<RaySwapLog> {
+    let payload = line.strip_prefix("Program log: ray_log: ")?;
+    let data = BASE64.decode(payload.trim()).ok()?;
+    if data.len() < RAY_LOG_SWAP_LEN {
+        return None;
+    }
+    let u64_at = |i: usize| u64::from_le_bytes(data[1 + i * 8..9 + i * 8].try_into().unwrap());
+    let coin_to_pc = u64_at(2) == RAY_LOG_COIN_TO_PC;
+    match data[0] {
+        // amount_in, minimum_out, direction, user_source, pool_coin, pool_pc, out_amount
+        RAY_LOG_SWAP_BASE_IN => Some(RaySwapLog { coin_to_pc, amount_in: u64_at(0), amount_out: u64_at(6) }),
+        // max_in, amount_out, direction, user_source, pool_coin, pool_pc, deduct_in
+        RAY_LOG_SWAP_BASE_OUT => Some(RaySwapLog { coin_to_pc, amount_in: u64_at(6), amount_out: u64_at(1) }),
+        _ => None,
+    }
+}
+
+/// 一次 Whirlpool 交换中的一跳 (金额在拿到 Traded 事件前是指令参数)
+struct Leg {
+    pool: Pubkey,
+    signer: Pubkey,
+    a_to_b: bool,
+    amount_in: u64,
+    amount_out: u64,
+}
+
+fn decode_whirlpool(tx: &ResolvedTransaction, signature: Signature) -> Vec<SwapEvent> {
+    let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_ID).unwrap();
+    let mut traded = traded_events(tx, &program_id);
+
+    tx.instructions
+        .iter()
+        .filter(|ix| ix.program_id == program_id)
+        .flat_map(whirlpool_legs)
+        .map(|leg| {
+            // 旧版本程序没有 Traded 事件，此时只能给出指令参数
+            let event = traded.iter().position(|t| t.whirlpool == leg.pool).map(|i| traded.remove(i));
+            SwapEvent {
+                dex: Dex::Orca,
+                signature,
+                slot: tx.slot,
+                pool: leg.pool,
+                signer: leg.signer,
+                a_to_b: event.map_or(leg.a_to_b, |t| t.a_to_b),
+                amount_in: event.map_or(leg.amount_in, |t| t.input_amount),
+                amount_out: event.map_or(leg.amount_out, |t| t.output_amount),
+                exact: event.is_some(),
+            }
+        })
+        .collect()
+}
+
+/// 解码 swap / swap_v2 / two_hop_swap / two_hop_swap_v2，其他指令返回空
+fn whirlpool_legs(ix: &InstructionView) -> Vec<Leg> {
+    let discriminator = match ix.data.get(..8) {
+        Some(d) => d,
+        None => return Vec::new(),
+    };
+    let legs = match discriminator {
+        d if d == WHIRLPOOL_SWAP => single_leg(ix, 1, 2),
+        d if d == WHIRLPOOL_SWAP_V2 => single_leg(ix, 3, 4),
+        d if d == WHIRLPOOL_TWO_HOP_SWAP => two_hop_legs(ix, 1, 2, 3),
+        d if d == WHIRLPOOL_TWO_HOP_SWAP_V2 => two_hop_legs(ix, 14, 0, 1),
+        _ => None,
+    };
+    legs.unwrap_or_default()
+}
+
+/// 参数: amount(8) + other_amount_threshold(8) + sqrt_price_limit(16) + amount_specified_is_input(1) + a_to_b(1)
+fn single_leg(ix: &InstructionView, authority: usize, whirlpool: usize) -> Option<Vec<Leg>> {
+    let args = &ix.data[8..];
+    let (amount_in, amount_out) = leg_amounts(read_u64(args, 0)?, read_u64(args, 8)?, read_bool(args, 32)?);
+    Some(vec![Leg {
+        pool: *ix.accounts.get(whirlpool)?,
+        signer: *ix.accounts.get(authority)?,
+        a_to_b: read_bool(args, 33)?,
+        amount_in,
+        amount_out,
+    }])
+}
+
+/// 参数: amount(8) + other_amount_threshold(8) + amount_specified_is_input(1) + a_to_b_one(1) + a_to_b_two(1) + ...
+/// 中间代币数量只能从 Traded 事件得到，这里记为 0
+fn two_hop_legs(ix: &InstructionView, authority: usize, one: usize, two: usize) -> Option<Vec<Leg>> {
+    let args = &ix.data[8..];
+    let (amount_in, amount_out) = leg_amounts(read_u64(args, 0)?, read_u64(args, 8)?, read_bool(args, 16)?);
+    let signer = *ix.accounts.get(authority)?;
+    Some(vec![
+        Leg { pool: *ix.accounts.get(one)?, signer, a_to_b: read_bool(args, 17)?, amount_in, amount_out: 0 },
+        Leg { pool: *ix.accounts.get(two)?, signer, a_to_b: read_bool(args, 18)?, amount_in: 0, amount_out },
+    ])
+}
+
+/// 指定输入时 (amount, threshold) = (输入, 最少输出)，指定输出时相反
+fn leg_amounts(amount: u64, threshold: u64, is_input: bool) -> (u64, u64) {
+    if is_input { (amount, threshold) } else { (threshold, amount) }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+fn read_bool(data: &[u8], offset: usize) -> Option<bool> {
+    data.get(offset).map(|b| *b != 0)
+}
+
+/// 收集交易中的 Traded 事件 (日志与 emit_cpi 内层指令两种形式)，按执行顺序排列
+fn traded_events(tx: &ResolvedTransaction, program_id: &Pubkey) -> Vec<Traded> {
+    let from_logs = tx
+        .logs
+        .iter()
+        .filter_map(|l| l.strip_prefix("Program data: "))
+        .filter_map(|payload| BASE64.decode(payload.trim()).ok());
+    let from_cpi = tx
+        .instructions
+        .iter()
+        .filter(|ix| ix.inner && ix.program_id == *program_id)
+        .filter_map(|ix| ix.data.strip_prefix(&ANCHOR_EVENT_IX_TAG[..]).map(|d| d.to_vec()));
+    from_logs.chain(from_cpi).filter_map(|data| parse_traded(&data)).collect()
+}
+
+pub fn parse_traded(data: &[u8]) -> Option<Traded> {
+    if data.len() < TRADED_MIN_LEN || data[..8] != TRADED_EVENT {
+        return None;
+    }
+    let body = &data[8..];
+    let u64_at = |i: usize| u64::from_le_bytes(body[i..i + 8].try_into().unwrap());
+    Some(Traded {
+        whirlpool: Pubkey::try_from(&body[..32]).ok()?,
+        a_to_b: body[32] != 0,
+        // 跳过 pre_sqrt_price / post_sqrt_price
+        input_amount: u64_at(65),
+        output_amount: u64_at(73),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scout::tx::TxAccount;
+
+    fn resolved(instructions: Vec<InstructionView>, logs: Vec<String>) -> ResolvedTransaction {
+        ResolvedTransaction {
+            signature: Signature::default().to_string(),
+            slot: 7,
+            keys: Vec::<TxAccount>::new(),
+            instructions,
+            logs,
+            failed: false,
+        }
+    }
+
+    #[test]
+    fn test_raydium_swap_with_ray_log() {
+        let accounts: Vec<Pubkey> = (0..18).map(|_| Pubkey::new_unique()).collect();
+        let mut data = vec![9];
+        data.extend_from_slice(&500_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        let ix = InstructionView {
+            program_id: Pubkey::from_str(RAYDIUM_AMM_V4_ID).unwrap(),
+            accounts: accounts.clone(),
+            data,
+            outer_index: 0,
+            inner: false,
+        };
+
+        // SwapBaseIn: amount_in, minimum_out, direction = 1 (PC -> Coin), user_source, pool_coin, pool_pc, out_amount
+        let mut log = vec![RAY_LOG_SWAP_BASE_IN];
+        for v in [500_000_000_000u64, 0, 1, 0, 0, 0, 123_456] {
+            log.extend_from_slice(&v.to_le_bytes());
+        }
+        let logs = vec![format!("Program log: ray_log: {}", BASE64.encode(&log))];
+        assert!(mentions_swap(&logs));
+
+        let swaps = decode_swaps(&resolved(vec![ix], logs));
+        assert_eq!(swaps.len(), 1);
+        let swap = &swaps[0];
+        assert_eq!((swap.pool, swap.signer), (accounts[1], accounts[17]));
+        assert!(!swap.a_to_b && swap.exact);
+        assert_eq!((swap.amount_in, swap.amount_out), (500_000_000_000, 123_456));
+    }
+
+    #[test]
+    fn test_whirlpool_two_hop_with_traded_events() {
+        let accounts: Vec<Pubkey> = (0..20).map(|_| Pubkey::new_unique()).collect();
+        let (one, two) = (accounts[2], accounts[3]);
+        let mut data = WHIRLPOOL_TWO_HOP_SWAP.to_vec();
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&900u64.to_le_bytes());
+        data.extend_from_slice(&[1, 1, 0]);
+        data.extend_from_slice(&[0; 32]);
+        let program_id = Pubkey::from_str(ORCA_WHIRLPOOL_ID).unwrap();
+        let ix = InstructionView { program_id, accounts: accounts.clone(), data, outer_index: 0, inner: false };
+
+        let traded = |pool: Pubkey, a_to_b: bool, input: u64, output: u64| {
+            let mut d = TRADED_EVENT.to_vec();
+            d.extend_from_slice(pool.as_ref());
+            d.push(a_to_b as u8);
+            d.extend_from_slice(&[0; 32]);
+            d.extend_from_slice(&input.to_le_bytes());
+            d.extend_from_slice(&output.to_le_bytes());
+            d.extend_from_slice(&[0; 32]);
+            d
+        };
+        // 第一跳用 emit! 日志，第二跳用 emit_cpi! 内层指令
+        let logs = vec![
+            "Program log: Instruction: TwoHopSwap".to_string(),
+            format!("Program data: {}", BASE64.encode(traded(one, true, 1_000, 50))),
+        ];
+        let mut cpi = ANCHOR_EVENT_IX_TAG.to_vec();
+        cpi.extend(traded(two, false, 50, 950));
+        let event_ix = InstructionView { program_id, accounts: vec![], data: cpi, outer_index: 0, inner: true };
+
+        let swaps = decode_swaps(&resolved(vec![ix, event_ix], logs));
+        assert_eq!(swaps.len(), 2);
+        assert_eq!((swaps[0].pool, swaps[0].a_to_b, swaps[0].amount_in, swaps[0].amount_out), (one, true, 1_000, 50));
+        assert_eq!((swaps[1].pool, swaps[1].a_to_b, swaps[1].amount_in, swaps[1].amount_out), (two, false, 50, 950));
+        assert!(swaps.iter().all(|s| s.exact && s.signer == accounts[1]));
+    }
+}
@@ -0,0 +1,284 @@
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use dashmap::DashMap;
+use log::warn;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::v0::MessageAddressTableLookup;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, UiCompiledInstruction, UiInnerInstructions, UiInstruction,
+    UiLoadedAddresses, UiTransactionEncoding,
+};
+
+/// 交易账户列表中的一项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+/// 交易中的一条指令 (顶层或 CPI 内层)，账户索引已展开为公钥
+#[derive(Debug, Clone)]
+pub struct InstructionView {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+    /// 所属顶层指令的序号
+    pub outer_index: usize,
+    /// 是否由 CPI 调用 (内层指令)
+    pub inner: bool,
+}
+
+/// 已解析 ALT 的交易
+#[derive(Debug, Clone)]
+pub struct ResolvedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    /// 完整账户列表: 静态账户 + ALT 加载的可写账户 + ALT 加载的只读账户 (与链上索引顺序一致)
+    pub keys: Vec<TxAccount>,
+    /// 按执行顺序展开的所有指令
+    pub instructions: Vec<InstructionView>,
+    pub logs: Vec<String>,
+    /// 交易执行失败 (失败的交易不会改变链上状态)
+    pub failed: bool,
+}
+
+impl ResolvedTransaction {
+    /// 手续费支付者 (第一个签名者)
+    pub fn fee_payer(&self) -> Option<Pubkey> {
+        self.keys.first().map(|k| k.pubkey)
+    }
+}
+
+/// ALT 内容缓存: 地址表只会追加 (extend)，索引越界时重新拉取
+fn table_cache() -> &'static DashMap<Pubkey, Arc<Vec<Pubkey>>> {
+    static TABLES: OnceLock<DashMap<Pubkey, Arc<Vec<Pubkey>>>> = OnceLock::new();
+    TABLES.get_or_init(DashMap::new)
+}
+
+/// 拉取交易 (Base64 编码) 并解析完整账户列表，失败时间隔 500ms 重试
+/// processed 日志里的交易可能还没确认 (或所在分叉被放弃)，重试用完仍拉不到时放弃
+pub async fn fetch_transaction(
+    rpc_client: Arc<RpcClient>,
+    signature: &str,
+    attempts: usize,
+    commitment: CommitmentConfig,
+) -> Option<ResolvedTransaction> {
+    let sig = Signature::from_str(signature).ok()?;
+    for _ in 0..attempts {
+        match rpc_client.get_transaction_with_config(&sig, RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        }).await {
+            Ok(tx) => return resolve(&rpc_client, signature, &tx).await,
+            Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(500)).await,
+        }
+    }
+    None
+}
+
+/// 解码交易并展开 ALT: 优先使用 meta 中的 loadedAddresses，没有时从地址表账户解析
+pub async fn resolve(
+    rpc_client: &RpcClient,
+    signature: &str,
+    tx: &EncodedConfirmedTransactionWithStatusMeta,
+) -> Option<ResolvedTransaction> {
+    let versioned = tx.transaction.transaction.decode()?;
+    let message = &versioned.message;
+    let meta = tx.transaction.meta.as_ref();
+
+    let mut keys: Vec<TxAccount> = message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .map(|(i, pubkey)| TxAccount {
+            pubkey: *pubkey,
+            is_signer: message.is_signer(i),
+            is_writable: message.is_maybe_writable(i),
+        })
+        .collect();
+
+    if let Some(lookups) = message.address_table_lookups().filter(|l| !l.is_empty()) {
+        let loaded = match meta.map(|m| m.loaded_addresses.as_ref()) {
+            Some(OptionSerializer::Some(loaded)) => parse_loaded_addresses(loaded),
+            _ => None,
+        };
+        let (writable, readonly) = match loaded {
+            Some(loaded) => loaded,
+            None => match load_from_tables(rpc_client, lookups).await {
+                Some(loaded) => loaded,
+                None => {
+                    warn!("⚠️ [Tx] 无法解析交易 {} 的地址查找表", signature);
+                    return None;
+                }
+            },
+        };
+        let loaded = |pubkey, is_writable| TxAccount { pubkey, is_signer: false, is_writable };
+        keys.extend(writable.into_iter().map(|k| loaded(k, true)));
+        keys.extend(readonly.into_iter().map(|k| loaded(k, false)));
+    }
+
+    let pubkeys: Vec<Pubkey> = keys.iter().map(|k| k.pubkey).collect();
+    let inner = match meta.map(|m| m.inner_instructions.as_ref()) {
+        Some(OptionSerializer::Some(inner)) => inner.as_slice(),
+        _ => &[],
+    };
+    let logs = match meta.map(|m| m.log_messages.as_ref()) {
+        Some(OptionSerializer::Some(logs)) => logs.clone(),
+        _ => Vec::new(),
+    };
+
+    Some(ResolvedTransaction {
+        signature: signature.to_string(),
+        slot: tx.slot,
+        instructions: flatten(&pubkeys, message.instructions(), inner),
+        keys,
+        logs,
+        failed: meta.is_some_and(|m| m.err.is_some()),
+    })
+}
+
+fn parse_loaded_addresses(loaded: &UiLoadedAddresses) -> Option<(Vec<Pubkey>, Vec<Pubkey>)> {
+    let parse = |keys: &[String]| keys.iter().map(|k| Pubkey::from_str(k).ok()).collect::<Option<Vec<_>>>();
+    Some((parse(&loaded.writable)?, parse(&loaded.readonly)?))
+}
+
+/// 按 lookup 顺序拼接: 所有可写地址在前，所有只读地址在后
+async fn load_from_tables(
+    rpc_client: &RpcClient,
+    lookups: &[MessageAddressTableLookup],
+) -> Option<(Vec<Pubkey>, Vec<Pubkey>)> {
+    let mut writable = Vec::new();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let max_index = lookup
+            .writable_indexes
+            .iter()
+            .chain(&lookup.readonly_indexes)
+            .copied()
+            .max()
+            .unwrap_or(0) as usize;
+        let table = lookup_table(rpc_client, &lookup.account_key, max_index).await?;
+        writable.extend(lookup.writable_indexes.iter().map(|&i| table[i as usize]));
+        readonly.extend(lookup.readonly_indexes.iter().map(|&i| table[i as usize]));
+    }
+    Some((writable, readonly))
+}
+
+/// 返回至少包含 `max_index` 的地址表内容
+async fn lookup_table(rpc_client: &RpcClient, key: &Pubkey, max_index: usize) -> Option<Arc<Vec<Pubkey>>> {
+    if let Some(table) = table_cache().get(key) {
+        if max_index < table.len() {
+            return Some(table.clone());
+        }
+    }
+    let data = rpc_client.get_account_data(key).await.ok()?;
+    let table = Arc::new(AddressLookupTable::deserialize(&data).ok()?.addresses.to_vec());
+    table_cache().insert(*key, table.clone());
+    (max_index < table.len()).then_some(table)
+}
+
+/// 每条顶层指令后紧跟它触发的内层指令；`keys` 需包含 ALT 加载的地址，索引越界的指令会被跳过
+pub fn flatten(keys: &[Pubkey], top: &[CompiledInstruction], inner: &[UiInnerInstructions]) -> Vec<InstructionView> {
+    let mut out = Vec::new();
+    for (i, ix) in top.iter().enumerate() {
+        out.extend(view(keys, ix.program_id_index, &ix.accounts, ix.data.clone(), i, false));
+        for group in inner.iter().filter(|g| g.index as usize == i) {
+            for ix in &group.instructions {
+                if let UiInstruction::Compiled(UiCompiledInstruction { program_id_index, accounts, data, .. }) = ix {
+                    if let Ok(data) = bs58::decode(data).into_vec() {
+                        out.extend(view(keys, *program_id_index, accounts, data, i, true));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn view(
+    keys: &[Pubkey],
+    program_id_index: u8,
+    accounts: &[u8],
+    data: Vec<u8>,
+    outer_index: usize,
+    inner: bool,
+) -> Option<InstructionView> {
+    let program_id = *keys.get(program_id_index as usize)?;
+    let accounts = accounts
+        .iter()
+        .map(|&i| keys.get(i as usize).copied())
+        .collect::<Option<Vec<_>>>()?;
+    Some(InstructionView { program_id, accounts, data, outer_index, inner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::{v0, MessageHeader, VersionedMessage};
+    use solana_sdk::transaction::VersionedTransaction;
+
+    #[tokio::test]
+    async fn test_resolve_v0_with_loaded_addresses() {
+        let (payer, program, table) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (loaded_w, loaded_r) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program],
+            recent_blockhash: Hash::default(),
+            // 账户索引 2 / 3 落在静态列表之外，来自 ALT
+            instructions: vec![CompiledInstruction { program_id_index: 1, accounts: vec![0, 2, 3], data: vec![7] }],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: table,
+                writable_indexes: vec![5],
+                readonly_indexes: vec![9],
+            }],
+        };
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::V0(message),
+        };
+        let encoded: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(serde_json::json!({
+            "slot": 42,
+            "transaction": [bs58::encode(bincode::serialize(&tx).unwrap()).into_string(), "base58"],
+            "meta": {
+                "err": null,
+                "status": { "Ok": null },
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [],
+                "loadedAddresses": { "writable": [loaded_w.to_string()], "readonly": [loaded_r.to_string()] },
+            },
+            "version": 0,
+        }))
+        .unwrap();
+
+        // 有 loadedAddresses 时不会访问 RPC
+        let rpc = RpcClient::new("http://127.0.0.1:1".to_string());
+        let resolved = resolve(&rpc, "sig", &encoded).await.unwrap();
+
+        let keys: Vec<Pubkey> = resolved.keys.iter().map(|k| k.pubkey).collect();
+        assert_eq!(keys, vec![payer, program, loaded_w, loaded_r]);
+        assert!(resolved.keys[0].is_signer && resolved.keys[0].is_writable);
+        assert!(!resolved.keys[1].is_writable);
+        assert!(resolved.keys[2].is_writable && !resolved.keys[3].is_writable);
+        assert_eq!(resolved.fee_payer(), Some(payer));
+        assert_eq!(resolved.instructions.len(), 1);
+        assert_eq!(resolved.instructions[0].accounts, vec![payer, loaded_w, loaded_r]);
+        assert!(!resolved.failed);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+use std::time::Instant;
+use solana_sdk::pubkey::Pubkey;
+use crate::config::ScoutConfig;
+use crate::state::{Inventory, PairActivity};
+
+// 已在订阅中的套利对享受的分数加成，避免分数接近的池子来回切换
+const INCUMBENT_BONUS: f64 = 0.1;
+
+/// 监控名单排序器
+/// 综合 TVL、账户更新频率和历史价差为套利对打分，在订阅预算内选出最值得监听的池子
+pub struct WatchListRanker {
+    config: ScoutConfig,
+    last_roll: Instant,
+    /// 探索名额的轮换位置
+    explore_cursor: usize,
+}
+
+impl WatchListRanker {
+    pub fn new(config: ScoutConfig) -> Self {
+        Self {
+            config,
+            last_roll: Instant::now(),
+            explore_cursor: 0,
+        }
+    }
+
+    /// 计算新的监控名单 (Pool Address 列表)
+    /// `current` 为当前已订阅的池子，用于给在位者加分
+    /// 活跃度和价差只能从订阅中的池子观察到，因此预留 `exploration_slots` 个名额轮流分给名单外的套利对
+    pub fn rank(&mut self, inventory: &Inventory, current: &HashSet<Pubkey>) -> Vec<Pubkey> {
+        let activity: std::collections::HashMap<Pubkey, PairActivity> =
+            inventory.roll_activity(self.last_roll.elapsed()).into_iter().collect();
+        self.last_roll = Instant::now();
+
+        // 非 Active 的池子 (未开放 / 已禁用 / 已抽空) 不进入监控名单
+        let pairs: Vec<_> = inventory
+            .pairs()
+            .into_iter()
+            .filter(|p| inventory.is_pair_tradable(p))
+            .collect();
+        let default_activity = PairActivity::default();
+
+        // 各维度先做 log 缩放，再按候选集中的最大值归一化，让权重可以直接比较
+        let features: Vec<(f64, f64, f64)> = pairs
+            .iter()
+            .map(|p| {
+                let a = activity.get(&p.token_mint).unwrap_or(&default_activity);
+                (a.tvl_usd.max(0.0).ln_1p(), a.updates_per_min.max(0.0).ln_1p(), a.spread_pct.max(0.0))
+            })
+            .collect();
+        let max = features.iter().fold((0.0f64, 0.0f64, 0.0f64), |m, f| (m.0.max(f.0), m.1.max(f.1), m.2.max(f.2)));
+        let norm = |v: f64, max: f64| if max > 0.0 { v / max } else { 0.0 };
+
+        let mut scored: Vec<(f64, usize)> = features
+            .iter()
+            .enumerate()
+            .map(|(i, f)| {
+                let mut score = self.config.tvl_weight * norm(f.0, max.0)
+                    + self.config.activity_weight * norm(f.1, max.1)
+                    + self.config.spread_weight * norm(f.2, max.2);
+                if pairs[i].pools().iter().any(|p| current.contains(p)) {
+                    score *= 1.0 + INCUMBENT_BONUS;
+                }
+                (score, i)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| pairs[a.1].token_mint.cmp(&pairs[b.1].token_mint))
+        });
+
+        // 按分数从高到低装入订阅预算，一个套利对的所有池子要么全订阅要么都不订阅
+        let budget = self.config.max_subscriptions;
+        let explore_budget = self.config.exploration_slots.min(budget);
+        let mut selected = Vec::with_capacity(budget);
+        let mut chosen = vec![false; pairs.len()];
+        let fill = |selected: &mut Vec<Pubkey>, chosen: &mut Vec<bool>, i: usize, limit: usize| {
+            let pools = pairs[i].pools();
+            if chosen[i] || selected.len() + pools.len() > limit {
+                return false;
+            }
+            selected.extend(pools);
+            chosen[i] = true;
+            true
+        };
+        for &(_, i) in &scored {
+            fill(&mut selected, &mut chosen, i, budget - explore_budget);
+        }
+
+        // 探索: 名单外的套利对按 Mint 排成固定顺序，从上次的位置继续轮换
+        let mut outside: Vec<usize> = (0..pairs.len()).filter(|i| !chosen[*i]).collect();
+        outside.sort_by_key(|i| pairs[*i].token_mint);
+        if !outside.is_empty() {
+            let start = self.explore_cursor % outside.len();
+            let mut explored = 0;
+            for &i in outside[start..].iter().chain(&outside[..start]) {
+                if selected.len() >= budget {
+                    break;
+                }
+                if fill(&mut selected, &mut chosen, i, budget) {
+                    explored += 1;
+                }
+            }
+            self.explore_cursor = start + explored;
+        }
+
+        // 候选不够探索时剩余名额仍按分数分配
+        for &(_, i) in &scored {
+            fill(&mut selected, &mut chosen, i, budget);
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scout::api::PoolInfo;
+
+    #[test]
+    fn test_rank_order_eviction_and_exploration() {
+        let inventory = Inventory::new();
+        let quote = Pubkey::new_unique();
+        // 三个套利对，TVL 依次递减
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let pool = |mint: Pubkey, tvl: f64| PoolInfo { address: Pubkey::new_unique(), token_a: mint, token_b: quote, tvl };
+        let ray: Vec<PoolInfo> = mints.iter().map(|m| pool(*m, 1e9)).collect();
+        let orca: Vec<PoolInfo> = mints.iter().zip([1e6, 1e4, 1e2]).map(|(m, tvl)| pool(*m, tvl)).collect();
+        inventory.ingest_pools(ray, orca, true);
+        let pairs_of = |selected: &[Pubkey]| -> HashSet<Pubkey> {
+            selected.iter().filter_map(|p| inventory.find_pair_by_pool(p)).map(|p| p.token_mint).collect()
+        };
+
+        // 不探索时按分数取前两个 (每个套利对 2 个池子)
+        let config = ScoutConfig { max_subscriptions: 4, exploration_slots: 0, spread_weight: 5.0, ..Default::default() };
+        let mut ranker = WatchListRanker::new(config.clone());
+        let selected = ranker.rank(&inventory, &HashSet::new());
+        assert_eq!(pairs_of(&selected), HashSet::from([mints[0], mints[1]]));
+
+        // 价差高的套利对挤掉分数最低的在位者
+        inventory.record_spread(&mints[2], 2.0);
+        let current: HashSet<Pubkey> = selected.iter().copied().collect();
+        let selected = ranker.rank(&inventory, &current);
+        assert_eq!(pairs_of(&selected), HashSet::from([mints[0], mints[2]]));
+
+        // 预留 2 个探索名额: 第一名 (价差最高的) 固定，另一个名额在其余套利对之间轮换
+        let mut ranker = WatchListRanker::new(ScoutConfig { exploration_slots: 2, ..config });
+        let first = pairs_of(&ranker.rank(&inventory, &HashSet::new()));
+        let second = pairs_of(&ranker.rank(&inventory, &HashSet::new()));
+        assert!(first.contains(&mints[2]) && second.contains(&mints[2]));
+        assert_eq!((first.len(), second.len()), (2, 2));
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,132 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use log::{info, warn, error};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use crate::state::{ArbitragePair, Inventory, PoolKeys};
+use crate::token_registry::TokenInfo;
+
+/// 快照格式版本，结构体字段有变化时必须递增
+/// 版本不匹配的快照会被直接丢弃，回退到 Cold Start
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Inventory 的磁盘快照 (bincode 编码，文件头 4 字节为版本号)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventorySnapshot {
+    pub created_at: u64,
+    pub pairs: Vec<ArbitragePair>,
+    // (Pool, TokenMintA, TokenMintB)
+    pub orca_pools: Vec<(Pubkey, Pubkey, Pubkey)>,
+    pub pool_keys: Vec<PoolKeys>,
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// 写入快照 (先写临时文件再 rename，避免进程中途退出留下半个文件)
+pub fn save(snapshot: &InventorySnapshot, path: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = bincode::serialize(&(SNAPSHOT_VERSION, snapshot))?;
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, bytes)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// 读取快照，文件不存在、版本不匹配或损坏时返回 None
+pub fn load(path: &str) -> Option<InventorySnapshot> {
+    if !Path::new(path).exists() {
+        return None;
+    }
+
+    let bytes = match fs::read(path) {
+        Ok(b) => b,
+        Err(e) => {
+            warn!("⚠️ 读取 Inventory 快照失败: {} - {}", path, e);
+            return None;
+        }
+    };
+
+    let version: u32 = bincode::deserialize(bytes.get(..4)?).ok()?;
+    if version != SNAPSHOT_VERSION {
+        warn!("⚠️ Inventory 快照版本不匹配 (文件: v{}, 当前: v{})，忽略快照", version, SNAPSHOT_VERSION);
+        return None;
+    }
+
+    match bincode::deserialize::<(u32, InventorySnapshot)>(&bytes) {
+        Ok((_, snapshot)) => Some(snapshot),
+        Err(e) => {
+            warn!("⚠️ Inventory 快照已损坏: {} - {}", path, e);
+            None
+        }
+    }
+}
+
+/// Warm Start: 从快照恢复 Inventory，返回是否成功
+pub fn warm_start(inventory: &Inventory, path: &str) -> bool {
+    match load(path) {
+        Some(snapshot) => {
+            let age = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs().saturating_sub(snapshot.created_at))
+                .unwrap_or_default();
+            info!("♻️ 从快照恢复 Inventory: {} 个套利对, {} 个 Orca 池子, {} 组静态 Key, {} 个代币 (快照时间: {}s 前)",
+                snapshot.pairs.len(), snapshot.orca_pools.len(), snapshot.pool_keys.len(), snapshot.tokens.len(), age);
+            inventory.restore_snapshot(snapshot);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 后台定时写入快照
+pub fn spawn_writer(inventory: Arc<Inventory>, path: String, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // 第一次 tick 立即返回，跳过它，避免启动时用空索引覆盖旧快照
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if inventory.pair_count() == 0 {
+                continue;
+            }
+
+            let snapshot = inventory.to_snapshot();
+            let path = path.clone();
+            let result = tokio::task::spawn_blocking(move || save(&snapshot, &path)).await;
+            match result {
+                Ok(Ok(())) => log::debug!("💾 Inventory 快照已写入"),
+                Ok(Err(e)) => error!("❌ 写入 Inventory 快照失败: {}", e),
+                Err(e) => error!("❌ 快照写入任务异常: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_roundtrip_and_version_check() {
+        let path = std::env::temp_dir().join(format!("scavenger-snapshot-{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let inventory = Inventory::new();
+        let (mint, quote, ray, orca) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        inventory.add_pool(mint, quote, orca);
+        inventory.insert_pair(ArbitragePair { token_mint: mint, raydium_pool: ray, orca_pool: Some(orca), meteora_pool: None });
+        save(&inventory.to_snapshot(), path).unwrap();
+
+        let restored = Inventory::new();
+        assert!(warm_start(&restored, path));
+        assert_eq!(restored.find_pair_by_pool(&ray).unwrap().token_mint, mint);
+        assert_eq!(restored.get_pools(&quote), Some(vec![orca]));
+
+        // 版本号不同的快照直接丢弃
+        let bytes = bincode::serialize(&(SNAPSHOT_VERSION + 1, inventory.to_snapshot())).unwrap();
+        fs::write(path, bytes).unwrap();
+        assert!(load(path).is_none());
+        fs::remove_file(path).unwrap();
+        assert!(load(path).is_none());
+    }
+}
@@ -1,12 +1,20 @@
 use std::sync::Arc;
 use dashmap::DashMap;
 use solana_sdk::pubkey::Pubkey;
-use crate::scout::api::{fetch_raydium_pools, fetch_orca_pools};
+use serde::{Deserialize, Serialize};
+use crate::scout::api::PoolInfo;
+use crate::core::raydium_keys::RaydiumPoolKeys;
+use crate::amm::orca_whirlpool::{Whirlpool, WhirlpoolKeys};
+use crate::amm::raydium_v4::AmmState;
+use crate::amm::{PoolLifecycle, unix_now};
+use crate::snapshot::InventorySnapshot;
+use crate::token_registry::TokenRegistry;
 use log::info;
-// use std::collections::HashSet;
+use tokio::sync::broadcast;
+use std::collections::{HashMap, HashSet};
 
 /// 套利对结构体
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbitragePair {
     pub token_mint: Pubkey,
     pub raydium_pool: Pubkey,
@@ -14,71 +22,218 @@ pub struct ArbitragePair {
     pub meteora_pool: Option<Pubkey>,
 }
 
+/// 已解析的池子静态 Key (Vault / Market / Authority 等)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PoolKeys {
+    Raydium(Box<RaydiumPoolKeys>),
+    Whirlpool(WhirlpoolKeys),
+}
+
+/// Inventory 统计信息
+#[derive(Debug, Clone, Default)]
+pub struct InventoryStats {
+    /// orca_pools 索引中的 Token 数量
+    pub tokens: usize,
+    /// 被套利对引用的池子数量
+    pub indexed_pools: usize,
+    pub pairs: usize,
+    // 各生命周期状态的池子数量 (只统计观察到过账户数据的池子)
+    pub pending_open: usize,
+    pub active: usize,
+    pub disabled: usize,
+    pub drained: usize,
+}
+
+/// Inventory 变更通知，订阅层据此在运行时增减订阅
+#[derive(Debug, Clone)]
+pub enum InventoryChange {
+    /// 新增套利对，或已有套利对的腿发生了变化
+    PairAdded(ArbitragePair),
+    /// 套利对被删除 (或被替换前的旧版本)
+    PairRemoved(ArbitragePair),
+    /// 池子进入不可交易状态 (Disabled / Drained)
+    PoolDisabled { pool: Pubkey, state: PoolLifecycle },
+    /// 新的 Orca 池子加入索引 (例如 Sniper 模式实时发现的新池)
+    PoolAdded { pool: Pubkey, token_a: Pubkey, token_b: Pubkey },
+}
+
+// 变更通知缓冲区，订阅方落后太多会收到 Lagged，需要整体重新对账
+const CHANGE_CHANNEL_CAPACITY: usize = 1024;
+
+/// 套利对的活跃度统计，用于监控名单排序
+#[derive(Debug, Clone, Default)]
+pub struct PairActivity {
+    /// 两边池子中较浅一侧的 TVL (USD)，决定了可套利的容量
+    pub tvl_usd: f64,
+    /// 上次排序以来收到的账户更新次数
+    pub pending_updates: u64,
+    /// 账户更新频率 (次/分钟, EWMA)
+    pub updates_per_min: f64,
+    /// 观察到的价差 (%, EWMA)
+    pub spread_pct: f64,
+}
+
 /// 全网代币索引 (In-Memory Inventory)
 /// 核心数据结构: TokenMint -> Vec<PoolAddress>
 /// 用于快速查找某个 Token 在 Orca 上是否有流动性池
+/// 所有写操作都必须经过 `insert_pair` / `remove_pair` / `add_pool` / `remove_pool`，
+/// 这样 `common_pairs` 与反向索引 `pool_index` 才不会出现不一致。
 #[derive(Debug, Clone)]
 pub struct Inventory {
     // Key: Token Mint, Value: List of Orca Whirlpool Addresses
     // 一个 Token 可能对应多个池子 (不同的 Fee Tier, 不同的配对如 SOL/USDC)
-    pub orca_pools: Arc<DashMap<Pubkey, Vec<Pubkey>>>,
+    orca_pools: Arc<DashMap<Pubkey, Vec<Pubkey>>>,
+
+    // Key: Orca Pool Address, Value: (TokenMintA, TokenMintB)
+    // 用于 remove_pool 时回收 orca_pools 中的条目
+    orca_pool_tokens: Arc<DashMap<Pubkey, (Pubkey, Pubkey)>>,
     
     // Key: Token Mint, Value: ArbitragePair
-    pub common_pairs: Arc<DashMap<Pubkey, ArbitragePair>>,
+    common_pairs: Arc<DashMap<Pubkey, ArbitragePair>>,
+
+    // 反向索引: Pool Address -> Token Mint (即 common_pairs 的 Key)
+    // 每次账户更新都要查一次，必须是 O(1)
+    pool_index: Arc<DashMap<Pubkey, Pubkey>>,
+
+    // Key: Pool Address, Value: 解析后的静态 Key，构建 Swap 时免去重复 RPC
+    pool_keys: Arc<DashMap<Pubkey, PoolKeys>>,
+
+    // 代币元数据注册表 (精度 / Token Program / 名称)
+    tokens: TokenRegistry,
+
+    // Key: Token Mint (套利对), Value: 活跃度统计
+    activity: Arc<DashMap<Pubkey, PairActivity>>,
+
+    // Key: Pool Address, Value: 最近一次从账户数据推导出的生命周期
+    lifecycle: Arc<DashMap<Pubkey, PoolLifecycle>>,
+
+    // 变更通知 (没有订阅方时直接丢弃)
+    changes: broadcast::Sender<InventoryChange>,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArbitragePair {
+    /// 该套利对涉及的所有池子地址
+    pub fn pools(&self) -> Vec<Pubkey> {
+        let mut pools = vec![self.raydium_pool];
+        pools.extend(self.orca_pool);
+        pools.extend(self.meteora_pool);
+        pools
+    }
 }
 
 impl Inventory {
     pub fn new() -> Self {
         Self {
             orca_pools: Arc::new(DashMap::new()),
+            orca_pool_tokens: Arc::new(DashMap::new()),
             common_pairs: Arc::new(DashMap::new()),
+            pool_index: Arc::new(DashMap::new()),
+            pool_keys: Arc::new(DashMap::new()),
+            tokens: TokenRegistry::new(),
+            activity: Arc::new(DashMap::new()),
+            lifecycle: Arc::new(DashMap::new()),
+            changes: broadcast::channel(CHANGE_CHANNEL_CAPACITY).0,
         }
     }
 
-    /// 从 API 加载并构建共有白名单
-    pub async fn load_from_api(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        // 1. 并发获取两个 DEX 的池子
-        let (ray_pools, orca_pools) = tokio::join!(
-            fetch_raydium_pools(),
-            fetch_orca_pools()
-        );
+    /// 订阅 Inventory 变更通知
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<InventoryChange> {
+        self.changes.subscribe()
+    }
 
-        let ray_pools = ray_pools?;
-        let orca_pools = orca_pools?;
+    fn notify(&self, change: InventoryChange) {
+        let _ = self.changes.send(change);
+    }
 
-        // 2. 构建映射以便查找
-        // TokenMint -> RaydiumPool (假设每个 Token 只有一个主要池子，或者取第一个)
-        let mut ray_map = std::collections::HashMap::new();
+    /// 用发现到的池子构建共有白名单 (数据来源见 scout::discovery)
+    /// 约定 Raydium 的 PoolInfo.token_a 为 Base Token (Orca 两边顺序任意)，返回本次写入的套利对数量
+    /// `prune` 为 true 表示这是一份完整的列表: 不在其中的套利对会被删除，
+    /// 只被这些套利对引用、也不在新列表中的池子一并移出索引
+    pub fn ingest_pools(&self, ray_pools: Vec<PoolInfo>, orca_pools: Vec<PoolInfo>, prune: bool) -> usize {
+        let listed: HashSet<Pubkey> = ray_pools.iter().chain(&orca_pools).map(|p| p.address).collect();
+        // 本次对账之前被套利对引用的池子
+        let previous: Vec<Pubkey> = self.pool_index.iter().map(|e| *e.key()).collect();
+        // 1. Raydium: 每个 Token 对只保留 TVL 最高的池子
+        // Orca 按字节序存放两边的 Mint，与 Raydium 的 Base / Quote 顺序不一定相同，按无序对匹配
+        let mut ray_best: HashMap<(Pubkey, Pubkey), PoolInfo> = HashMap::new();
         for p in ray_pools {
-            // 我们关注的是非 Quote Token (即 Base Token)
-            // 简单逻辑：如果 A 是 SOL/USDC，则 B 是 Base；反之亦然。
-            // 这里为了简化，我们把 TokenA 和 TokenB 都作为 Key 存一下
-            ray_map.insert(p.token_a, p.address);
-            // ray_map.insert(p.token_b, p.address); 
-            // 注意：这样可能会覆盖。更好的做法是识别 Base Token。
-            // 但 API 返回的通常 TokenA 是 Base。
+            let key = unordered(p.token_a, p.token_b);
+            match ray_best.get(&key) {
+                Some(best) if !prefer_pool(&p, best) => {}
+                _ => {
+                    ray_best.insert(key, p);
+                }
+            }
         }
 
-        // 3. 遍历 Orca 池子，寻找交集
-        let mut count = 0;
+        // 2. 遍历 Orca 池子，寻找 Token 对相同的 Raydium 池子
+        // 同一个 Token 有多个候选 (不同 Fee Tier / Quote) 时先选出 TVL 最高的一组，
+        // 每个 Token 只写入一次，避免每次对账都在候选之间来回切换
+        // 套利对以 Raydium 一侧的 Base Token 为键 (Orca 价格方向由策略按 Base 调整)
+        let mut candidates: HashMap<Pubkey, (PoolInfo, PoolInfo)> = HashMap::new();
         for p in orca_pools {
-            // 检查 Token A 是否在 Raydium 有池子
-            if let Some(ray_addr) = ray_map.get(&p.token_a) {
-                self.common_pairs.insert(p.token_a, ArbitragePair {
-                    token_mint: p.token_a,
-                    raydium_pool: *ray_addr,
-                    orca_pool: Some(p.address),
-                    meteora_pool: None,
-                });
-                count += 1;
-            }
-            
             // 同时也添加单纯的 Orca 池子到 orca_pools 索引中 (为了 Sniper 策略兼容)
             self.add_pool(p.token_a, p.token_b, p.address);
+
+            let ray = match ray_best.get(&unordered(p.token_a, p.token_b)) {
+                Some(ray) => ray,
+                None => continue,
+            };
+            let base = ray.token_a;
+            // 套利容量取决于较浅的一侧
+            let shallow = PoolInfo { tvl: ray.tvl.min(p.tvl), ..p.clone() };
+            match candidates.get(&base) {
+                Some((best, _)) if !prefer_pool(&shallow, best) => {}
+                _ => {
+                    candidates.insert(base, (shallow, ray.clone()));
+                }
+            }
+        }
+
+        let count = candidates.len();
+        let fresh_pairs: HashSet<Pubkey> = candidates.keys().copied().collect();
+        for (mint, (orca, ray)) in candidates {
+            self.insert_pair(ArbitragePair {
+                token_mint: mint,
+                raydium_pool: ray.address,
+                orca_pool: Some(orca.address),
+                meteora_pool: None,
+            });
+            self.activity.entry(mint).or_default().tvl_usd = orca.tvl;
+        }
+
+        // 3. 数据源中已经消失的套利对 / 池子
+        if prune {
+            let gone: Vec<Pubkey> = self
+                .common_pairs
+                .iter()
+                .map(|e| *e.key())
+                .filter(|mint| !fresh_pairs.contains(mint))
+                .collect();
+            for mint in &gone {
+                self.remove_pair(mint);
+            }
+            // 不再被任何套利对引用 (套利对删除或换腿) 且新列表里也没有的池子
+            let mut removed_pools = 0;
+            for pool in previous {
+                if !listed.contains(&pool) && !self.pool_index.contains_key(&pool) {
+                    self.remove_pool(&pool);
+                    removed_pools += 1;
+                }
+            }
+            if !gone.is_empty() || removed_pools > 0 {
+                info!("🧹 数据源中已消失: 删除 {} 个套利对, {} 个池子", gone.len(), removed_pools);
+            }
         }
 
         info!("✅ Loaded {} common arbitrage pairs from Raydium/Orca", count);
-        Ok(())
+        count
     }
 
     /// 获取需要监听的 Pool Address 列表 (用于 Geyser/WebSocket)
@@ -94,31 +249,209 @@ impl Inventory {
         list
     }
     
-    /// 根据 Pool Address 查找所属的 ArbitragePair
+    /// 根据 Pool Address 查找所属的 ArbitragePair (O(1)，走反向索引)
     pub fn find_pair_by_pool(&self, pool_address: &Pubkey) -> Option<ArbitragePair> {
-        for entry in self.common_pairs.iter() {
-            let pair = entry.value();
-            if pair.raydium_pool == *pool_address || pair.orca_pool == Some(*pool_address) {
-                return Some(pair.clone());
+        let token_mint = *self.pool_index.get(pool_address)?;
+        let pair = self.common_pairs.get(&token_mint)?;
+        // 读取两张表之间 pair 可能刚被替换，再确认一次
+        if pair.pools().contains(pool_address) {
+            Some(pair.clone())
+        } else {
+            None
+        }
+    }
+
+    /// 根据 Token Mint 获取套利对
+    pub fn get_pair(&self, token_mint: &Pubkey) -> Option<ArbitragePair> {
+        self.common_pairs.get(token_mint).map(|p| p.clone())
+    }
+
+    /// 当前套利对数量
+    pub fn pair_count(&self) -> usize {
+        self.common_pairs.len()
+    }
+
+    /// 插入或替换一个套利对，同时维护反向索引
+    /// 返回被替换掉的旧套利对 (如果有)
+    pub fn insert_pair(&self, pair: ArbitragePair) -> Option<ArbitragePair> {
+        let token_mint = pair.token_mint;
+        let new_pools = pair.pools();
+        let old = self.common_pairs.insert(token_mint, pair.clone());
+
+        // 并发写同一个套利对时索引里可能短暂残留旧池子，
+        // find_pair_by_pool 会二次校验，所以残留条目只会返回 None
+        if let Some(old) = &old {
+            for pool in old.pools() {
+                if !new_pools.contains(&pool) {
+                    self.pool_index.remove_if(&pool, |_, mint| *mint == token_mint);
+                }
+            }
+        }
+        for &pool in &new_pools {
+            if let Some(prev) = self.pool_index.insert(pool, token_mint) {
+                if prev != token_mint {
+                    // 同一个池子只能属于一个套利对，把旧套利对中的引用摘掉
+                    self.detach_pool_from_pair(&prev, &pool);
+                }
+            }
+        }
+
+        // 对账时大部分套利对不会变化，只通知真正的变更
+        match &old {
+            Some(o) if o.pools() == new_pools => {}
+            Some(o) => {
+                self.notify(InventoryChange::PairRemoved(o.clone()));
+                self.notify(InventoryChange::PairAdded(pair));
+            }
+            None => self.notify(InventoryChange::PairAdded(pair)),
+        }
+        old
+    }
+
+    /// 删除一个套利对，同时清理反向索引
+    pub fn remove_pair(&self, token_mint: &Pubkey) -> Option<ArbitragePair> {
+        let (_, pair) = self.common_pairs.remove(token_mint)?;
+        self.activity.remove(token_mint);
+        for pool in pair.pools() {
+            self.pool_index.remove_if(&pool, |_, mint| mint == token_mint);
+        }
+        self.notify(InventoryChange::PairRemoved(pair.clone()));
+        Some(pair)
+    }
+
+    /// 从索引中彻底删除一个池子 (Orca 索引 + 所属套利对)
+    /// 如果删除后套利对只剩单边，则整个套利对一并删除
+    pub fn remove_pool(&self, pool_address: &Pubkey) -> Option<ArbitragePair> {
+        self.lifecycle.remove(pool_address);
+        if let Some((_, (token_a, token_b))) = self.orca_pool_tokens.remove(pool_address) {
+            for mint in [token_a, token_b] {
+                self.orca_pools.remove_if_mut(&mint, |_, pools| {
+                    pools.retain(|p| p != pool_address);
+                    pools.is_empty()
+                });
+            }
+        }
+
+        let (_, token_mint) = self.pool_index.remove(pool_address)?;
+        self.detach_pool_from_pair(&token_mint, pool_address)
+    }
+
+    /// 把池子从套利对中摘除，返回因此失效而被删除的套利对
+    fn detach_pool_from_pair(&self, token_mint: &Pubkey, pool_address: &Pubkey) -> Option<ArbitragePair> {
+        let removed = self.common_pairs.remove_if_mut(token_mint, |_, pair| {
+            if pair.orca_pool == Some(*pool_address) {
+                pair.orca_pool = None;
+            }
+            if pair.meteora_pool == Some(*pool_address) {
+                pair.meteora_pool = None;
             }
+            // Raydium 是套利对的主腿，或者已经没有对手盘了
+            pair.raydium_pool == *pool_address
+                || (pair.orca_pool.is_none() && pair.meteora_pool.is_none())
+        });
+        let (_, pair) = removed?;
+        self.activity.remove(token_mint);
+        for pool in pair.pools() {
+            self.pool_index.remove_if(&pool, |_, mint| mint == token_mint);
+        }
+        self.pool_index.remove_if(pool_address, |_, mint| mint == token_mint);
+        self.notify(InventoryChange::PairRemoved(pair.clone()));
+        Some(pair)
+    }
+
+    /// 当前所有套利对的快照
+    pub fn pairs(&self) -> Vec<ArbitragePair> {
+        self.common_pairs.iter().map(|e| e.value().clone()).collect()
+    }
+
+    /// 记录一次池子账户更新 (热路径，只做计数)
+    pub fn record_pool_update(&self, pool_address: &Pubkey) {
+        if let Some(token_mint) = self.pool_index.get(pool_address).map(|m| *m) {
+            self.activity.entry(token_mint).or_default().pending_updates += 1;
+        }
+    }
+
+    /// 记录一次观察到的价差
+    pub fn record_spread(&self, token_mint: &Pubkey, spread_pct: f64) {
+        if !self.common_pairs.contains_key(token_mint) {
+            return;
         }
-        None
+        let mut activity = self.activity.entry(*token_mint).or_default();
+        activity.spread_pct = ewma(activity.spread_pct, spread_pct);
+    }
+
+    /// 把上个窗口内的更新计数折算进更新频率，返回最新的活跃度快照
+    pub fn roll_activity(&self, elapsed: std::time::Duration) -> Vec<(Pubkey, PairActivity)> {
+        let minutes = elapsed.as_secs_f64() / 60.0;
+        self.activity
+            .iter_mut()
+            .map(|mut e| {
+                if minutes > 0.0 {
+                    let rate = e.pending_updates as f64 / minutes;
+                    e.updates_per_min = ewma(e.updates_per_min, rate);
+                    e.pending_updates = 0;
+                }
+                (*e.key(), e.value().clone())
+            })
+            .collect()
     }
 
     /// 添加一个新的 Orca 池子到索引中
     /// 通常在启动时全量加载，或监听到 InitializePool 事件时调用
     pub fn add_pool(&self, token_mint_a: Pubkey, token_mint_b: Pubkey, pool_address: Pubkey) {
+        let is_new = self.orca_pool_tokens.insert(pool_address, (token_mint_a, token_mint_b)).is_none();
+
         // 索引 Token A -> Pool
-        let mut pools_a = self.orca_pools.entry(token_mint_a).or_insert(Vec::new());
+        let mut pools_a = self.orca_pools.entry(token_mint_a).or_default();
         if !pools_a.contains(&pool_address) {
             pools_a.push(pool_address);
         }
+        drop(pools_a);
 
         // 索引 Token B -> Pool
-        let mut pools_b = self.orca_pools.entry(token_mint_b).or_insert(Vec::new());
+        let mut pools_b = self.orca_pools.entry(token_mint_b).or_default();
         if !pools_b.contains(&pool_address) {
             pools_b.push(pool_address);
         }
+        drop(pools_b);
+
+        if is_new {
+            self.notify(InventoryChange::PoolAdded {
+                pool: pool_address,
+                token_a: token_mint_a,
+                token_b: token_mint_b,
+            });
+        }
+    }
+
+    /// 新的 Raydium 池子: 同一 Token 对 (Base / Quote) 在 Orca 上也有池子且还没有套利对时组成新的套利对
+    /// 已有的套利对不会被替换，返回是否新增
+    pub fn pair_raydium_pool(&self, base_mint: Pubkey, quote_mint: Pubkey, raydium_pool: Pubkey) -> bool {
+        if self.common_pairs.contains_key(&base_mint) || self.pool_index.contains_key(&raydium_pool) {
+            return false;
+        }
+        // 有多个匹配的 Fee Tier 时按地址取固定的一个
+        let orca_pool = self
+            .get_pools(&base_mint)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|pool| {
+                self.orca_pool_tokens.get(pool).is_some_and(|tokens| {
+                    *tokens == (base_mint, quote_mint) || *tokens == (quote_mint, base_mint)
+                })
+            })
+            .min();
+        let orca_pool = match orca_pool {
+            Some(pool) => pool,
+            None => return false,
+        };
+        self.insert_pair(ArbitragePair {
+            token_mint: base_mint,
+            raydium_pool,
+            orca_pool: Some(orca_pool),
+            meteora_pool: None,
+        });
+        true
     }
 
     /// 获取某个 Token 参与的所有 Orca 池子
@@ -131,11 +464,316 @@ impl Inventory {
         self.orca_pools.contains_key(token_mint)
     }
 
+    /// 缓存池子的静态 Key，Raydium 池子会顺带记录两边代币的精度
+    pub fn set_pool_keys(&self, keys: PoolKeys) {
+        let pool = match &keys {
+            PoolKeys::Raydium(k) => {
+                self.tokens.record_decimals(k.base_mint, k.base_decimals);
+                self.tokens.record_decimals(k.quote_mint, k.quote_decimals);
+                k.id
+            }
+            PoolKeys::Whirlpool(k) => {
+                self.tokens.request(k.token_mint_a);
+                self.tokens.request(k.token_mint_b);
+                k.id
+            }
+        };
+        self.pool_keys.insert(pool, keys);
+    }
+
+    pub fn has_pool_keys(&self, pool_address: &Pubkey) -> bool {
+        self.pool_keys.contains_key(pool_address)
+    }
+
+    pub fn get_pool_keys(&self, pool_address: &Pubkey) -> Option<PoolKeys> {
+        self.pool_keys.get(pool_address).map(|k| k.clone())
+    }
+
+    /// 代币元数据注册表
+    pub fn tokens(&self) -> &TokenRegistry {
+        &self.tokens
+    }
+
+    /// 导出当前索引，用于写入磁盘快照
+    pub fn to_snapshot(&self) -> InventorySnapshot {
+        InventorySnapshot {
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            pairs: self.common_pairs.iter().map(|e| e.value().clone()).collect(),
+            orca_pools: self.orca_pool_tokens.iter().map(|e| (*e.key(), e.value().0, e.value().1)).collect(),
+            pool_keys: self.pool_keys.iter().map(|e| e.value().clone()).collect(),
+            tokens: self.tokens.all(),
+        }
+    }
+
+    /// 从快照恢复索引 (Warm Start)，走正常的写入接口以保证反向索引一致
+    pub fn restore_snapshot(&self, snapshot: InventorySnapshot) {
+        for (pool, token_a, token_b) in snapshot.orca_pools {
+            self.add_pool(token_a, token_b, pool);
+        }
+        for pair in snapshot.pairs {
+            self.insert_pair(pair);
+        }
+        for keys in snapshot.pool_keys {
+            self.set_pool_keys(keys);
+        }
+        for info in snapshot.tokens {
+            self.tokens.insert(info);
+        }
+    }
+
+    /// 根据池子账户数据更新生命周期 (按账户长度区分 Raydium / Whirlpool)
+    /// 状态发生变化时返回新状态
+    pub fn observe_pool_account(&self, pool_address: &Pubkey, data: &[u8]) -> Option<PoolLifecycle> {
+        let state = if data.len() == AmmState::LEN {
+            AmmState::lifecycle(data, unix_now())?
+        } else if data.len() == Whirlpool::LEN {
+            Whirlpool::lifecycle(data)?
+        } else {
+            return None;
+        };
+        match self.lifecycle.insert(*pool_address, state) {
+            Some(prev) if prev == state => None,
+            _ => {
+                if !state.is_tradable(unix_now()) {
+                    self.notify(InventoryChange::PoolDisabled { pool: *pool_address, state });
+                }
+                Some(state)
+            }
+        }
+    }
+
+    /// 处于 Disabled / Drained 的池子，需要定期重查是否已恢复
+    /// (这些池子已被退订，不会再收到账户推送)
+    pub fn inactive_pools(&self) -> Vec<Pubkey> {
+        self.lifecycle
+            .iter()
+            .filter(|e| matches!(e.value(), PoolLifecycle::Disabled | PoolLifecycle::Drained))
+            .map(|e| *e.key())
+            .collect()
+    }
+
+    /// 池子当前的生命周期 (PendingOpen 到点后视为 Active)
+    pub fn pool_lifecycle(&self, pool_address: &Pubkey) -> Option<PoolLifecycle> {
+        self.lifecycle.get(pool_address).map(|s| s.at(unix_now()))
+    }
+
+    /// 池子是否可交易，尚未观察到账户数据的池子视为可交易
+    pub fn is_pool_tradable(&self, pool_address: &Pubkey) -> bool {
+        self.pool_lifecycle(pool_address).map_or(true, |s| s == PoolLifecycle::Active)
+    }
+
+    /// 套利对的所有腿都可交易
+    pub fn is_pair_tradable(&self, pair: &ArbitragePair) -> bool {
+        pair.pools().iter().all(|p| self.is_pool_tradable(p))
+    }
+
     /// 获取当前索引的统计信息
-    pub fn stats(&self) -> (usize, usize) {
-        // (Token 数量, 总索引条目数)
-        let token_count = self.orca_pools.len();
-        // 简单的估算，不遍历
-        (token_count, 0) 
+    pub fn stats(&self) -> InventoryStats {
+        let mut stats = InventoryStats {
+            tokens: self.orca_pools.len(),
+            // 反向索引的条目数即被套利对引用的池子总数，不需要遍历
+            indexed_pools: self.pool_index.len(),
+            pairs: self.common_pairs.len(),
+            ..Default::default()
+        };
+        let now = unix_now();
+        for entry in self.lifecycle.iter() {
+            match entry.value().at(now) {
+                PoolLifecycle::PendingOpen { .. } => stats.pending_open += 1,
+                PoolLifecycle::Active => stats.active += 1,
+                PoolLifecycle::Disabled => stats.disabled += 1,
+                PoolLifecycle::Drained => stats.drained += 1,
+            }
+        }
+        stats
+    }
+}
+
+/// 候选池子中优先 TVL 高的；TVL 相同 (例如链上发现没有 TVL) 时按地址取固定的一个
+fn prefer_pool(candidate: &PoolInfo, current: &PoolInfo) -> bool {
+    candidate.tvl > current.tvl || (candidate.tvl == current.tvl && candidate.address < current.address)
+}
+
+/// 与顺序无关的 Token 对
+fn unordered(a: Pubkey, b: Pubkey) -> (Pubkey, Pubkey) {
+    if a <= b { (a, b) } else { (b, a) }
+}
+
+/// 活跃度统计使用的指数移动平均
+fn ewma(prev: f64, sample: f64) -> f64 {
+    const ALPHA: f64 = 0.3;
+    if prev == 0.0 {
+        sample
+    } else {
+        ALPHA * sample + (1.0 - ALPHA) * prev
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(token_mint: Pubkey, raydium_pool: Pubkey, orca_pool: Pubkey) -> ArbitragePair {
+        ArbitragePair {
+            token_mint,
+            raydium_pool,
+            orca_pool: Some(orca_pool),
+            meteora_pool: None,
+        }
+    }
+
+    #[test]
+    fn test_pool_index_stays_in_sync() {
+        let inventory = Inventory::new();
+        let (mint, ray, orca) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        inventory.insert_pair(pair(mint, ray, orca));
+        assert_eq!(inventory.find_pair_by_pool(&ray).unwrap().token_mint, mint);
+        assert_eq!(inventory.find_pair_by_pool(&orca).unwrap().token_mint, mint);
+
+        // 替换 Orca 腿后，旧池子不应再命中
+        let new_orca = Pubkey::new_unique();
+        inventory.insert_pair(pair(mint, ray, new_orca));
+        assert!(inventory.find_pair_by_pool(&orca).is_none());
+        assert!(inventory.find_pair_by_pool(&new_orca).is_some());
+
+        // 删除唯一的对手盘，整个套利对失效
+        inventory.add_pool(mint, Pubkey::new_unique(), new_orca);
+        assert!(inventory.remove_pool(&new_orca).is_some());
+        assert!(inventory.find_pair_by_pool(&ray).is_none());
+        assert_eq!(inventory.pair_count(), 0);
+        assert!(!inventory.has_liquidity(&mint));
+        assert_eq!(inventory.stats().indexed_pools, 0);
+    }
+
+    #[test]
+    fn test_change_notifications() {
+        let inventory = Inventory::new();
+        let mut changes = inventory.subscribe_changes();
+        let (mint, ray, orca) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        inventory.insert_pair(pair(mint, ray, orca));
+        assert!(matches!(changes.try_recv(), Ok(InventoryChange::PairAdded(p)) if p.token_mint == mint));
+
+        // 内容不变的重复写入不产生通知
+        inventory.insert_pair(pair(mint, ray, orca));
+        assert!(changes.try_recv().is_err());
+
+        let new_pool = Pubkey::new_unique();
+        inventory.add_pool(mint, Pubkey::new_unique(), new_pool);
+        assert!(matches!(changes.try_recv(), Ok(InventoryChange::PoolAdded { pool, .. }) if pool == new_pool));
+
+        inventory.remove_pair(&mint);
+        assert!(matches!(changes.try_recv(), Ok(InventoryChange::PairRemoved(p)) if p.raydium_pool == ray));
+    }
+
+    fn pool_info(address: Pubkey, token_a: Pubkey, token_b: Pubkey, tvl: f64) -> PoolInfo {
+        PoolInfo { address, token_a, token_b, tvl }
+    }
+
+    #[test]
+    fn test_ingest_prunes_vanished_pairs() {
+        let inventory = Inventory::new();
+        let quote = Pubkey::new_unique();
+        let (kept, gone) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (kept_ray, kept_orca) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (gone_ray, gone_orca) = (Pubkey::new_unique(), Pubkey::new_unique());
+        inventory.ingest_pools(
+            vec![pool_info(kept_ray, kept, quote, 0.0), pool_info(gone_ray, gone, quote, 0.0)],
+            vec![pool_info(kept_orca, kept, quote, 0.0), pool_info(gone_orca, gone, quote, 0.0)],
+            true,
+        );
+        assert_eq!(inventory.pair_count(), 2);
+
+        let mut changes = inventory.subscribe_changes();
+        // 不完整的列表不删除任何东西
+        inventory.ingest_pools(vec![pool_info(kept_ray, kept, quote, 0.0)], vec![pool_info(kept_orca, kept, quote, 0.0)], false);
+        assert_eq!(inventory.pair_count(), 2);
+
+        inventory.ingest_pools(vec![pool_info(kept_ray, kept, quote, 0.0)], vec![pool_info(kept_orca, kept, quote, 0.0)], true);
+        assert_eq!(inventory.pair_count(), 1);
+        assert!(inventory.find_pair_by_pool(&gone_ray).is_none());
+        assert!(!inventory.get_pools(&gone).unwrap_or_default().contains(&gone_orca));
+        assert!(matches!(changes.try_recv(), Ok(InventoryChange::PairRemoved(p)) if p.token_mint == gone));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_ingest_picks_one_pool_per_token() {
+        let inventory = Inventory::new();
+        let (mint, quote) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ray = Pubkey::new_unique();
+        let (shallow, deep) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ray_pools = || vec![pool_info(ray, mint, quote, 1_000_000.0)];
+        let orca_pools = || vec![pool_info(deep, mint, quote, 50_000.0), pool_info(shallow, mint, quote, 5_000.0)];
+
+        let mut changes = inventory.subscribe_changes();
+        assert_eq!(inventory.ingest_pools(ray_pools(), orca_pools(), true), 1);
+        assert_eq!(inventory.get_pair(&mint).unwrap().orca_pool, Some(deep));
+        assert_eq!(inventory.roll_activity(std::time::Duration::ZERO)[0].1.tvl_usd, 50_000.0);
+        let added = std::iter::from_fn(|| changes.try_recv().ok())
+            .filter(|c| matches!(c, InventoryChange::PairAdded(_)))
+            .count();
+        assert_eq!(added, 1);
+
+        // 顺序不同的重复对账不产生任何套利对变更
+        let mut reversed = orca_pools();
+        reversed.reverse();
+        inventory.ingest_pools(ray_pools(), reversed, true);
+        assert_eq!(inventory.get_pair(&mint).unwrap().orca_pool, Some(deep));
+        assert!(std::iter::from_fn(|| changes.try_recv().ok())
+            .all(|c| !matches!(c, InventoryChange::PairAdded(_) | InventoryChange::PairRemoved(_))));
+    }
+
+    #[test]
+    fn test_ingest_matches_reversed_orca_mints() {
+        let inventory = Inventory::new();
+        let (base, quote) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (ray, orca) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // Orca 按字节序存放 Mint，这里与 Raydium 的 Base / Quote 顺序相反
+        assert_eq!(
+            inventory.ingest_pools(vec![pool_info(ray, base, quote, 10.0)], vec![pool_info(orca, quote, base, 20.0)], true),
+            1
+        );
+        let pair = inventory.get_pair(&base).unwrap();
+        assert_eq!((pair.raydium_pool, pair.orca_pool), (ray, Some(orca)));
+        assert!(inventory.get_pair(&quote).is_none());
+        assert_eq!(inventory.find_pair_by_pool(&orca).map(|p| p.token_mint), Some(base));
+    }
+
+    #[test]
+    fn test_pair_raydium_pool_matches_quote() {
+        let inventory = Inventory::new();
+        let (mint, usdc, sol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (orca_usdc, orca_sol) = (Pubkey::new_unique(), Pubkey::new_unique());
+        inventory.add_pool(mint, usdc, orca_usdc);
+        inventory.add_pool(sol, mint, orca_sol);
+
+        // 没有 Token 对相同的 Orca 池子时不组成套利对
+        assert!(!inventory.pair_raydium_pool(mint, Pubkey::new_unique(), Pubkey::new_unique()));
+        assert_eq!(inventory.pair_count(), 0);
+
+        let ray = Pubkey::new_unique();
+        assert!(inventory.pair_raydium_pool(mint, sol, ray));
+        assert_eq!(inventory.get_pair(&mint).unwrap().orca_pool, Some(orca_sol));
+    }
+
+    #[test]
+    fn test_disabled_pool_can_recover() {
+        let inventory = Inventory::new();
+        let pool = Pubkey::new_unique();
+        let mut data = vec![0u8; Whirlpool::LEN];
+        assert_eq!(inventory.observe_pool_account(&pool, &data), Some(PoolLifecycle::Drained));
+        assert_eq!(inventory.inactive_pools(), vec![pool]);
+        assert!(!inventory.is_pool_tradable(&pool));
+
+        // 重新注入流动性后恢复为可交易
+        data[Whirlpool::LIQUIDITY_OFFSET..Whirlpool::LIQUIDITY_OFFSET + 16].copy_from_slice(&7u128.to_le_bytes());
+        assert_eq!(inventory.observe_pool_account(&pool, &data), Some(PoolLifecycle::Active));
+        assert!(inventory.inactive_pools().is_empty());
+        assert!(inventory.is_pool_tradable(&pool));
     }
 }
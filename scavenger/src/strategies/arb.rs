@@ -4,21 +4,55 @@ use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
 use log::{info, warn, error};
 use crate::config::StrategyConfig;
-use crate::state::Inventory;
-use crate::amm::orca_whirlpool::Whirlpool;
+use crate::state::{Inventory, PoolKeys};
+use crate::core::raydium_keys::{decode_raydium_keys_from_data, fetch_raydium_keys};
+use crate::amm::orca_whirlpool::{Whirlpool, adjust_price_for_decimals};
 use crate::amm::raydium_v4::AmmState;
-use crate::amm::serum::SerumMarketV3;
 use borsh::BorshDeserialize;
 use crate::core::jito_http::JitoHttpClient;
-use crate::core::swap::{swap as build_raydium_swap, build_orca_swap};
+use crate::core::accounts::AccountLoader;
+use crate::core::blockhash::BlockhashCache;
+use crate::core::latency::{LatencyTracker, Stage};
+use crate::core::leader::{LeaderTracker, SubmitDecision};
+use crate::core::swap::swap as build_raydium_swap;
 use std::str::FromStr;
+use tokio::task::JoinHandle;
+use crate::bus::{BusEvent, EventBus, EventKind};
+use crate::scout::events::EventSource;
+use std::time::Instant;
+use crate::strategies::StrategyContext;
 
 // Constants
-const ORCA_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+const RAYDIUM_V4_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
 const JITO_TIP_ACCOUNT: &str = "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"; // Jito Tip Account 1
-const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// 订阅池子账户更新，每次更新单独起一个任务计算价差
+pub fn spawn(ctx: StrategyContext, bus: &EventBus) -> JoinHandle<()> {
+    let mut events = bus.subscribe("arb", &[EventKind::AccountUpdate]);
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let BusEvent::AccountUpdate(update) = &*event {
+                tokio::spawn(process_account_update(
+                    ctx.rpc_client.clone(),
+                    ctx.keypair.clone(),
+                    update.pubkey,
+                    update.data.clone(),
+                    ctx.config.clone(),
+                    ctx.inventory.clone(),
+                    ctx.leaders.clone(),
+                    ctx.blockhash.clone(),
+                    ctx.accounts.clone(),
+                    ctx.latency.clone(),
+                    update.source,
+                    update.received_at,
+                ));
+            }
+        }
+    })
+}
 
 /// 处理账户更新 (主要针对 Orca)
+#[allow(clippy::too_many_arguments)]
 pub async fn process_account_update(
     rpc_client: Arc<RpcClient>,
     keypair: Arc<Keypair>,
@@ -26,6 +60,12 @@ pub async fn process_account_update(
     data: Vec<u8>,
     config: Arc<StrategyConfig>,
     inventory: Arc<Inventory>,
+    leaders: LeaderTracker,
+    blockhash: BlockhashCache,
+    accounts: AccountLoader,
+    latency: LatencyTracker,
+    source: EventSource,
+    arrived: Instant,
 ) {
     // 1. 识别这属于哪个共有对
     let pair = match inventory.find_pair_by_pool(&pool_address) {
@@ -35,20 +75,40 @@ pub async fn process_account_update(
 
     // 2. 识别是哪个 DEX
     let is_orca = Some(pool_address) == pair.orca_pool;
+
+    // 跳过未开放 / 已禁用 / 已抽空的池子
+    inventory.observe_pool_account(&pool_address, &data);
+    if !inventory.is_pair_tradable(&pair) {
+        return;
+    }
+
+    // 顺带缓存池子的静态 Key (只解析一次，之后随快照持久化)
+    if !inventory.has_pool_keys(&pool_address) {
+        let keys = if is_orca {
+            Whirlpool::decode_keys(&pool_address, &data).map(PoolKeys::Whirlpool)
+        } else {
+            decode_raydium_keys_from_data(&pool_address, &raydium_program_id(), &data).map(|k| PoolKeys::Raydium(Box::new(k)))
+        };
+        if let Some(keys) = keys {
+            inventory.set_pool_keys(keys);
+        }
+    }
     
     if is_orca {
-        // 解析 Orca 价格
-        if let Some(price_info) = Whirlpool::decode_current_price(&data) {
-            let orca_price = price_info.price;
-            info!("🐬 [Orca Update] Pool: {} | Price: {:.6}", pool_address, orca_price);
+        // 解析 Orca 价格 (按代币精度调整)
+        if let Some(orca_price) = orca_ui_price(&accounts, &inventory, &pool_address, &data, &pair.token_mint).await {
+            let decoded = Instant::now();
+            info!("🐬 [Orca Update] Pool: {} ({}) | Price: {:.6}",
+                pool_address, inventory.tokens().label(&pair.token_mint), orca_price);
             
             // 3. 获取对手盘 (Raydium) 价格
             let ray_pool_id = pair.raydium_pool;
-            let ray_price = fetch_raydium_price(rpc_client.clone(), ray_pool_id).await;
+            let ray_price = fetch_raydium_price(&accounts, &inventory, ray_pool_id).await;
+            let timeline = Timeline { source, arrived, decoded, quoted: Instant::now() };
             
             if let Some(ray_p) = ray_price {
                 // 4. 计算价差
-                check_spread_and_execute(rpc_client, keypair, orca_price, ray_p, "Orca", "Raydium", config, &pair).await;
+                check_spread_and_execute(rpc_client, keypair, orca_price, ray_p, "Orca", "Raydium", config, &pair, &inventory, &leaders, &blockhash, &accounts, &latency, timeline).await;
             }
         }
     } else {
@@ -64,13 +124,15 @@ pub async fn process_account_update(
                  let pc_scalar = 10f64.powi(pc_decimals as i32);
                  let ray_price = (pc_amount as f64 / pc_scalar) / (coin_amount as f64 / coin_scalar);
                  
+                 let decoded = Instant::now();
                  info!("🦄 [Raydium Update] Pool: {} | Price: {:.6}", pool_address, ray_price);
                  
                  if let Some(orca_pool_id) = pair.orca_pool {
-                     let orca_price = fetch_orca_price(rpc_client.clone(), orca_pool_id).await;
+                     let orca_price = fetch_orca_price(&accounts, &inventory, orca_pool_id, &pair.token_mint).await;
+                     let timeline = Timeline { source, arrived, decoded, quoted: Instant::now() };
                      
                      if let Some(orca_p) = orca_price {
-                         check_spread_and_execute(rpc_client, keypair, ray_price, orca_p, "Raydium", "Orca", config, &pair).await;
+                         check_spread_and_execute(rpc_client, keypair, ray_price, orca_p, "Raydium", "Orca", config, &pair, &inventory, &leaders, &blockhash, &accounts, &latency, timeline).await;
                      }
                  }
              }
@@ -78,7 +140,17 @@ pub async fn process_account_update(
     }
 }
 
+/// 事件到达、解析出价格、拿到对手盘报价的时刻
+/// 绝大多数更新不构成机会，确认是机会后才据此开始追踪延迟
+struct Timeline {
+    source: EventSource,
+    arrived: Instant,
+    decoded: Instant,
+    quoted: Instant,
+}
+
 /// 检查价差并执行
+#[allow(clippy::too_many_arguments)]
 async fn check_spread_and_execute(
     rpc_client: Arc<RpcClient>,
     keypair: Arc<Keypair>,
@@ -88,13 +160,23 @@ async fn check_spread_and_execute(
     label_b: &str,
     config: Arc<StrategyConfig>,
     pair: &crate::state::ArbitragePair,
+    inventory: &Inventory,
+    leaders: &LeaderTracker,
+    blockhash: &BlockhashCache,
+    accounts: &AccountLoader,
+    latency: &LatencyTracker,
+    timeline: Timeline,
 ) {
     let spread = (price_a - price_b).abs() / price_a.min(price_b);
     let spread_pct = spread * 100.0;
+    inventory.record_spread(&pair.token_mint, spread_pct);
     
     if spread_pct > 0.5 { // 0.5% 阈值
-        info!("🚨 [ARBITRAGE] Opportunity! {} (${:.6}) vs {} (${:.6}) | Spread: {:.2}%", 
-            label_a, price_a, label_b, price_b, spread_pct);
+        let mut trace = latency.begin(timeline.source, timeline.arrived);
+        trace.mark_at(Stage::Decode, timeline.decoded);
+        trace.mark_at(Stage::Quote, timeline.quoted);
+        info!("🚨 [ARBITRAGE] Opportunity #{}! {} (${:.6}) vs {} (${:.6}) | Spread: {:.2}%",
+            trace.id, label_a, price_a, label_b, price_b, spread_pct);
         
         let jito_client = JitoHttpClient::new();
         let amount_in_sol = config.trade_amount_sol; // e.g. 0.1 SOL
@@ -117,6 +199,7 @@ async fn check_spread_and_execute(
         };
         
         info!("🔄 Strategy: Buy on {} ({}), Sell on {} ({})", buy_label, buy_pool, sell_label, sell_pool);
+        trace.mark(Stage::Sizing);
         
         // 1. 构建 Swap Instructions (核心逻辑)
         let mut instructions = Vec::new();
@@ -129,7 +212,7 @@ async fn check_spread_and_execute(
         
         // 构建 Raydium 指令
         if buy_label == "Raydium" {
-            if let Some(ix) = build_raydium_swap_ix(rpc_client.clone(), &keypair.pubkey(), buy_pool, amount_in_lamports, 0).await {
+            if let Some(ix) = build_raydium_swap_ix(accounts, inventory, &keypair.pubkey(), buy_pool, amount_in_lamports, 0).await {
                 instructions.push(ix);
             } else {
                 warn!("❌ Failed to build Raydium Buy Instruction");
@@ -147,7 +230,7 @@ async fn check_spread_and_execute(
         // 但原子交易中无法预知确切的 out，通常使用 estimated out 或者 100% balance
         // 这里简化，假设 1:1 兑换
         if sell_label == "Raydium" {
-            if let Some(ix) = build_raydium_swap_ix(rpc_client.clone(), &keypair.pubkey(), sell_pool, amount_in_lamports, 0).await {
+            if let Some(ix) = build_raydium_swap_ix(accounts, inventory, &keypair.pubkey(), sell_pool, amount_in_lamports, 0).await {
                 instructions.push(ix);
             }
         } else if sell_label == "Orca" {
@@ -169,110 +252,145 @@ async fn check_spread_and_execute(
             tip_lamports,
         );
         instructions.push(tip_instruction);
-        
-        // 4. Build & Send Transaction
-        let recent_blockhash = match rpc_client.get_latest_blockhash().await {
-            Ok(hash) => hash,
+        // 先取一个 Blockhash 备用，签名前再取最新的
+        let held = match blockhash.get_or_fetch(&rpc_client).await {
+            Ok(recent) => recent,
             Err(e) => {
                 error!("❌ Failed to get blockhash: {}", e);
                 return;
             }
         };
+        trace.mark(Stage::Build);
+        
+        // 4. 等待 Jito Leader: 前瞻窗口内没有 Jito Leader 时放弃，Bundle 不会被打包
+        match leaders.decide() {
+            SubmitDecision::Send => {}
+            SubmitDecision::Hold { slots } => {
+                info!("⏳ 距下一个 Jito Leader 还有 {} 个 Slot，等待后发送", slots);
+                tokio::time::sleep(tokio::time::Duration::from_millis(slots * 400)).await;
+            }
+            SubmitDecision::Skip => {
+                warn!("⏭️ 前瞻窗口内没有 Jito Leader，放弃本次 Bundle");
+                return;
+            }
+        }
+        trace.mark(Stage::LeaderWait);
+
+        // 5. Build & Send Transaction
+        // 等待 Leader 期间缓存可能已经刷新，签名前取最新的 Blockhash；取不到时才沿用之前的，且不能临近过期
+        let recent = match blockhash.get_or_fetch(&rpc_client).await {
+            Ok(fresh) => fresh,
+            Err(e) if !blockhash.is_expiring(&held) => {
+                warn!("⚠️ 刷新 Blockhash 失败，沿用 {}: {}", held.blockhash, e);
+                held
+            }
+            Err(e) => {
+                error!("❌ Blockhash 即将过期且刷新失败: {}", e);
+                return;
+            }
+        };
         
         let tx = solana_sdk::transaction::Transaction::new_signed_with_payer(
             &instructions,
             Some(&keypair.pubkey()),
             &[&*keypair],
-            recent_blockhash,
+            recent.blockhash,
         );
         
         let tx_base58 = bs58::encode(bincode::serialize(&tx).unwrap()).into_string();
+        trace.mark(Stage::Sign);
         info!("📦 Sending Bundle to Jito (Real Arb Tx)...");
         
-        match jito_client.send_bundle(vec![tx_base58]).await {
+        match jito_client.send_bundle_traced(vec![tx_base58], Some(&mut trace)).await {
             Ok(bundle_id) => info!("✅ Bundle Sent! ID: {}", bundle_id),
             Err(e) => error!("❌ Bundle Send Failed: {}", e),
         }
+        let id = trace.id;
+        info!("⏱️ 机会 #{} 端到端耗时 {:?}", id, trace.finish());
     }
 }
 
 async fn build_raydium_swap_ix(
-    rpc_client: Arc<RpcClient>,
+    accounts: &AccountLoader,
+    inventory: &Inventory,
     user_owner: &Pubkey,
     pool_id: Pubkey,
     amount_in: u64,
     min_amount_out: u64,
 ) -> Option<solana_sdk::instruction::Instruction> {
-    // 1. Fetch Amm State
-    let data = rpc_client.get_account_data(&pool_id).await.ok()?;
-    let state = AmmState::try_from_slice(&data).ok()?;
-    
-    // 2. Fetch Serum Market
-    let market_id = state.serum_market;
-    let market_data = rpc_client.get_account_data(&market_id).await.ok()?;
-    // 手动解析 Serum Market V3 (跳过 Padding)
-    // Blob(5) + Flags(8) + OwnAddress(32) + VaultSignerNonce(8) + BaseMint(32) + QuoteMint(32) + BaseVault(32) ...
-    // 我们直接用 Borsh 尝试，或者手动取 offset
-    // Offset for EventQueue: 5+8+32+8+32+32+32+8+8+32+8+8+8+32 = 253? 
-    // Let's use the struct we defined
-    // 注意：Serum 头部有 padding，我们去掉前 5 字节
-    if market_data.len() < 5 { return None; }
-    let market_state = SerumMarketV3::try_from_slice(&market_data[5..]).ok();
-    
-    if let Some(market) = market_state {
-         // 3. Derive/Find accounts
-         // User ATA (Need to know which token is In/Out. Assuming WSOL -> Token or Token -> WSOL)
-         // 简化：假设用户已经有对应的 ATA
-         let spl_token_prog = Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).unwrap();
-         
-         let user_source = spl_associated_token_account::get_associated_token_address(user_owner, &state.coin_mint_address);
-         let user_dest = spl_associated_token_account::get_associated_token_address(user_owner, &state.pc_mint_address);
-         // Swap 实际方向需要根据 amount_in 是 coin 还是 pc 来决定，或者 swap 指令里的参数
-         // Raydium swap instruction 9 实际上并不区分 A->B 或 B->A，而是根据 user source/dest 账户来扣款
-         
-         // 4. Build Ix
-         let ix = build_raydium_swap(
-             &Pubkey::from_str("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8").unwrap(), // Raydium V4 Program
-             &pool_id,
-             &state.amm_owner, // Authority? No, Amm Authority is PDA derived from Program
-             &state.amm_open_orders,
-             &state.amm_target_orders,
-             &state.pool_coin_token_account,
-             &state.pool_pc_token_account,
-             &state.serum_program_id,
-             &market_id,
-             &market.bids,
-             &market.asks,
-             &market.event_queue,
-             &market.base_vault,
-             &market.quote_vault,
-             &Pubkey::default(), // Serum Vault Signer (Need to derive?) - Raydium program usually calculates this or we pass it
-             &user_source,
-             &user_dest,
-             user_owner,
-             amount_in,
-             min_amount_out
-         );
-         
-         // Fix Serum Vault Signer
-         // 这里的 serum_vault_signer 必须正确，否则 Serum 程序会报错
-         // 我们可以通过 rpc 拿，或者计算
-         // let vault_signer = crate::amm::serum::get_vault_signer(&market_id, &state.serum_program_id, market.vault_signer_nonce).ok()?;
-         // ix.accounts[14] = AccountMeta::new_readonly(vault_signer, false);
-         
-         return Some(ix);
-    } else {
-        warn!("❌ Failed to parse Serum Market {}", market_id);
-    }
-    
-    None
+    // 1. 优先使用缓存的静态 Key，缺少 Market Keys 时才走 RPC
+    let cached = match inventory.get_pool_keys(&pool_id) {
+        Some(PoolKeys::Raydium(keys)) if keys.market.is_some() => Some(*keys),
+        _ => None,
+    };
+    let keys = match cached {
+        Some(keys) => keys,
+        None => {
+            let keys = fetch_raydium_keys(accounts, &pool_id).await?;
+            inventory.set_pool_keys(PoolKeys::Raydium(Box::new(keys.clone())));
+            keys
+        }
+    };
+
+    let market = match &keys.market {
+        Some(m) => m,
+        None => {
+            warn!("❌ Failed to parse Serum Market {}", keys.market_id);
+            return None;
+        }
+    };
+
+    // 2. User ATA (Need to know which token is In/Out. Assuming WSOL -> Token or Token -> WSOL)
+    // 简化：假设用户已经有对应的 ATA
+    let tokens = inventory.tokens();
+    let user_source = spl_associated_token_account::get_associated_token_address_with_program_id(
+        user_owner, &keys.base_mint, &tokens.token_program(&keys.base_mint));
+    let user_dest = spl_associated_token_account::get_associated_token_address_with_program_id(
+        user_owner, &keys.quote_mint, &tokens.token_program(&keys.quote_mint));
+    // Swap 实际方向需要根据 amount_in 是 coin 还是 pc 来决定，或者 swap 指令里的参数
+    // Raydium swap instruction 9 实际上并不区分 A->B 或 B->A，而是根据 user source/dest 账户来扣款
+
+    // 3. Build Ix
+    Some(build_raydium_swap(
+        &raydium_program_id(),
+        &pool_id,
+        &keys.amm_authority,
+        &keys.open_orders,
+        &keys.target_orders,
+        &keys.base_vault,
+        &keys.quote_vault,
+        &keys.market_program_id,
+        &keys.market_id,
+        &market.bids,
+        &market.asks,
+        &market.event_queue,
+        &market.base_vault,
+        &market.quote_vault,
+        &market.vault_signer,
+        &user_source,
+        &user_dest,
+        user_owner,
+        amount_in,
+        min_amount_out
+    ))
+}
+
+fn raydium_program_id() -> Pubkey {
+    Pubkey::from_str(RAYDIUM_V4_PROGRAM_ID).unwrap()
 }
 
 /// 获取 Raydium 价格 (真实逻辑)
 /// 通过 RPC 获取 Pool Account Data，解析 State，计算 Price
-async fn fetch_raydium_price(rpc_client: Arc<RpcClient>, pool_id: Pubkey) -> Option<f64> {
-    match rpc_client.get_account_data(&pool_id).await {
-        Ok(data) => {
+async fn fetch_raydium_price(accounts: &AccountLoader, inventory: &Inventory, pool_id: Pubkey) -> Option<f64> {
+    match accounts.load(&pool_id).await {
+        Ok(Some(account)) => {
+            let data = account.data;
+            // 对手盘不可交易时不报价
+            inventory.observe_pool_account(&pool_id, &data);
+            if !inventory.is_pool_tradable(&pool_id) {
+                return None;
+            }
+
             // 1. 反序列化 AmmState
             if let Ok(state) = AmmState::try_from_slice(&data) {
                 // 2. 获取精度
@@ -298,6 +416,7 @@ async fn fetch_raydium_price(rpc_client: Arc<RpcClient>, pool_id: Pubkey) -> Opt
                 warn!("❌ Failed to deserialize Raydium AMM State for {}", pool_id);
             }
         },
+        Ok(None) => error!("❌ Raydium Pool Account {} Not Found", pool_id),
         Err(e) => {
             error!("❌ Failed to fetch Raydium Pool Account {}: {}", pool_id, e);
         }
@@ -306,14 +425,46 @@ async fn fetch_raydium_price(rpc_client: Arc<RpcClient>, pool_id: Pubkey) -> Opt
 }
 
 /// 获取 Orca 价格 (真实逻辑)
-async fn fetch_orca_price(rpc_client: Arc<RpcClient>, pool_id: Pubkey) -> Option<f64> {
-    match rpc_client.get_account_data(&pool_id).await {
-        Ok(data) => {
-            if let Some(price_info) = Whirlpool::decode_current_price(&data) {
-                return Some(price_info.price);
+async fn fetch_orca_price(accounts: &AccountLoader, inventory: &Inventory, pool_id: Pubkey, base_mint: &Pubkey) -> Option<f64> {
+    match accounts.load(&pool_id).await {
+        Ok(Some(account)) => {
+            inventory.observe_pool_account(&pool_id, &account.data);
+            if !inventory.is_pool_tradable(&pool_id) {
+                return None;
             }
-        },
+            return orca_ui_price(accounts, inventory, &pool_id, &account.data, base_mint).await;
+        }
+        Ok(None) => error!("❌ Orca Pool Account {} Not Found", pool_id),
         Err(e) => error!("❌ Failed to fetch Orca Pool Account {}: {}", pool_id, e),
     }
     None
 }
+
+/// 解析 Whirlpool 价格并按两边代币精度调整，统一为每个 `base_mint` 值多少 Quote (与 Raydium 报价同向)
+/// 精度未知时通过 TokenRegistry 现场批量拉取
+async fn orca_ui_price(accounts: &AccountLoader, inventory: &Inventory, pool_id: &Pubkey, data: &[u8], base_mint: &Pubkey) -> Option<f64> {
+    let price_info = Whirlpool::decode_current_price(data)?;
+    let keys = Whirlpool::decode_keys(pool_id, data)?;
+    let tokens = inventory.tokens();
+
+    let mints = [keys.token_mint_a, keys.token_mint_b];
+    if mints.iter().any(|m| tokens.decimals(m).is_none()) {
+        tokens.resolve(accounts, &mints).await;
+    }
+    let (decimals_a, decimals_b) = match (tokens.decimals(&keys.token_mint_a), tokens.decimals(&keys.token_mint_b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            warn!("⚠️ 无法获取代币精度，跳过 Orca 定价: {}", pool_id);
+            return None;
+        }
+    };
+    let price = adjust_price_for_decimals(price_info.price, decimals_a, decimals_b);
+    // Whirlpool 价格是 Token B / Token A，Base 在 B 一侧时取倒数
+    if keys.token_mint_a == *base_mint {
+        Some(price)
+    } else if price > 0.0 {
+        Some(1.0 / price)
+    } else {
+        None
+    }
+}
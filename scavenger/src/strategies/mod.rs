@@ -1,20 +1,41 @@
 pub mod arb;
 pub mod sniper;
 
-// use solana_client::nonblocking::rpc_client::RpcClient;
-// use solana_sdk::signature::Keypair;
-// use std::sync::Arc;
-// use crate::config::StrategyConfig;
-// use crate::state::Inventory;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::signature::Keypair;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use crate::bus::EventBus;
+use crate::config::StrategyConfig;
+use crate::core::accounts::AccountLoader;
+use crate::core::blockhash::BlockhashCache;
+use crate::core::latency::LatencyTracker;
+use crate::core::leader::LeaderTracker;
+use crate::state::Inventory;
 
-// Removed async_trait for now as we use static dispatch in monitor.rs
-// #[async_trait::async_trait]
-// pub trait Strategy {
-//     async fn execute(
-//         &self,
-//         rpc_client: Arc<RpcClient>,
-//         keypair: Arc<Keypair>,
-//         config: Arc<StrategyConfig>,
-//         inventory: Arc<Inventory>,
-//     );
-// }
+/// 策略运行所需的共享资源
+#[derive(Clone)]
+pub struct StrategyContext {
+    pub rpc_client: Arc<RpcClient>,
+    pub keypair: Arc<Keypair>,
+    pub config: Arc<StrategyConfig>,
+    pub inventory: Arc<Inventory>,
+    /// Slot / Leader 追踪，决定 Bundle 的提交时机
+    pub leaders: LeaderTracker,
+    /// 后台刷新的 Blockhash
+    pub blockhash: BlockhashCache,
+    /// 池子 / Vault / Mint 账户的批量读取
+    pub accounts: AccountLoader,
+    /// 每个机会的分阶段延迟统计
+    pub latency: LatencyTracker,
+}
+
+/// 按名称启动策略；每个策略在总线上订阅自己关心的事件类型
+/// 必须在侦察层启动前调用，侦察层根据总线上的订阅决定采集哪些数据
+pub fn spawn(name: &str, ctx: StrategyContext, bus: &EventBus) -> Result<JoinHandle<()>, String> {
+    match name {
+        "arb" => Ok(arb::spawn(ctx, bus)),
+        "sniper" => Ok(sniper::spawn(ctx, bus)),
+        other => Err(format!("unknown strategy: {}", other)),
+    }
+}
@@ -1,9 +1,46 @@
-use log::info;
+use log::{info, warn};
 use std::sync::Arc;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::signature::Keypair;
+use tokio::task::JoinHandle;
+use crate::amm::orca_whirlpool::Whirlpool;
+use crate::bus::{BusEvent, Dex, EventBus, EventKind, NewPool};
 use crate::config::StrategyConfig;
 use crate::state::Inventory;
+use crate::strategies::StrategyContext;
+
+/// 订阅新池事件
+pub fn spawn(ctx: StrategyContext, bus: &EventBus) -> JoinHandle<()> {
+    let mut events = bus.subscribe("sniper", &[EventKind::NewPool]);
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if let BusEvent::NewPool(pool) = &*event {
+                let ctx = ctx.clone();
+                let pool = pool.clone();
+                tokio::spawn(async move { on_new_pool(ctx, pool).await });
+            }
+        }
+    })
+}
+
+async fn on_new_pool(ctx: StrategyContext, pool: NewPool) {
+    match pool.dex {
+        Dex::Raydium => execute(ctx.rpc_client, ctx.keypair, ctx.config, ctx.inventory).await,
+        Dex::Orca => {
+            // 尝试获取池子当前价格
+            match ctx.accounts.load(&pool.pool).await {
+                Ok(Some(account)) => {
+                    if let Some(price_info) = Whirlpool::decode_current_price(&account.data) {
+                        info!("💲 [Orca Pricing] Pool: {} | Price: {:.6} | Tick: {} | Liquidity: {}",
+                            pool.pool, price_info.price, price_info.tick, price_info.liquidity);
+                    }
+                }
+                Ok(None) => warn!("⚠️ [Orca Pricing] 池子账户不存在: {}", pool.pool),
+                Err(e) => warn!("⚠️ [Orca Pricing] 获取账户数据失败: {}", e),
+            }
+        }
+    }
+}
 
 pub async fn execute(
     _rpc_client: Arc<RpcClient>,
@@ -0,0 +1,397 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use dashmap::DashMap;
+use log::{info, warn, error};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::state::Mint;
+use tokio::sync::mpsc;
+use crate::core::accounts::AccountLoader;
+
+// Metaplex Token Metadata Program ID
+pub const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// 代币所属的 Token Program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TokenProgram {
+    Spl,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            TokenProgram::Spl => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+
+    pub fn from_owner(owner: &Pubkey) -> Option<Self> {
+        if *owner == spl_token::id() {
+            Some(TokenProgram::Spl)
+        } else if *owner == spl_token_2022::id() {
+            Some(TokenProgram::Token2022)
+        } else {
+            None
+        }
+    }
+}
+
+/// 代币元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenInfo {
+    pub mint: Pubkey,
+    pub decimals: u8,
+    /// 未从链上确认前 (例如只从 AMM 状态里得到了精度) 为 None
+    pub token_program: Option<TokenProgram>,
+    /// Token-2022 扩展 (例如 "TransferFeeConfig")
+    pub extensions: Vec<String>,
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+}
+
+impl TokenInfo {
+    pub fn has_extension(&self, extension: ExtensionType) -> bool {
+        let name = format!("{:?}", extension);
+        self.extensions.contains(&name)
+    }
+}
+
+/// 代币注册表 (与 Inventory 并列的内存索引)
+/// 记录所有接触过的 Mint 的精度、Token Program、扩展以及名称，
+/// 通过批量 RPC 懒加载，供定价、风控和日志使用
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    tokens: Arc<DashMap<Pubkey, TokenInfo>>,
+    // 等待后台批量解析的 Mint
+    pending_tx: Arc<std::sync::Mutex<Option<mpsc::UnboundedSender<Pubkey>>>>,
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self {
+            tokens: Arc::new(DashMap::new()),
+            pending_tx: Arc::new(std::sync::Mutex::new(None)),
+        }
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<TokenInfo> {
+        self.tokens.get(mint).map(|t| t.clone())
+    }
+
+    pub fn decimals(&self, mint: &Pubkey) -> Option<u8> {
+        self.tokens.get(mint).map(|t| t.decimals)
+    }
+
+    /// 代币所属的 Token Program，未知时按 SPL Token 处理
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        self.tokens
+            .get(mint)
+            .and_then(|t| t.token_program)
+            .unwrap_or(TokenProgram::Spl)
+            .id()
+    }
+
+    /// 日志中使用的代币标签: 优先 Symbol，否则使用缩写的 Mint
+    pub fn label(&self, mint: &Pubkey) -> String {
+        match self.tokens.get(mint).and_then(|t| t.symbol.clone()) {
+            Some(symbol) => symbol,
+            None => {
+                let s = mint.to_string();
+                format!("{}..{}", &s[..4], &s[s.len() - 4..])
+            }
+        }
+    }
+
+    /// 是否已从链上确认过 Mint 账户
+    pub fn is_resolved(&self, mint: &Pubkey) -> bool {
+        self.tokens.get(mint).is_some_and(|t| t.token_program.is_some())
+    }
+
+    pub fn insert(&self, info: TokenInfo) {
+        self.tokens.insert(info.mint, info);
+    }
+
+    /// 记录从 AMM 状态等途径得知的精度 (不覆盖已从链上确认的数据)
+    pub fn record_decimals(&self, mint: Pubkey, decimals: u8) {
+        self.tokens.entry(mint).or_insert_with(|| TokenInfo {
+            mint,
+            decimals,
+            token_program: None,
+            extensions: Vec::new(),
+            symbol: None,
+            name: None,
+        });
+        self.request(mint);
+    }
+
+    pub fn all(&self) -> Vec<TokenInfo> {
+        self.tokens.iter().map(|e| e.value().clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// 从本地 Token List 文件加载 Symbol / Name
+    /// 支持标准 Solana Token List 格式 ({"tokens": [...]}) 或直接的数组
+    pub fn load_token_list(&self, path: &str) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let content = std::fs::read_to_string(path)?;
+        let json: Value = serde_json::from_str(&content)?;
+        let entries = json
+            .get("tokens")
+            .and_then(|v| v.as_array())
+            .or_else(|| json.as_array())
+            .ok_or("token list must be an array or contain a 'tokens' array")?;
+
+        let mut count = 0;
+        for entry in entries {
+            let mint = match entry.get("address").and_then(|v| v.as_str()).map(Pubkey::from_str) {
+                Some(Ok(m)) => m,
+                _ => continue,
+            };
+            let symbol = entry.get("symbol").and_then(|v| v.as_str()).map(str::to_string);
+            let name = entry.get("name").and_then(|v| v.as_str()).map(str::to_string);
+            let decimals = entry.get("decimals").and_then(|v| v.as_u64());
+
+            let mut info = self.tokens.entry(mint).or_insert_with(|| TokenInfo {
+                mint,
+                decimals: decimals.unwrap_or_default() as u8,
+                token_program: None,
+                extensions: Vec::new(),
+                symbol: None,
+                name: None,
+            });
+            info.symbol = info.symbol.take().or(symbol);
+            info.name = info.name.take().or(name);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// 非阻塞地登记一个待解析的 Mint，由后台任务批量拉取
+    pub fn request(&self, mint: Pubkey) {
+        if self.is_resolved(&mint) {
+            return;
+        }
+        if let Some(tx) = self.pending_tx.lock().unwrap().as_ref() {
+            let _ = tx.send(mint);
+        }
+    }
+
+    /// 启动后台解析任务: 收集一个窗口内的请求后一次性批量拉取
+    pub fn spawn_resolver(&self, accounts: AccountLoader, window: Duration) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Pubkey>();
+        *self.pending_tx.lock().unwrap() = Some(tx);
+
+        let registry = self.clone();
+        tokio::spawn(async move {
+            while let Some(first) = rx.recv().await {
+                let mut batch = HashSet::from([first]);
+                tokio::time::sleep(window).await;
+                while let Ok(mint) = rx.try_recv() {
+                    batch.insert(mint);
+                }
+                let mints: Vec<Pubkey> = batch.into_iter().collect();
+                registry.resolve(&accounts, &mints).await;
+            }
+        });
+    }
+
+    /// 批量解析 Mint 账户和 Metaplex 元数据 (已确认的 Mint 会被跳过)
+    pub async fn resolve(&self, accounts: &AccountLoader, mints: &[Pubkey]) {
+        let unresolved: Vec<Pubkey> = mints
+            .iter()
+            .filter(|m| !self.is_resolved(m))
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if unresolved.is_empty() {
+            return;
+        }
+
+        // 每个 Mint 需要两个账户: Mint 本身 + Metadata PDA，分批与按 Key 对应交给 AccountLoader
+        let keys: Vec<(Pubkey, Pubkey)> = unresolved.iter().map(|mint| (*mint, metadata_pda(mint))).collect();
+        let flat: Vec<Pubkey> = keys.iter().flat_map(|(mint, pda)| [*mint, *pda]).collect();
+        let snapshot = match accounts.snapshot(&flat).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                error!("❌ 批量获取 Mint 账户失败: {}", e);
+                return;
+            }
+        };
+
+        for (mint, pda) in &keys {
+            let mint_account = match snapshot.get(mint) {
+                Some(a) => a,
+                None => {
+                    warn!("⚠️ Mint 账户不存在: {}", mint);
+                    continue;
+                }
+            };
+            let mut info = match decode_mint(mint, &mint_account.owner, &mint_account.data) {
+                Some(info) => info,
+                None => continue,
+            };
+            if let Some((name, symbol)) = snapshot.get(pda).and_then(|a| decode_metadata(&a.data)) {
+                info.name = Some(name);
+                info.symbol = Some(symbol);
+            }
+            // 保留 Token List 中的名称
+            if let Some(prev) = self.tokens.get(mint) {
+                info.symbol = info.symbol.or(prev.symbol.clone());
+                info.name = info.name.or(prev.name.clone());
+            }
+            self.tokens.insert(*mint, info);
+        }
+        info!("🏷️ 已解析 {} 个代币元数据 (注册表共 {} 个)", unresolved.len(), self.tokens.len());
+    }
+}
+
+/// Metaplex Metadata PDA: ["metadata", program_id, mint]
+pub fn metadata_pda(mint: &Pubkey) -> Pubkey {
+    let program_id = Pubkey::from_str(METADATA_PROGRAM_ID).unwrap();
+    Pubkey::find_program_address(&[b"metadata", program_id.as_ref(), mint.as_ref()], &program_id).0
+}
+
+/// 解析 Mint 账户 (兼容 SPL Token 与 Token-2022)
+pub fn decode_mint(mint: &Pubkey, owner: &Pubkey, data: &[u8]) -> Option<TokenInfo> {
+    decode_mint_state(mint, owner, data).map(|(info, _)| info)
+}
+
+/// 同 `decode_mint`，同时返回 Mint 基础状态 (Authority / Supply)，调用方不必再解析一次
+pub fn decode_mint_state(mint: &Pubkey, owner: &Pubkey, data: &[u8]) -> Option<(TokenInfo, Mint)> {
+    let token_program = TokenProgram::from_owner(owner)?;
+    let state = StateWithExtensions::<Mint>::unpack(data).ok()?;
+    let extensions = state
+        .get_extension_types()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|e| format!("{:?}", e))
+        .collect();
+
+    let info = TokenInfo {
+        mint: *mint,
+        decimals: state.base.decimals,
+        token_program: Some(token_program),
+        extensions,
+        symbol: None,
+        name: None,
+    };
+    Some((info, state.base))
+}
+
+/// 解析 Metaplex Metadata 账户中的 (name, symbol)
+/// 布局: key(1) + update_authority(32) + mint(32) + name(String) + symbol(String) + ...
+pub fn decode_metadata(data: &[u8]) -> Option<(String, String)> {
+    let mut offset = 1 + 32 + 32;
+    let mut read_string = || -> Option<String> {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let bytes = data.get(offset + 4..offset + 4 + len)?;
+        offset += 4 + len;
+        // 链上字符串用 \0 填充到固定长度
+        Some(String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string())
+    };
+    let name = read_string()?;
+    let symbol = read_string()?;
+    Some((name, symbol))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_client::rpc_request::RpcRequest;
+    use solana_client::rpc_response::{Response, RpcResponseContext};
+    use solana_sdk::account::Account;
+    use solana_sdk::program_option::COption;
+    use solana_sdk::program_pack::Pack;
+    use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+    use spl_token_2022::extension::StateWithExtensionsMut;
+    use crate::config::AccountLoaderConfig;
+    use solana_sdk::commitment_config::CommitmentConfig;
+
+    fn spl_mint(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0u8; Mint::LEN];
+        Mint { decimals, is_initialized: true, supply: 1_000, ..Default::default() }.pack_into_slice(&mut data);
+        data
+    }
+
+    fn token_2022_mint_with_fee(decimals: u8) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0u8; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<TransferFeeConfig>(true).unwrap();
+        state.base = Mint { decimals, is_initialized: true, freeze_authority: COption::None, ..Default::default() };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_decode_mint_decimals_and_extensions() {
+        let mint = Pubkey::new_unique();
+        let info = decode_mint(&mint, &spl_token::id(), &spl_mint(6)).unwrap();
+        assert_eq!((info.decimals, info.token_program), (6, Some(TokenProgram::Spl)));
+        assert!(info.extensions.is_empty());
+
+        let (info, base) = decode_mint_state(&mint, &spl_token_2022::id(), &token_2022_mint_with_fee(9)).unwrap();
+        assert_eq!((info.decimals, base.decimals), (9, 9));
+        assert_eq!(info.token_program, Some(TokenProgram::Token2022));
+        assert!(info.has_extension(ExtensionType::TransferFeeConfig));
+        assert!(!info.has_extension(ExtensionType::TransferHook));
+
+        // Owner 不是 Token Program 的账户不是 Mint
+        assert!(decode_mint(&mint, &Pubkey::new_unique(), &spl_mint(6)).is_none());
+    }
+
+    fn loader(rpc_client: RpcClient) -> AccountLoader {
+        AccountLoader::new(Arc::new(rpc_client), &AccountLoaderConfig::default(), CommitmentConfig::confirmed())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_cache_hit_and_miss() {
+        let registry = TokenRegistry::new();
+        let (cached, fresh) = (Pubkey::new_unique(), Pubkey::new_unique());
+        registry.insert(decode_mint(&cached, &spl_token::id(), &spl_mint(6)).unwrap());
+        // 只从 AMM 状态得知精度的 Mint 仍需要解析
+        registry.record_decimals(fresh, 5);
+        assert!(registry.is_resolved(&cached));
+        assert!(!registry.is_resolved(&fresh));
+        assert_eq!(registry.decimals(&fresh), Some(5));
+
+        // 已解析的 Mint 不发 RPC (这个客户端的所有请求都会失败)
+        let failing = loader(RpcClient::new_mock("fails".to_string()));
+        registry.resolve(&failing, &[cached]).await;
+        assert!(registry.is_resolved(&cached));
+
+        let account = Account { lamports: 1, data: token_2022_mint_with_fee(8), owner: spl_token_2022::id(), ..Default::default() };
+        let response = Response {
+            context: RpcResponseContext { slot: 1, api_version: None },
+            value: vec![Some(UiAccount::encode(&fresh, &account, UiAccountEncoding::Base64, None, None)), None],
+        };
+        let mocks = std::collections::HashMap::from([(RpcRequest::GetMultipleAccounts, serde_json::to_value(response).unwrap())]);
+        let accounts = loader(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks));
+        registry.resolve(&accounts, &[cached, fresh]).await;
+        assert!(registry.is_resolved(&fresh));
+        assert_eq!(registry.decimals(&fresh), Some(8));
+        assert_eq!(registry.token_program(&fresh), spl_token_2022::id());
+        assert_eq!(registry.get(&fresh).unwrap().extensions, vec!["TransferFeeConfig".to_string()]);
+    }
+}